@@ -0,0 +1,167 @@
+use std::fmt;
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use crate::device_struct::{EmulatedDevice, UserAgentBrandVersion, UserAgentMetadata};
+
+// Placeholder DevTools uses for the browser version in EmulatedDevices.ts
+const VERSION_PLACEHOLDER: &str = "%s";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ChromeVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub build: u32,
+    pub patch: u32,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("Invalid Chrome version \"{0}\", expected MAJOR[.MINOR.BUILD.PATCH]")]
+pub struct ParseChromeVersionError(String);
+
+/// How the version appears in the `User-Agent` string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UaMode {
+    /// `Chrome/120.0.6099.109`
+    Full,
+    /// `Chrome/120.0.0.0`, what Chrome sends since User-Agent Reduction.
+    #[default]
+    Reduced,
+}
+
+impl ChromeVersion {
+    pub const fn new(major: u32, minor: u32, build: u32, patch: u32) -> Self {
+        Self { major, minor, build, patch }
+    }
+
+    /// `major.0.0.0`
+    pub const fn reduced(self) -> Self {
+        Self::new(self.major, 0, 0, 0)
+    }
+
+    pub fn for_mode(self, mode: UaMode) -> Self {
+        match mode {
+            UaMode::Full => self,
+            UaMode::Reduced => self.reduced(),
+        }
+    }
+
+    /// `Sec-CH-UA` brands: GREASE, Chromium and Google Chrome at their major
+    /// version, permuted like `GenerateBrandVersionList` does.
+    pub fn brands(self) -> Vec<UserAgentBrandVersion> {
+        let (grease_brand, grease_version) = self.grease();
+        self.permute(grease_brand, grease_version.to_string(), self.major.to_string())
+    }
+
+    /// `Sec-CH-UA-Full-Version-List`: same brands and order as `brands`, with
+    /// full versions.
+    pub fn full_version_list(self) -> Vec<UserAgentBrandVersion> {
+        let (grease_brand, grease_version) = self.grease();
+        self.permute(grease_brand, format!("{grease_version}.0.0.0"), self.to_string())
+    }
+
+    // GetGreasedUserAgentBrandVersion, seeded with the major version.
+    fn grease(self) -> (String, &'static str) {
+        const GREASE_CHARS: [char; 11] = [' ', '(', ':', '-', '.', '/', ')', ';', '=', '?', '_'];
+        const GREASE_VERSIONS: [&str; 3] = ["8", "99", "24"];
+        let seed = self.major as usize;
+        let brand = format!(
+            "Not{}A{}Brand",
+            GREASE_CHARS[seed % GREASE_CHARS.len()],
+            GREASE_CHARS[(seed + 1) % GREASE_CHARS.len()]
+        );
+        (brand, GREASE_VERSIONS[seed % GREASE_VERSIONS.len()])
+    }
+
+    fn permute(
+        self,
+        grease_brand: String,
+        grease_version: String,
+        version: String,
+    ) -> Vec<UserAgentBrandVersion> {
+        const ORDERS: [[usize; 3]; 6] =
+            [[0, 1, 2], [0, 2, 1], [1, 0, 2], [1, 2, 0], [2, 0, 1], [2, 1, 0]];
+        let order = ORDERS[self.major as usize % ORDERS.len()];
+        let entries = [
+            (grease_brand, grease_version),
+            ("Chromium".to_string(), version.clone()),
+            ("Google Chrome".to_string(), version),
+        ];
+        let mut slots: [Option<UserAgentBrandVersion>; 3] = Default::default();
+        for (position, (brand, version)) in order.into_iter().zip(entries) {
+            slots[position] = Some(UserAgentBrandVersion { brand, version });
+        }
+        slots.into_iter().flatten().collect()
+    }
+}
+
+impl fmt::Display for ChromeVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}.{}", self.major, self.minor, self.build, self.patch)
+    }
+}
+
+impl FromStr for ChromeVersion {
+    type Err = ParseChromeVersionError;
+
+    /// Accepts `120` or `120.0.6099.109`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseChromeVersionError(s.to_string());
+        let parts = s
+            .trim()
+            .split('.')
+            .map(|p| p.parse::<u32>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| err())?;
+        match parts[..] {
+            [major] => Ok(Self::new(major, 0, 0, 0)),
+            [major, minor, build, patch] => Ok(Self::new(major, minor, build, patch)),
+            _ => Err(err()),
+        }
+    }
+}
+
+impl EmulatedDevice {
+    /// The device's `User-Agent` for `version`.
+    ///
+    /// Fills the `%s` template DevTools uses, or rewrites a version frozen into
+    /// the string (`devices.json` carries `Chrome/120.0.0.0`). Safari UAs have
+    /// neither and are returned unchanged.
+    pub fn user_agent_for(&self, version: ChromeVersion, mode: UaMode) -> String {
+        render_user_agent(&self.user_agent, version.for_mode(mode))
+    }
+
+    /// Copy of the device with the UA rendered for `version` and the metadata's
+    /// `brands`/`fullVersionList` filled in, as `emulation_headers` sends it.
+    pub fn with_chrome_version(&self, version: ChromeVersion, mode: UaMode) -> EmulatedDevice {
+        let mut device = self.clone();
+        device.user_agent = self.user_agent_for(version, mode);
+        if let Some(metadata) = device.user_agent_metadata.as_mut() {
+            metadata.set_chrome_version(version);
+        }
+        device
+    }
+}
+
+impl UserAgentMetadata {
+    /// Client hints always carry the full version list, even in reduced mode.
+    pub fn set_chrome_version(&mut self, version: ChromeVersion) {
+        self.brands = version.brands();
+        self.full_version_list = version.full_version_list();
+    }
+}
+
+fn render_user_agent(template: &str, version: ChromeVersion) -> String {
+    let version = version.to_string();
+    if template.contains(VERSION_PLACEHOLDER) {
+        return template.replace(VERSION_PLACEHOLDER, &version);
+    }
+    let Some(start) = template.find("Chrome/").map(|i| i + "Chrome/".len()) else {
+        return template.to_string();
+    };
+    let end = template[start..]
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .map_or(template.len(), |i| start + i);
+    format!("{}{}{}", &template[..start], version, &template[end..])
+}
@@ -0,0 +1,431 @@
+use std::collections::{BTreeSet, HashMap};
+
+use http::header::{HeaderMap, HeaderName, HeaderValue, InvalidHeaderValue};
+use url::{Origin, Url};
+
+use crate::device_headers::{
+    serialize_brand_list, sf_boolean, sf_string, SEC_CH_UA, SEC_CH_UA_ARCH,
+    SEC_CH_UA_FULL_VERSION_LIST, SEC_CH_UA_MOBILE, SEC_CH_UA_MODEL, SEC_CH_UA_PLATFORM,
+    SEC_CH_UA_PLATFORM_VERSION,
+};
+use crate::device_struct::UserAgentMetadata;
+
+pub const ACCEPT_CH: HeaderName = HeaderName::from_static("accept-ch");
+pub const CRITICAL_CH: HeaderName = HeaderName::from_static("critical-ch");
+pub const DELEGATE_CH: HeaderName = HeaderName::from_static("delegate-ch");
+
+/// UA client hints we know how to serialize. Variant order is the order Chrome
+/// writes them on the wire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ClientHint {
+    Ua,
+    UaMobile,
+    UaPlatform,
+    UaArch,
+    UaFullVersionList,
+    UaModel,
+    UaPlatformVersion,
+}
+
+impl ClientHint {
+    pub const ALL: [ClientHint; 7] = [
+        ClientHint::Ua,
+        ClientHint::UaMobile,
+        ClientHint::UaPlatform,
+        ClientHint::UaArch,
+        ClientHint::UaFullVersionList,
+        ClientHint::UaModel,
+        ClientHint::UaPlatformVersion,
+    ];
+
+    pub fn header_name(self) -> HeaderName {
+        match self {
+            ClientHint::Ua => SEC_CH_UA,
+            ClientHint::UaMobile => SEC_CH_UA_MOBILE,
+            ClientHint::UaPlatform => SEC_CH_UA_PLATFORM,
+            ClientHint::UaArch => SEC_CH_UA_ARCH,
+            ClientHint::UaFullVersionList => SEC_CH_UA_FULL_VERSION_LIST,
+            ClientHint::UaModel => SEC_CH_UA_MODEL,
+            ClientHint::UaPlatformVersion => SEC_CH_UA_PLATFORM_VERSION,
+        }
+    }
+
+    /// Parses a token from `Accept-CH`/`Critical-CH`/`delegate-ch`.
+    /// Hints we do not implement are ignored, like Chromium ignores unknown ones.
+    pub fn from_token(token: &str) -> Option<ClientHint> {
+        let token = token.trim();
+        ClientHint::ALL
+            .into_iter()
+            .find(|hint| hint.header_name().as_str().eq_ignore_ascii_case(token))
+    }
+
+    /// Low-entropy hints go out on every request without an opt-in.
+    pub fn is_low_entropy(self) -> bool {
+        matches!(self, ClientHint::Ua | ClientHint::UaMobile | ClientHint::UaPlatform)
+    }
+
+    /// The header value for `metadata`, or `None` for a brand list that is
+    /// empty (an empty SF list is not a valid `Sec-CH-UA`), matching
+    /// `append_client_hints`.
+    fn value(self, metadata: &UserAgentMetadata) -> Option<String> {
+        let value = match self {
+            ClientHint::Ua if metadata.brands.is_empty() => return None,
+            ClientHint::Ua => serialize_brand_list(&metadata.brands),
+            ClientHint::UaMobile => sf_boolean(metadata.mobile).to_string(),
+            ClientHint::UaPlatform => sf_string(&metadata.platform),
+            ClientHint::UaArch => sf_string(&metadata.architecture),
+            ClientHint::UaFullVersionList if metadata.full_version_list.is_empty() => return None,
+            ClientHint::UaFullVersionList => serialize_brand_list(&metadata.full_version_list),
+            ClientHint::UaModel => sf_string(&metadata.model),
+            ClientHint::UaPlatformVersion => sf_string(&metadata.platform_version),
+        };
+        Some(value)
+    }
+}
+
+/// Parses an `Accept-CH`/`Critical-CH` value: a comma separated list of hint
+/// tokens. Unknown tokens are dropped.
+pub fn parse_hint_list(value: &str) -> BTreeSet<ClientHint> {
+    value.split(',').filter_map(ClientHint::from_token).collect()
+}
+
+/// What the caller should do with a navigation response after
+/// `ClientHintsStore::process_response`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CriticalChAction {
+    Proceed,
+    /// `Critical-CH` named an accepted hint we did not send: restart the
+    /// request once with the updated hints.
+    Retry,
+}
+
+/// Per-origin client hint opt-ins, the equivalent of Chromium's
+/// `ClientHintsControllerDelegate` storage.
+#[derive(Debug, Clone, Default)]
+pub struct ClientHintsStore {
+    accepted: HashMap<Origin, BTreeSet<ClientHint>>,
+    // top-level origin -> hint -> third-party origins it is delegated to
+    delegated: HashMap<Origin, HashMap<ClientHint, Vec<Origin>>>,
+}
+
+impl ClientHintsStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// High-entropy hints `origin` has opted into.
+    pub fn accepted(&self, origin: &Origin) -> Option<&BTreeSet<ClientHint>> {
+        self.accepted.get(origin)
+    }
+
+    pub fn clear(&mut self) {
+        self.accepted.clear();
+        self.delegated.clear();
+    }
+
+    /// Hints to attach to a request for `url` made from the page at `top_level`.
+    ///
+    /// Low-entropy hints are always included. High-entropy hints need an
+    /// `Accept-CH` opt-in from the top-level origin, and for third-party
+    /// requests also a `delegate-ch` grant for the request's origin.
+    pub fn hints_for(&self, top_level: &Url, url: &Url) -> BTreeSet<ClientHint> {
+        let mut hints: BTreeSet<ClientHint> =
+            ClientHint::ALL.into_iter().filter(|h| h.is_low_entropy()).collect();
+
+        let top_origin = top_level.origin();
+        let request_origin = url.origin();
+        if !is_secure(url) {
+            return hints;
+        }
+        let Some(accepted) = self.accepted.get(&top_origin) else {
+            return hints;
+        };
+        for &hint in accepted {
+            let allowed = top_origin == request_origin
+                || self
+                    .delegated
+                    .get(&top_origin)
+                    .and_then(|d| d.get(&hint))
+                    .is_some_and(|origins| origins.contains(&request_origin));
+            if allowed {
+                hints.insert(hint);
+            }
+        }
+        hints
+    }
+
+    /// Adds the headers for `hints_for(top_level, url)` to `headers` and
+    /// returns the set that was sent, for `process_response`. Brand-list hints
+    /// are skipped, and left out of the returned set, when `metadata` has no
+    /// brands for them.
+    pub fn apply(
+        &self,
+        top_level: &Url,
+        url: &Url,
+        metadata: &UserAgentMetadata,
+        headers: &mut HeaderMap,
+    ) -> Result<BTreeSet<ClientHint>, InvalidHeaderValue> {
+        let mut sent = BTreeSet::new();
+        for hint in self.hints_for(top_level, url) {
+            let Some(value) = hint.value(metadata) else {
+                continue;
+            };
+            headers.insert(hint.header_name(), HeaderValue::from_str(&value)?);
+            sent.insert(hint);
+        }
+        Ok(sent)
+    }
+
+    /// Records the `Accept-CH` and `Delegate-CH` of a top-level navigation
+    /// response and checks its `Critical-CH`.
+    ///
+    /// As in Chromium, a present `Accept-CH` replaces the origin's previous
+    /// opt-ins (an empty one clears them), and only secure origins may opt in.
+    /// `Retry` is returned at most once per navigation: pass `retried = true`
+    /// for the restarted request.
+    pub fn process_response(
+        &mut self,
+        url: &Url,
+        response_headers: &HeaderMap,
+        sent: &BTreeSet<ClientHint>,
+        retried: bool,
+    ) -> CriticalChAction {
+        if !is_secure(url) {
+            return CriticalChAction::Proceed;
+        }
+        let origin = url.origin();
+
+        if response_headers.contains_key(ACCEPT_CH) {
+            let accepted: BTreeSet<ClientHint> = header_values(response_headers, &ACCEPT_CH)
+                .flat_map(parse_hint_list)
+                .filter(|h| !h.is_low_entropy())
+                .collect();
+            if accepted.is_empty() {
+                self.accepted.remove(&origin);
+            } else {
+                self.accepted.insert(origin.clone(), accepted);
+            }
+        }
+        let delegations: Vec<String> =
+            header_values(response_headers, &DELEGATE_CH).map(str::to_owned).collect();
+        for content in delegations {
+            self.process_delegate_ch(url, &content);
+        }
+
+        if retried {
+            return CriticalChAction::Proceed;
+        }
+        let Some(accepted) = self.accepted.get(&origin) else {
+            return CriticalChAction::Proceed;
+        };
+        let missing_critical = header_values(response_headers, &CRITICAL_CH)
+            .flat_map(parse_hint_list)
+            .any(|h| accepted.contains(&h) && !sent.contains(&h));
+        if missing_critical {
+            CriticalChAction::Retry
+        } else {
+            CriticalChAction::Proceed
+        }
+    }
+
+    /// Records a `<meta name="delegate-ch" content="...">` (or `Delegate-CH`
+    /// header) from the page at `top_level`, e.g.
+    /// `sec-ch-ua-model https://cdn.example; sec-ch-ua-arch`. Each directive
+    /// replaces the previous grant for that hint.
+    pub fn process_delegate_ch(&mut self, top_level: &Url, content: &str) {
+        if !is_secure(top_level) {
+            return;
+        }
+        let grants = self.delegated.entry(top_level.origin()).or_default();
+        for directive in content.split(';') {
+            let mut parts = directive.split_whitespace();
+            let Some(hint) = parts.next().and_then(ClientHint::from_token) else {
+                continue;
+            };
+            let origins = parts
+                .filter_map(|o| Url::parse(o).ok())
+                .map(|u| u.origin())
+                .filter(|o| o.is_tuple())
+                .collect();
+            grants.insert(hint, origins);
+        }
+    }
+}
+
+fn header_values<'a>(headers: &'a HeaderMap, name: &HeaderName) -> impl Iterator<Item = &'a str> {
+    headers.get_all(name).iter().filter_map(|v| v.to_str().ok())
+}
+
+// Chromium only honours client hint opt-ins in secure contexts.
+fn is_secure(url: &Url) -> bool {
+    match url.scheme() {
+        "https" | "wss" => true,
+        "http" | "ws" => matches!(url.host_str(), Some("localhost" | "127.0.0.1" | "[::1]")),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device_struct::UserAgentBrandVersion;
+
+    fn url(s: &str) -> Url {
+        Url::parse(s).unwrap()
+    }
+
+    fn response(headers: &[(HeaderName, &str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in headers {
+            map.append(name.clone(), HeaderValue::from_str(value).unwrap());
+        }
+        map
+    }
+
+    fn metadata() -> UserAgentMetadata {
+        let brand = |version: &str| UserAgentBrandVersion {
+            brand: "Chromium".into(),
+            version: version.into(),
+        };
+        UserAgentMetadata {
+            brands: vec![brand("120")],
+            full_version_list: vec![brand("120.0.6099.109")],
+            platform: "Android".into(),
+            platform_version: "9".into(),
+            architecture: "".into(),
+            model: "Pixel 3".into(),
+            mobile: true,
+        }
+    }
+
+    fn low_entropy() -> BTreeSet<ClientHint> {
+        BTreeSet::from([ClientHint::Ua, ClientHint::UaMobile, ClientHint::UaPlatform])
+    }
+
+    #[test]
+    fn accept_ch_replaces_and_empty_value_clears() {
+        let site = url("https://example.com/");
+        let mut store = ClientHintsStore::new();
+
+        let headers = response(&[(ACCEPT_CH, "Sec-CH-UA-Model, sec-ch-ua-arch, Sec-CH-Bogus")]);
+        store.process_response(&site, &headers, &low_entropy(), false);
+        assert_eq!(
+            store.accepted(&site.origin()),
+            Some(&BTreeSet::from([ClientHint::UaArch, ClientHint::UaModel]))
+        );
+
+        let headers = response(&[(ACCEPT_CH, "Sec-CH-UA-Platform-Version")]);
+        store.process_response(&site, &headers, &low_entropy(), false);
+        assert_eq!(
+            store.accepted(&site.origin()),
+            Some(&BTreeSet::from([ClientHint::UaPlatformVersion]))
+        );
+
+        // No Accept-CH at all keeps the opt-ins; an empty one clears them.
+        store.process_response(&site, &HeaderMap::new(), &low_entropy(), false);
+        assert!(store.accepted(&site.origin()).is_some());
+        store.process_response(&site, &response(&[(ACCEPT_CH, "")]), &low_entropy(), false);
+        assert_eq!(store.accepted(&site.origin()), None);
+    }
+
+    #[test]
+    fn opt_in_requires_secure_origin() {
+        let mut store = ClientHintsStore::new();
+        let headers = response(&[(ACCEPT_CH, "Sec-CH-UA-Model")]);
+
+        let insecure = url("http://example.com/");
+        store.process_response(&insecure, &headers, &low_entropy(), false);
+        assert_eq!(store.accepted(&insecure.origin()), None);
+
+        let localhost = url("http://localhost:8080/");
+        store.process_response(&localhost, &headers, &low_entropy(), false);
+        assert!(store.accepted(&localhost.origin()).is_some());
+
+        // High-entropy hints are not sent over an insecure request even from
+        // an opted-in page.
+        let page = url("https://example.com/");
+        store.process_response(&page, &headers, &low_entropy(), false);
+        assert_eq!(store.hints_for(&page, &url("http://example.com/img.png")), low_entropy());
+        assert!(store.hints_for(&page, &page).contains(&ClientHint::UaModel));
+    }
+
+    #[test]
+    fn delegate_ch_grants_third_party_hints() {
+        let page = url("https://example.com/");
+        let cdn = url("https://cdn.example.net/lib.js");
+        let other = url("https://other.example.org/lib.js");
+        let mut store = ClientHintsStore::new();
+
+        let headers = response(&[
+            (ACCEPT_CH, "Sec-CH-UA-Model, Sec-CH-UA-Arch"),
+            (DELEGATE_CH, "sec-ch-ua-model https://cdn.example.net; sec-ch-ua-arch"),
+        ]);
+        store.process_response(&page, &headers, &low_entropy(), false);
+
+        let cdn_hints = store.hints_for(&page, &cdn);
+        assert!(cdn_hints.contains(&ClientHint::UaModel));
+        assert!(!cdn_hints.contains(&ClientHint::UaArch));
+        assert_eq!(store.hints_for(&page, &other), low_entropy());
+
+        // A later directive for the same hint replaces the grant.
+        store.process_delegate_ch(&page, "sec-ch-ua-model https://other.example.org");
+        assert!(!store.hints_for(&page, &cdn).contains(&ClientHint::UaModel));
+        assert!(store.hints_for(&page, &other).contains(&ClientHint::UaModel));
+    }
+
+    #[test]
+    fn critical_ch_retries_once() {
+        let site = url("https://example.com/");
+        let mut store = ClientHintsStore::new();
+        let headers = response(&[(ACCEPT_CH, "Sec-CH-UA-Model"), (CRITICAL_CH, "Sec-CH-UA-Model")]);
+
+        let sent = store.apply(&site, &site, &metadata(), &mut HeaderMap::new()).unwrap();
+        assert_eq!(sent, low_entropy());
+        assert_eq!(store.process_response(&site, &headers, &sent, false), CriticalChAction::Retry);
+
+        // The restarted request carries the hint; even if it had not, a
+        // second Retry is never returned.
+        let mut request = HeaderMap::new();
+        let sent = store.apply(&site, &site, &metadata(), &mut request).unwrap();
+        assert!(sent.contains(&ClientHint::UaModel));
+        assert_eq!(request.get(SEC_CH_UA_MODEL).unwrap(), r#""Pixel 3""#);
+        assert_eq!(
+            store.process_response(&site, &headers, &low_entropy(), true),
+            CriticalChAction::Proceed
+        );
+        assert_eq!(
+            store.process_response(&site, &headers, &sent, false),
+            CriticalChAction::Proceed
+        );
+    }
+
+    #[test]
+    fn critical_ch_without_accept_ch_does_not_retry() {
+        let site = url("https://example.com/");
+        let mut store = ClientHintsStore::new();
+        let headers = response(&[(CRITICAL_CH, "Sec-CH-UA-Model")]);
+        assert_eq!(
+            store.process_response(&site, &headers, &low_entropy(), false),
+            CriticalChAction::Proceed
+        );
+    }
+
+    #[test]
+    fn empty_brand_lists_are_not_sent() {
+        let site = url("https://example.com/");
+        let mut store = ClientHintsStore::new();
+        let headers = response(&[(ACCEPT_CH, "Sec-CH-UA-Full-Version-List")]);
+        store.process_response(&site, &headers, &low_entropy(), false);
+
+        let mut metadata = metadata();
+        metadata.brands.clear();
+        metadata.full_version_list.clear();
+        let mut request = HeaderMap::new();
+        let sent = store.apply(&site, &site, &metadata, &mut request).unwrap();
+
+        assert_eq!(sent, BTreeSet::from([ClientHint::UaMobile, ClientHint::UaPlatform]));
+        assert!(!request.contains_key(SEC_CH_UA));
+        assert!(!request.contains_key(SEC_CH_UA_FULL_VERSION_LIST));
+        assert_eq!(request.get(SEC_CH_UA_MOBILE).unwrap(), "?1");
+    }
+}
@@ -0,0 +1,325 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::device_struct::EmulatedDevice;
+
+// Diffs between two device lists, e.g. the EmulatedDevices.ts of two Chromium
+// revisions, or devices.json against all_devices.json.
+//
+// Devices are matched on (title, order). Titles alone are not unique
+// (`iPad Pro` is listed twice with different orders), and entries with the
+// same key are paired in source order. A device whose order changed shows up
+// as one removal plus one addition.
+
+/// Snapshot format written by this version.
+pub const SNAPSHOT_FORMAT: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DeviceKey {
+    pub title: String,
+    pub order: Option<i32>,
+}
+
+impl DeviceKey {
+    pub fn of(device: &EmulatedDevice) -> Self {
+        Self { title: device.title.clone(), order: device.order }
+    }
+}
+
+impl fmt::Display for DeviceKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.order {
+            Some(order) => write!(f, "\"{}\" (order {order})", self.title),
+            None => write!(f, "\"{}\"", self.title),
+        }
+    }
+}
+
+/// One changed value, addressed by its dotted JSON path
+/// (`screen.vertical.width`, `user-agent-metadata.platform`). A side is
+/// `None` when the field is absent there.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldChange {
+    pub path: String,
+    pub old: Option<Value>,
+    pub new: Option<Value>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceChange {
+    pub key: DeviceKey,
+    pub fields: Vec<FieldChange>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DeviceDiff {
+    pub added: Vec<EmulatedDevice>,
+    pub removed: Vec<EmulatedDevice>,
+    pub changed: Vec<DeviceChange>,
+}
+
+impl DeviceDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl fmt::Display for DeviceDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for device in &self.removed {
+            writeln!(f, "- {}", DeviceKey::of(device))?;
+        }
+        for device in &self.added {
+            writeln!(f, "+ {}", DeviceKey::of(device))?;
+        }
+        for change in &self.changed {
+            writeln!(f, "~ {}", change.key)?;
+            for field in &change.fields {
+                let show =
+                    |v: &Option<Value>| v.as_ref().map_or("(absent)".to_string(), Value::to_string);
+                writeln!(f, "    {}: {} -> {}", field.path, show(&field.old), show(&field.new))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Compares `old` against `new`. Added and removed devices keep the order of
+/// their list; changes follow the order of `old`.
+pub fn diff_devices(old: &[EmulatedDevice], new: &[EmulatedDevice]) -> DeviceDiff {
+    let mut unmatched: HashMap<DeviceKey, Vec<usize>> = HashMap::new();
+    for (index, device) in new.iter().enumerate().rev() {
+        unmatched.entry(DeviceKey::of(device)).or_default().push(index);
+    }
+
+    let mut diff = DeviceDiff::default();
+    let mut matched = vec![false; new.len()];
+    for device in old {
+        let key = DeviceKey::of(device);
+        let Some(index) = unmatched.get_mut(&key).and_then(Vec::pop) else {
+            diff.removed.push(device.clone());
+            continue;
+        };
+        matched[index] = true;
+        let fields = diff_fields(device, &new[index]);
+        if !fields.is_empty() {
+            diff.changed.push(DeviceChange { key, fields });
+        }
+    }
+    diff.added = new
+        .iter()
+        .zip(&matched)
+        .filter(|(_, matched)| !**matched)
+        .map(|(device, _)| device.clone())
+        .collect();
+    diff
+}
+
+/// Field-level changes between two versions of one device. Objects are
+/// compared key by key; arrays (brands, capabilities, modes) as a whole.
+pub fn diff_fields(old: &EmulatedDevice, new: &EmulatedDevice) -> Vec<FieldChange> {
+    let old = serde_json::to_value(old).expect("EmulatedDevice serializes to JSON");
+    let new = serde_json::to_value(new).expect("EmulatedDevice serializes to JSON");
+    let mut out = Vec::new();
+    diff_values(String::new(), Some(&old), Some(&new), &mut out);
+    out
+}
+
+fn diff_values(path: String, old: Option<&Value>, new: Option<&Value>, out: &mut Vec<FieldChange>) {
+    match (old, new) {
+        (Some(Value::Object(a)), Some(Value::Object(b))) => {
+            let mut keys: Vec<&String> =
+                a.keys().chain(b.keys().filter(|k| !a.contains_key(*k))).collect();
+            keys.sort();
+            for key in keys {
+                let path = if path.is_empty() { key.clone() } else { format!("{path}.{key}") };
+                diff_values(path, a.get(key), b.get(key), out);
+            }
+        }
+        (a, b) if a != b => out.push(FieldChange { path, old: a.cloned(), new: b.cloned() }),
+        _ => {}
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum SnapshotError {
+    #[error("unsupported device snapshot format {0} (this build reads up to {SNAPSHOT_FORMAT})")]
+    UnsupportedFormat(u32),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+/// A device list pinned to the Chromium revision its EmulatedDevices.ts came
+/// from. `chromium_revision` is free-form: a commit hash, a commit position
+/// or a version like "131.0.6778.85".
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeviceSnapshot {
+    pub format: u32,
+    #[serde(rename = "chromium-revision")]
+    pub chromium_revision: String,
+    pub devices: Vec<EmulatedDevice>,
+}
+
+impl DeviceSnapshot {
+    pub fn new(chromium_revision: impl Into<String>, devices: Vec<EmulatedDevice>) -> Self {
+        Self { format: SNAPSHOT_FORMAT, chromium_revision: chromium_revision.into(), devices }
+    }
+
+    /// Reads a snapshot, rejecting formats newer than `SNAPSHOT_FORMAT`
+    /// before looking at the devices.
+    pub fn from_json(json: &str) -> Result<Self, SnapshotError> {
+        #[derive(Deserialize)]
+        struct Header {
+            format: u32,
+        }
+        let header: Header = serde_json::from_str(json)?;
+        if header.format == 0 || header.format > SNAPSHOT_FORMAT {
+            return Err(SnapshotError::UnsupportedFormat(header.format));
+        }
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// What changed going from `self` to `newer`.
+    pub fn diff(&self, newer: &DeviceSnapshot) -> DeviceDiff {
+        diff_devices(&self.devices, &newer.devices)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device_registry::DeviceRegistry;
+
+    fn embedded() -> Vec<EmulatedDevice> {
+        DeviceRegistry::embedded().devices().to_vec()
+    }
+
+    fn paths(change: &DeviceChange) -> Vec<&str> {
+        change.fields.iter().map(|f| f.path.as_str()).collect()
+    }
+
+    #[test]
+    fn identical_lists_have_no_diff() {
+        assert!(diff_devices(&embedded(), &embedded()).is_empty());
+    }
+
+    #[test]
+    fn duplicate_titles_pair_by_order_then_position() {
+        let old = embedded();
+        let ipads: Vec<usize> =
+            old.iter().enumerate().filter(|(_, d)| d.title == "iPad Pro").map(|(i, _)| i).collect();
+        assert_eq!(ipads.len(), 2);
+        assert_ne!(old[ipads[0]].order, old[ipads[1]].order);
+
+        let mut new = old.clone();
+        new[ipads[1]].user_agent.push_str(" Changed");
+        let diff = diff_devices(&old, &new);
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].key, DeviceKey::of(&old[ipads[1]]));
+
+        // Same key twice: entries pair up in source order.
+        let first = old[ipads[0]].clone();
+        let mut second = first.clone();
+        second.user_agent.push_str(" Second");
+        let old = vec![first.clone(), second.clone()];
+        assert!(diff_devices(&old, &old).is_empty());
+        let swapped = diff_devices(&old, &[second, first]);
+        assert_eq!(swapped.changed.len(), 2);
+        assert!(swapped.added.is_empty() && swapped.removed.is_empty());
+    }
+
+    #[test]
+    fn field_level_changes() {
+        let old = embedded();
+        let mut new = old.clone();
+        let pixel = new.iter_mut().find(|d| d.title == "Pixel 7").unwrap();
+        pixel.user_agent = "Mozilla/5.0 (Linux; Android 14; Pixel 7)".into();
+        pixel.screen.vertical.height = 914;
+        pixel.user_agent_metadata.as_mut().unwrap().model = "Pixel 7".into();
+
+        let diff = diff_devices(&old, &new);
+        assert!(diff.added.is_empty() && diff.removed.is_empty());
+        assert_eq!(diff.changed.len(), 1);
+        let change = &diff.changed[0];
+        assert_eq!(
+            paths(change),
+            ["screen.vertical.height", "user-agent", "user-agent-metadata.model"]
+        );
+        assert_eq!(change.fields[0].old, Some(915.into()));
+        assert_eq!(change.fields[0].new, Some(914.into()));
+        assert_eq!(change.fields[2].old, Some("Pixel 5".into()));
+
+        let text = diff.to_string();
+        assert!(text.starts_with("~ \"Pixel 7\" (order 18)\n"), "{text}");
+        assert!(text.contains("    screen.vertical.height: 915 -> 914\n"), "{text}");
+    }
+
+    #[test]
+    fn absent_fields_diff_against_none() {
+        let old = embedded();
+        let mut new = old.clone();
+        new[0].user_agent_metadata =
+            old.iter().find(|d| d.title == "Pixel 7").unwrap().user_agent_metadata.clone();
+        let diff = diff_devices(&old, &new);
+        let field = &diff.changed[0].fields[0];
+        assert_eq!(field.path, "user-agent-metadata");
+        assert_eq!(field.old, None);
+        assert!(diff.to_string().contains("user-agent-metadata: (absent) -> {"));
+    }
+
+    #[test]
+    fn order_change_is_remove_plus_add() {
+        let old = embedded();
+        let mut new = old.clone();
+        let index = new.iter().position(|d| d.title == "iPhone XR").unwrap();
+        new[index].order = Some(13);
+
+        let diff = diff_devices(&old, &new);
+        assert!(diff.changed.is_empty());
+        assert_eq!(diff.removed, [old[index].clone()]);
+        assert_eq!(diff.added, [new[index].clone()]);
+        assert_eq!(diff.to_string(), "- \"iPhone XR\" (order 12)\n+ \"iPhone XR\" (order 13)\n");
+    }
+
+    #[test]
+    fn snapshot_format_is_checked() {
+        let snapshot = DeviceSnapshot::new("131.0.6778.85", embedded());
+        let json = snapshot.to_json().unwrap();
+        assert_eq!(DeviceSnapshot::from_json(&json).unwrap(), snapshot);
+
+        for format in [0, SNAPSHOT_FORMAT + 1] {
+            let json = format!(r#"{{"format": {format}, "chromium-revision": "x", "devices": 7}}"#);
+            assert!(matches!(
+                DeviceSnapshot::from_json(&json),
+                Err(SnapshotError::UnsupportedFormat(f)) if f == format
+            ));
+        }
+        assert!(matches!(DeviceSnapshot::from_json("{}"), Err(SnapshotError::Json(_))));
+    }
+
+    #[test]
+    fn devices_json_against_all_devices_json() {
+        let old: Vec<EmulatedDevice> = serde_json::from_str(include_str!("devices.json")).unwrap();
+        let new = embedded();
+        let diff = diff_devices(&old, &new);
+
+        assert!(diff.removed.is_empty());
+        assert_eq!(diff.added.len(), new.len() - old.len());
+        // devices.json's single `iPad Pro` pairs with the one of the same order.
+        assert!(diff.added.iter().any(|d| d.title == "iPad Pro" && d.order == Some(141)));
+
+        let changed: Vec<String> = diff.changed.iter().map(|c| c.key.to_string()).collect();
+        assert_eq!(changed.len(), old.len());
+        assert!(diff.changed.iter().all(|c| paths(c).contains(&"user-agent")));
+        let nexus = diff.changed.iter().find(|c| c.key.title == "Nexus 5").unwrap();
+        assert_eq!(paths(nexus), ["modes", "user-agent"]);
+    }
+}
@@ -0,0 +1,413 @@
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::device_registry::{DeviceLookupError, DeviceRegistry};
+use crate::device_struct::{
+    Capability, DeviceType, EmulatedDevice, Orientation, Screen, UserAgentMetadata,
+};
+use crate::device_viewport::{Posture, ScreenMetrics};
+
+// Converters between EmulatedDevice and the payloads real Chrome accepts:
+// ChromeDriver's `goog:chromeOptions.mobileEmulation` (parsed by
+// chrome/test/chromedriver/chrome/mobile_device.cc) and the CDP
+// Emulation.setDeviceMetricsOverride / Emulation.setUserAgentOverride params.
+//
+// The UA is exported verbatim; render `%s` templates first with
+// `EmulatedDevice::with_chrome_version`.
+
+#[derive(Debug, Error)]
+pub enum ConvertError {
+    #[error("mobileEmulation has neither deviceName nor deviceMetrics")]
+    MissingDeviceMetrics,
+    #[error(transparent)]
+    UnknownDevice(#[from] DeviceLookupError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DeviceOrientation {
+    #[default]
+    Portrait,
+    Landscape,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MobileEmulation {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_metrics: Option<DeviceMetrics>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_hints: Option<UserAgentMetadata>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceMetrics {
+    pub width: i32,
+    pub height: i32,
+    pub pixel_ratio: f64,
+    // mobile_device.cc defaults both to true when absent
+    #[serde(default = "default_true")]
+    pub touch: bool,
+    #[serde(default = "default_true")]
+    pub mobile: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScreenOrientation {
+    #[serde(rename = "type")]
+    pub orientation_type: String,
+    pub angle: i32,
+}
+
+/// Params of `Emulation.setDeviceMetricsOverride`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetDeviceMetricsOverride {
+    pub width: i32,
+    pub height: i32,
+    pub device_scale_factor: f64,
+    pub mobile: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub screen_width: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub screen_height: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position_x: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position_y: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub screen_orientation: Option<ScreenOrientation>,
+}
+
+/// Params of `Emulation.setUserAgentOverride`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetUserAgentOverride {
+    pub user_agent: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accept_language: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub platform: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_agent_metadata: Option<UserAgentMetadata>,
+}
+
+impl MobileEmulation {
+    /// Explicit metrics in portrait, like ChromeDriver builds for a `deviceName`.
+    pub fn from_device(device: &EmulatedDevice) -> Self {
+        let portrait = &device.screen.vertical;
+        Self {
+            device_name: None,
+            device_metrics: Some(DeviceMetrics {
+                width: portrait.width,
+                height: portrait.height,
+                pixel_ratio: device.screen.device_pixel_ratio,
+                touch: device.capabilities.touch(),
+                mobile: device.capabilities.mobile(),
+            }),
+            user_agent: Some(device.user_agent.clone()),
+            client_hints: device.user_agent_metadata.clone(),
+        }
+    }
+
+    /// Just `{"deviceName": title}`, resolved by ChromeDriver's own list.
+    pub fn by_name(device: &EmulatedDevice) -> Self {
+        Self {
+            device_name: Some(device.title.clone()),
+            device_metrics: None,
+            user_agent: None,
+            client_hints: None,
+        }
+    }
+
+    /// `deviceName` is resolved against the embedded registry; explicit
+    /// metrics produce an untitled device whose landscape screen is the
+    /// transposed portrait one.
+    pub fn to_device(&self) -> Result<EmulatedDevice, ConvertError> {
+        if let Some(name) = &self.device_name {
+            return Ok(DeviceRegistry::embedded().find(name)?.clone());
+        }
+        let metrics = self.device_metrics.as_ref().ok_or(ConvertError::MissingDeviceMetrics)?;
+        Ok(build_device(
+            String::new(),
+            metrics,
+            self.user_agent.clone().unwrap_or_default(),
+            self.client_hints.clone(),
+        ))
+    }
+}
+
+impl SetDeviceMetricsOverride {
+    pub fn from_device(device: &EmulatedDevice, orientation: DeviceOrientation) -> Self {
+        let posture = match orientation {
+            DeviceOrientation::Portrait => Posture::Portrait,
+            DeviceOrientation::Landscape => Posture::Landscape,
+        };
+        let metrics = device.screen_metrics(posture).expect("every device has both orientations");
+        Self::from_screen_metrics(&metrics, device.capabilities.mobile())
+    }
+
+    /// The viewport is the screen minus the mode's insets, offset by them.
+    pub fn from_screen_metrics(metrics: &ScreenMetrics, mobile: bool) -> Self {
+        Self {
+            width: metrics.viewport_width,
+            height: metrics.viewport_height,
+            device_scale_factor: metrics.device_pixel_ratio,
+            mobile,
+            screen_width: Some(metrics.screen_width),
+            screen_height: Some(metrics.screen_height),
+            position_x: Some(metrics.viewport_x),
+            position_y: Some(metrics.viewport_y),
+            screen_orientation: Some(metrics.orientation.clone()),
+        }
+    }
+
+    pub fn orientation(&self) -> DeviceOrientation {
+        match &self.screen_orientation {
+            Some(o) if o.orientation_type.starts_with("landscape") => DeviceOrientation::Landscape,
+            Some(_) => DeviceOrientation::Portrait,
+            None if self.width > self.height => DeviceOrientation::Landscape,
+            None => DeviceOrientation::Portrait,
+        }
+    }
+}
+
+impl SetUserAgentOverride {
+    pub fn from_device(device: &EmulatedDevice) -> Self {
+        Self {
+            user_agent: device.user_agent.clone(),
+            accept_language: None,
+            platform: None,
+            user_agent_metadata: device.user_agent_metadata.clone(),
+        }
+    }
+}
+
+/// Rebuilds a device from the two CDP commands DevTools sends for it. Touch is
+/// not part of either command and is assumed for mobile devices.
+pub fn device_from_cdp(
+    title: &str,
+    metrics: &SetDeviceMetricsOverride,
+    user_agent: &SetUserAgentOverride,
+) -> EmulatedDevice {
+    let width = metrics.screen_width.unwrap_or(metrics.width);
+    let height = metrics.screen_height.unwrap_or(metrics.height);
+    let (width, height) = match metrics.orientation() {
+        DeviceOrientation::Portrait => (width, height),
+        DeviceOrientation::Landscape => (height, width),
+    };
+    let portrait = DeviceMetrics {
+        width,
+        height,
+        pixel_ratio: metrics.device_scale_factor,
+        touch: metrics.mobile,
+        mobile: metrics.mobile,
+    };
+    build_device(
+        title.to_string(),
+        &portrait,
+        user_agent.user_agent.clone(),
+        user_agent.user_agent_metadata.clone(),
+    )
+}
+
+fn build_device(
+    title: String,
+    portrait: &DeviceMetrics,
+    user_agent: String,
+    user_agent_metadata: Option<UserAgentMetadata>,
+) -> EmulatedDevice {
+    let orientation = |width, height| Orientation { width, height, outline: None };
+    let capabilities = [(Capability::Touch, portrait.touch), (Capability::Mobile, portrait.mobile)]
+        .into_iter()
+        .filter_map(|(capability, enabled)| enabled.then_some(capability))
+        .collect();
+    EmulatedDevice {
+        title,
+        device_type: if portrait.mobile { DeviceType::Phone } else { DeviceType::Desktop },
+        order: None,
+        user_agent,
+        capabilities,
+        screen: Screen {
+            device_pixel_ratio: portrait.pixel_ratio,
+            horizontal: orientation(portrait.height, portrait.width),
+            vertical: orientation(portrait.width, portrait.height),
+            vertical_spanned: None,
+            horizontal_spanned: None,
+        },
+        modes: Vec::new(),
+        user_agent_metadata,
+        show_by_default: false,
+        dual_screen: false,
+        foldable_screen: false,
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    const PIXEL_3_UA: &str = "Mozilla/5.0 (Linux; Android 11; Pixel 3) AppleWebKit/537.36 \
+                              (KHTML, like Gecko) Chrome/%s Mobile Safari/537.36";
+
+    fn device(title: &str) -> &'static EmulatedDevice {
+        DeviceRegistry::embedded().get(title).unwrap()
+    }
+
+    fn pixel_3_metadata() -> serde_json::Value {
+        json!({
+            "platform": "Android",
+            "platformVersion": "11",
+            "architecture": "",
+            "model": "Pixel 3",
+            "mobile": true,
+        })
+    }
+
+    #[test]
+    fn mobile_emulation_golden_json() {
+        let emulation = MobileEmulation::from_device(device("Pixel 3 XL"));
+        assert_eq!(
+            serde_json::to_value(&emulation).unwrap(),
+            json!({
+                "deviceMetrics": {
+                    "width": 393,
+                    "height": 786,
+                    "pixelRatio": 2.75,
+                    "touch": true,
+                    "mobile": true,
+                },
+                "userAgent": PIXEL_3_UA,
+                "clientHints": pixel_3_metadata(),
+            })
+        );
+        assert_eq!(
+            serde_json::to_value(MobileEmulation::by_name(device("Pixel 7"))).unwrap(),
+            json!({"deviceName": "Pixel 7"})
+        );
+    }
+
+    #[test]
+    fn mobile_emulation_round_trips() {
+        let original = device("Pixel 3 XL");
+        let emulation = MobileEmulation::from_device(original);
+        let json = serde_json::to_string(&emulation).unwrap();
+        let parsed: MobileEmulation = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, emulation);
+
+        let rebuilt = parsed.to_device().unwrap();
+        assert_eq!(rebuilt.title, "");
+        assert_eq!(rebuilt.device_type, DeviceType::Phone);
+        assert_eq!(rebuilt.screen, original.screen);
+        assert_eq!(rebuilt.capabilities, original.capabilities);
+        assert_eq!(rebuilt.user_agent, original.user_agent);
+        assert_eq!(rebuilt.user_agent_metadata, original.user_agent_metadata);
+
+        let by_name = MobileEmulation::by_name(original).to_device().unwrap();
+        assert_eq!(&by_name, original);
+    }
+
+    #[test]
+    fn mobile_emulation_defaults_touch_and_mobile() {
+        let emulation: MobileEmulation = serde_json::from_value(json!({
+            "deviceMetrics": {"width": 360, "height": 640, "pixelRatio": 3.0},
+        }))
+        .unwrap();
+        let metrics = emulation.device_metrics.as_ref().unwrap();
+        assert!(metrics.touch && metrics.mobile);
+
+        let device = emulation.to_device().unwrap();
+        assert_eq!(device.user_agent, "");
+        assert_eq!((device.screen.horizontal.width, device.screen.horizontal.height), (640, 360));
+    }
+
+    #[test]
+    fn mobile_emulation_errors() {
+        let empty: MobileEmulation = serde_json::from_value(json!({})).unwrap();
+        assert!(matches!(empty.to_device(), Err(ConvertError::MissingDeviceMetrics)));
+
+        let unknown: MobileEmulation =
+            serde_json::from_value(json!({"deviceName": "Nokia 3310"})).unwrap();
+        assert!(matches!(unknown.to_device(), Err(ConvertError::UnknownDevice(_))));
+    }
+
+    #[test]
+    fn cdp_golden_json() {
+        let metrics = SetDeviceMetricsOverride::from_device(
+            device("iPhone SE"),
+            DeviceOrientation::Landscape,
+        );
+        assert_eq!(
+            serde_json::to_value(&metrics).unwrap(),
+            json!({
+                "width": 667,
+                "height": 375,
+                "deviceScaleFactor": 2.0,
+                "mobile": true,
+                "screenWidth": 667,
+                "screenHeight": 375,
+                "positionX": 0,
+                "positionY": 0,
+                "screenOrientation": {"type": "landscapePrimary", "angle": 90},
+            })
+        );
+
+        let user_agent = SetUserAgentOverride::from_device(device("Pixel 3 XL"));
+        assert_eq!(
+            serde_json::to_value(&user_agent).unwrap(),
+            json!({"userAgent": PIXEL_3_UA, "userAgentMetadata": pixel_3_metadata()})
+        );
+    }
+
+    #[test]
+    fn device_from_cdp_round_trips_both_orientations() {
+        // Nexus 5 has status and navigation bar insets, so the viewport is
+        // smaller than the screen and the screen must come from screenWidth.
+        for title in ["Pixel 3 XL", "Nexus 5", "iPhone SE"] {
+            let original = device(title);
+            let user_agent = SetUserAgentOverride::from_device(original);
+            for orientation in [DeviceOrientation::Portrait, DeviceOrientation::Landscape] {
+                let metrics = SetDeviceMetricsOverride::from_device(original, orientation);
+                assert_eq!(metrics.orientation(), orientation);
+                let rebuilt = device_from_cdp(title, &metrics, &user_agent);
+                assert_eq!(rebuilt.title, title);
+                assert_eq!(rebuilt.screen.vertical, original.screen.vertical, "{title}");
+                assert_eq!(rebuilt.screen.horizontal.width, original.screen.horizontal.width);
+                assert_eq!(rebuilt.screen.device_pixel_ratio, original.screen.device_pixel_ratio);
+                assert_eq!(rebuilt.capabilities, original.capabilities);
+                assert_eq!(rebuilt.user_agent_metadata, original.user_agent_metadata);
+            }
+        }
+    }
+
+    #[test]
+    fn device_from_cdp_without_screen_fields() {
+        let metrics: SetDeviceMetricsOverride = serde_json::from_value(json!({
+            "width": 800,
+            "height": 600,
+            "deviceScaleFactor": 1,
+            "mobile": false,
+        }))
+        .unwrap();
+        assert_eq!(metrics.orientation(), DeviceOrientation::Landscape);
+        let user_agent: SetUserAgentOverride =
+            serde_json::from_value(json!({"userAgent": "Mozilla/5.0"})).unwrap();
+
+        let device = device_from_cdp("Laptop", &metrics, &user_agent);
+        assert_eq!(device.device_type, DeviceType::Desktop);
+        assert_eq!((device.screen.vertical.width, device.screen.vertical.height), (600, 800));
+        assert!(!device.capabilities.touch());
+        assert_eq!(device.user_agent_metadata, None);
+    }
+}
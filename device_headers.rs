@@ -0,0 +1,172 @@
+use http::header::{HeaderMap, HeaderName, HeaderValue, InvalidHeaderValue, USER_AGENT};
+
+use crate::chrome_version::{ChromeVersion, UaMode};
+use crate::device_struct::{EmulatedDevice, UserAgentBrandVersion, UserAgentMetadata};
+
+pub const SEC_CH_UA: HeaderName = HeaderName::from_static("sec-ch-ua");
+pub const SEC_CH_UA_MOBILE: HeaderName = HeaderName::from_static("sec-ch-ua-mobile");
+pub const SEC_CH_UA_PLATFORM: HeaderName = HeaderName::from_static("sec-ch-ua-platform");
+pub const SEC_CH_UA_ARCH: HeaderName = HeaderName::from_static("sec-ch-ua-arch");
+pub const SEC_CH_UA_MODEL: HeaderName = HeaderName::from_static("sec-ch-ua-model");
+pub const SEC_CH_UA_PLATFORM_VERSION: HeaderName =
+    HeaderName::from_static("sec-ch-ua-platform-version");
+pub const SEC_CH_UA_FULL_VERSION_LIST: HeaderName =
+    HeaderName::from_static("sec-ch-ua-full-version-list");
+
+/// Headers `EmulationHandler::ApplyOverrides` would set for `device` running
+/// as Chrome `version`.
+///
+/// The device is rendered first, the way DevTools patches `%s` and the brand
+/// lists with the running browser's: `all_devices.json` carries neither.
+/// Entries are inserted in Chrome's wire order: the low-entropy hints, then the
+/// high-entropy ones, then `User-Agent`. Devices without `user-agent-metadata`
+/// (the Safari-based iOS entries) get `User-Agent` only, because Chrome sends
+/// no UA client hints when an override carries no metadata.
+pub fn emulation_headers(
+    device: &EmulatedDevice,
+    version: ChromeVersion,
+    mode: UaMode,
+) -> Result<HeaderMap, InvalidHeaderValue> {
+    let device = device.with_chrome_version(version, mode);
+    let mut headers = HeaderMap::new();
+    if let Some(metadata) = &device.user_agent_metadata {
+        append_client_hints(&mut headers, metadata)?;
+    }
+    headers.insert(USER_AGENT, HeaderValue::from_str(&device.user_agent)?);
+    Ok(headers)
+}
+
+/// The `Sec-CH-UA*` headers for `metadata`, serialized as Structured Field
+/// values exactly like Chrome's `UserAgentMetadata` serializers.
+///
+/// The brand lists are skipped when empty: an empty sf-list is not a valid
+/// `Sec-CH-UA`, and Chrome never sends one.
+pub fn append_client_hints(
+    headers: &mut HeaderMap,
+    metadata: &UserAgentMetadata,
+) -> Result<(), InvalidHeaderValue> {
+    if !metadata.brands.is_empty() {
+        headers.insert(SEC_CH_UA, HeaderValue::from_str(&serialize_brand_list(&metadata.brands))?);
+    }
+    headers.insert(SEC_CH_UA_MOBILE, HeaderValue::from_static(sf_boolean(metadata.mobile)));
+    headers.insert(SEC_CH_UA_PLATFORM, HeaderValue::from_str(&sf_string(&metadata.platform))?);
+    headers.insert(SEC_CH_UA_ARCH, HeaderValue::from_str(&sf_string(&metadata.architecture))?);
+    if !metadata.full_version_list.is_empty() {
+        headers.insert(
+            SEC_CH_UA_FULL_VERSION_LIST,
+            HeaderValue::from_str(&serialize_brand_list(&metadata.full_version_list))?,
+        );
+    }
+    headers.insert(SEC_CH_UA_MODEL, HeaderValue::from_str(&sf_string(&metadata.model))?);
+    headers.insert(
+        SEC_CH_UA_PLATFORM_VERSION,
+        HeaderValue::from_str(&sf_string(&metadata.platform_version))?,
+    );
+    Ok(())
+}
+
+/// `"Chromium";v="120", "Not_A Brand";v="8"`: an sf-list of strings with a `v`
+/// parameter, joined with ", " as Chrome does.
+pub fn serialize_brand_list(brands: &[UserAgentBrandVersion]) -> String {
+    brands
+        .iter()
+        .map(|b| format!("{};v={}", sf_string(&b.brand), sf_string(&b.version)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// RFC 8941 sf-string: wrapped in double quotes, with `\` and `"` escaped.
+pub fn sf_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        if c == '"' || c == '\\' {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out.push('"');
+    out
+}
+
+/// RFC 8941 sf-boolean: `?1` or `?0`.
+pub fn sf_boolean(value: bool) -> &'static str {
+    if value {
+        "?1"
+    } else {
+        "?0"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device_registry::DeviceRegistry;
+
+    const CHROME_120: ChromeVersion = ChromeVersion::new(120, 0, 6099, 109);
+
+    fn header<'a>(headers: &'a HeaderMap, name: &HeaderName) -> Option<&'a str> {
+        headers.get(name).map(|v| v.to_str().unwrap())
+    }
+
+    #[test]
+    fn sf_string_escapes_quotes_and_backslashes() {
+        assert_eq!(sf_string("Android"), r#""Android""#);
+        assert_eq!(sf_string(r#"a"b\c"#), r#""a\"b\\c""#);
+        assert_eq!(sf_string(""), r#""""#);
+    }
+
+    #[test]
+    fn brand_list_is_joined_sf_list() {
+        let brands = [
+            UserAgentBrandVersion { brand: "Not_A Brand".into(), version: "8".into() },
+            UserAgentBrandVersion { brand: "Chromium".into(), version: "120".into() },
+        ];
+        assert_eq!(serialize_brand_list(&brands), r#""Not_A Brand";v="8", "Chromium";v="120""#);
+    }
+
+    #[test]
+    fn android_device_gets_rendered_ua_and_brands() {
+        let device = DeviceRegistry::embedded().get("Pixel 3 XL").unwrap();
+        let headers = emulation_headers(device, CHROME_120, UaMode::Reduced).unwrap();
+        let user_agent = header(&headers, &USER_AGENT).unwrap();
+        assert!(user_agent.contains("Chrome/120.0.0.0 Mobile"), "{user_agent}");
+        assert!(!user_agent.contains("%s"));
+        assert_eq!(
+            header(&headers, &SEC_CH_UA).unwrap(),
+            serialize_brand_list(&CHROME_120.brands())
+        );
+        assert!(header(&headers, &SEC_CH_UA_FULL_VERSION_LIST)
+            .unwrap()
+            .contains(r#""120.0.6099.109""#));
+        assert_eq!(header(&headers, &SEC_CH_UA_MOBILE), Some("?1"));
+        assert_eq!(header(&headers, &SEC_CH_UA_PLATFORM), Some(r#""Android""#));
+        assert_eq!(header(&headers, &SEC_CH_UA_MODEL), Some(r#""Pixel 3""#));
+    }
+
+    #[test]
+    fn safari_device_gets_user_agent_only() {
+        let device = DeviceRegistry::embedded().get("iPhone SE").unwrap();
+        let headers = emulation_headers(device, CHROME_120, UaMode::Reduced).unwrap();
+        assert_eq!(headers.len(), 1);
+        assert!(headers.contains_key(USER_AGENT));
+    }
+
+    #[test]
+    fn empty_brand_lists_are_not_sent() {
+        let metadata = UserAgentMetadata {
+            brands: Vec::new(),
+            full_version_list: Vec::new(),
+            platform: "Android".into(),
+            platform_version: "11".into(),
+            architecture: String::new(),
+            model: "Pixel 3".into(),
+            mobile: true,
+        };
+        let mut headers = HeaderMap::new();
+        append_client_hints(&mut headers, &metadata).unwrap();
+        assert!(!headers.contains_key(SEC_CH_UA));
+        assert!(!headers.contains_key(SEC_CH_UA_FULL_VERSION_LIST));
+        assert_eq!(header(&headers, &SEC_CH_UA_ARCH), Some(r#""""#));
+    }
+}
@@ -0,0 +1,166 @@
+use std::sync::OnceLock;
+
+use thiserror::Error;
+
+use crate::device_struct::{Capability, DeviceType, EmulatedDevice};
+
+// Snapshot of DevTools' EmulatedDevices.ts, see emulation_request_analysis.md
+const EMBEDDED_DEVICES: &str = include_str!("all_devices.json");
+
+#[derive(Debug, Error)]
+pub enum DeviceLookupError {
+    #[error("Unknown device \"{query}\"; did you mean \"{suggestion}\"?")]
+    DidYouMean { query: String, suggestion: String },
+    #[error("Unknown device \"{0}\"")]
+    NotFound(String),
+}
+
+/// Read-only index over a list of `EmulatedDevice`s.
+///
+/// Titles are not unique (`iPad Pro` appears twice in `all_devices.json`), so
+/// single-result lookups return the first entry in source order.
+#[derive(Debug, Clone)]
+pub struct DeviceRegistry {
+    devices: Vec<EmulatedDevice>,
+}
+
+impl DeviceRegistry {
+    /// The 55 devices from `all_devices.json`, parsed once on first use.
+    pub fn embedded() -> &'static DeviceRegistry {
+        static REGISTRY: OnceLock<DeviceRegistry> = OnceLock::new();
+        REGISTRY.get_or_init(|| {
+            DeviceRegistry::from_json(EMBEDDED_DEVICES).expect("embedded all_devices.json is valid")
+        })
+    }
+
+    pub fn new(devices: Vec<EmulatedDevice>) -> Self {
+        Self { devices }
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json).map(Self::new)
+    }
+
+    pub fn len(&self) -> usize {
+        self.devices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.devices.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &EmulatedDevice> {
+        self.devices.iter()
+    }
+
+    pub fn devices(&self) -> &[EmulatedDevice] {
+        &self.devices
+    }
+
+    /// Exact, case-sensitive title match.
+    pub fn get(&self, title: &str) -> Option<&EmulatedDevice> {
+        self.devices.iter().find(|d| d.title == title)
+    }
+
+    /// Every entry with exactly this title.
+    pub fn get_all<'a>(&'a self, title: &'a str) -> impl Iterator<Item = &'a EmulatedDevice> {
+        self.devices.iter().filter(move |d| d.title == title)
+    }
+
+    /// Lenient lookup for names coming from config files.
+    ///
+    /// Tries an exact match, then a case-insensitive one, then a match that
+    /// ignores spaces and punctuation ("pixel-7" finds "Pixel 7"). On failure the
+    /// error carries the closest title by edit distance, if any is close enough.
+    pub fn find(&self, name: &str) -> Result<&EmulatedDevice, DeviceLookupError> {
+        if let Some(device) = self.get(name) {
+            return Ok(device);
+        }
+        let trimmed = name.trim();
+        if let Some(device) = self.devices.iter().find(|d| d.title.eq_ignore_ascii_case(trimmed)) {
+            return Ok(device);
+        }
+        let wanted = normalize(trimmed);
+        if let Some(device) = self.devices.iter().find(|d| normalize(&d.title) == wanted) {
+            return Ok(device);
+        }
+
+        match self.suggest(&wanted) {
+            Some(suggestion) => Err(DeviceLookupError::DidYouMean {
+                query: name.to_string(),
+                suggestion: suggestion.to_string(),
+            }),
+            None => Err(DeviceLookupError::NotFound(name.to_string())),
+        }
+    }
+
+    pub fn by_type<'a>(
+        &'a self,
+        device_type: &'a DeviceType,
+    ) -> impl Iterator<Item = &'a EmulatedDevice> {
+        self.devices.iter().filter(move |d| d.device_type == *device_type)
+    }
+
+    pub fn with_capability(&self, capability: Capability) -> impl Iterator<Item = &EmulatedDevice> {
+        self.devices.iter().filter(move |d| d.capabilities.contains(capability))
+    }
+
+    pub fn touch(&self) -> impl Iterator<Item = &EmulatedDevice> {
+        self.with_capability(Capability::Touch)
+    }
+
+    pub fn mobile(&self) -> impl Iterator<Item = &EmulatedDevice> {
+        self.with_capability(Capability::Mobile)
+    }
+
+    /// Devices DevTools lists in the toolbar without the user enabling them.
+    pub fn shown_by_default(&self) -> impl Iterator<Item = &EmulatedDevice> {
+        self.devices.iter().filter(|d| d.show_by_default)
+    }
+
+    pub fn by_order(&self, order: i32) -> Option<&EmulatedDevice> {
+        self.devices.iter().find(|d| d.order == Some(order))
+    }
+
+    /// Devices sorted the way the DevTools device menu shows them. Entries
+    /// without an `order` go last, keeping their source order.
+    pub fn ordered(&self) -> Vec<&EmulatedDevice> {
+        let mut devices: Vec<&EmulatedDevice> = self.devices.iter().collect();
+        devices.sort_by_key(|d| d.order.unwrap_or(i32::MAX));
+        devices
+    }
+
+    fn suggest(&self, wanted: &str) -> Option<&str> {
+        let (distance, title) = self
+            .devices
+            .iter()
+            .map(|d| (levenshtein(wanted, &normalize(&d.title)), d.title.as_str()))
+            .min_by_key(|(distance, _)| *distance)?;
+        // Allow roughly one typo per three characters, never fewer than two.
+        let limit = (wanted.chars().count() / 3).max(2);
+        (distance <= limit).then_some(title)
+    }
+}
+
+fn normalize(title: &str) -> String {
+    title
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+    for (i, ca) in a.chars().enumerate() {
+        curr[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            curr[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+    prev[b.len()]
+}
@@ -1,6 +1,9 @@
-use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Insets {
     pub left: i32,
     pub top: i32,
@@ -8,7 +11,7 @@ pub struct Insets {
     pub bottom: i32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Orientation {
     pub width: i32,
     pub height: i32,
@@ -16,13 +19,13 @@ pub struct Orientation {
     pub outline: Option<Outline>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Outline {
     pub image: Option<String>,
     pub insets: Option<Insets>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Screen {
     #[serde(rename = "device-pixel-ratio")]
     pub device_pixel_ratio: f64,
@@ -34,26 +37,53 @@ pub struct Screen {
     pub horizontal_spanned: Option<Orientation>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+// A DevTools device mode: the system UI (status/navigation bar, keyboard)
+// carved out of the screen in one orientation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Mode {
+    pub title: String,
+    pub orientation: String, // "vertical", "horizontal", "vertical-spanned", ...
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub insets: Option<Insets>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserAgentBrandVersion {
+    pub brand: String,
+    pub version: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct UserAgentMetadata {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub brands: Vec<UserAgentBrandVersion>, // Filled from the browser version by DevTools
+    #[serde(rename = "fullVersionList", default, skip_serializing_if = "Vec::is_empty")]
+    pub full_version_list: Vec<UserAgentBrandVersion>,
     pub platform: String,
-    #[serde(rename = "platformVersion")]
+    #[serde(rename = "platformVersion", default)]
     pub platform_version: String,
+    #[serde(default)]
     pub architecture: String,
+    #[serde(default)]
     pub model: String,
     pub mobile: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct EmulatedDevice {
     pub title: String,
     #[serde(rename = "type")]
-    pub device_type: String, // "phone", "tablet", etc.
-    pub order: i32,
+    pub device_type: DeviceType,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order: Option<i32>, // Legacy DevTools entries have no order
     #[serde(rename = "user-agent")]
     pub user_agent: String,
-    pub capabilities: Vec<String>, // ["touch", "mobile"]
+    pub capabilities: Capabilities,
     pub screen: Screen,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub modes: Vec<Mode>,
     #[serde(rename = "user-agent-metadata", skip_serializing_if = "Option::is_none")]
     pub user_agent_metadata: Option<UserAgentMetadata>,
     #[serde(rename = "show-by-default")]
@@ -64,4 +94,205 @@ pub struct EmulatedDevice {
     pub foldable_screen: bool,
 }
 
+// DevTools' `type` field. Values this code does not know yet are kept in
+// `Other` so they serialize back unchanged.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DeviceType {
+    Phone,
+    Tablet,
+    Notebook,
+    Desktop,
+    Unknown,
+    Other(String),
+}
+
+impl DeviceType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            DeviceType::Phone => "phone",
+            DeviceType::Tablet => "tablet",
+            DeviceType::Notebook => "notebook",
+            DeviceType::Desktop => "desktop",
+            DeviceType::Unknown => "unknown",
+            DeviceType::Other(other) => other,
+        }
+    }
+}
+
+impl FromStr for DeviceType {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "phone" => DeviceType::Phone,
+            "tablet" => DeviceType::Tablet,
+            "notebook" => DeviceType::Notebook,
+            "desktop" => DeviceType::Desktop,
+            "unknown" => DeviceType::Unknown,
+            other => DeviceType::Other(other.to_string()),
+        })
+    }
+}
+
+impl fmt::Display for DeviceType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for DeviceType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for DeviceType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Ok(s.parse().unwrap_or_else(|never| match never {}))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Capability {
+    Touch,
+    Mobile,
+}
+
+impl Capability {
+    pub const ALL: [Capability; 2] = [Capability::Touch, Capability::Mobile];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Capability::Touch => "touch",
+            Capability::Mobile => "mobile",
+        }
+    }
+
+    fn bit(self) -> u8 {
+        match self {
+            Capability::Touch => 1 << 0,
+            Capability::Mobile => 1 << 1,
+        }
+    }
+}
+
+// DevTools' `capabilities` array, kept as written (order, duplicates and
+// unrecognized entries included) so it serializes back byte for byte, with the
+// known entries mirrored in a bitset for lookups.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Capabilities {
+    bits: u8,
+    entries: Vec<String>,
+}
+
+impl Capabilities {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn contains(&self, capability: Capability) -> bool {
+        self.bits & capability.bit() != 0
+    }
+
+    /// Appends `capability` unless it is already present.
+    pub fn insert(&mut self, capability: Capability) {
+        if !self.contains(capability) {
+            self.bits |= capability.bit();
+            self.entries.push(capability.as_str().to_string());
+        }
+    }
+
+    pub fn remove(&mut self, capability: Capability) {
+        self.bits &= !capability.bit();
+        self.entries.retain(|entry| entry != capability.as_str());
+    }
+
+    pub fn touch(&self) -> bool {
+        self.contains(Capability::Touch)
+    }
+
+    pub fn mobile(&self) -> bool {
+        self.contains(Capability::Mobile)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Capability> + '_ {
+        Capability::ALL.into_iter().filter(|c| self.contains(*c))
+    }
+
+    /// Entries that are not a known `Capability`.
+    pub fn unknown(&self) -> impl Iterator<Item = &str> + '_ {
+        self.entries
+            .iter()
+            .map(String::as_str)
+            .filter(|entry| Capability::ALL.iter().all(|c| c.as_str() != *entry))
+    }
+}
+
+impl FromIterator<Capability> for Capabilities {
+    fn from_iter<I: IntoIterator<Item = Capability>>(iter: I) -> Self {
+        let mut capabilities = Capabilities::new();
+        for capability in iter {
+            capabilities.insert(capability);
+        }
+        capabilities
+    }
+}
+
+impl Serialize for Capabilities {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(&self.entries)
+    }
+}
+
+impl<'de> Deserialize<'de> for Capabilities {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let entries = Vec::<String>::deserialize(deserializer)?;
+        let bits = Capability::ALL
+            .into_iter()
+            .filter(|c| entries.iter().any(|entry| entry == c.as_str()))
+            .fold(0, |bits, c| bits | c.bit());
+        Ok(Capabilities { bits, entries })
+    }
+}
+
 // A collection of these devices would be Vec<EmulatedDevice>
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn type_and_capabilities_round_trip_all_devices() {
+        let raw: serde_json::Value =
+            serde_json::from_str(include_str!("all_devices.json")).unwrap();
+        for device in raw.as_array().unwrap() {
+            for (key, value) in
+                [("type", &device["type"]), ("capabilities", &device["capabilities"])]
+            {
+                let expected = serde_json::to_string(value).unwrap();
+                let back = match key {
+                    "type" => serde_json::to_string(&DeviceType::deserialize(value).unwrap()),
+                    _ => serde_json::to_string(&Capabilities::deserialize(value).unwrap()),
+                };
+                assert_eq!(back.unwrap(), expected, "{} {key}", device["title"]);
+            }
+        }
+    }
+
+    #[test]
+    fn capabilities_keep_order_duplicates_and_unknown_entries() {
+        let json = r#"["mobile","touch","touch","foldable"]"#;
+        let capabilities: Capabilities = serde_json::from_str(json).unwrap();
+        assert!(capabilities.touch() && capabilities.mobile());
+        assert_eq!(capabilities.unknown().collect::<Vec<_>>(), ["foldable"]);
+        assert_eq!(serde_json::to_string(&capabilities).unwrap(), json);
+    }
+
+    #[test]
+    fn unknown_device_type_is_preserved() {
+        let device_type: DeviceType = serde_json::from_str(r#""watch""#).unwrap();
+        assert_eq!(device_type, DeviceType::Other("watch".to_string()));
+        assert_eq!(serde_json::to_string(&device_type).unwrap(), r#""watch""#);
+    }
+}
@@ -0,0 +1,400 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::device_registry::DeviceRegistry;
+use crate::device_struct::{EmulatedDevice, Orientation};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DiagnosticCode {
+    EmptyTitle,
+    NonPositiveSize,
+    InvalidDevicePixelRatio,
+    VerticalNotTransposed,
+    SpannedNotTransposed,
+    MissingVerticalSpanned,
+    UnexpectedSpanned,
+    MobileMismatch,
+    PlatformMismatch,
+    UnknownCapability,
+    DuplicateTitle,
+    DuplicateOrder,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: DiagnosticCode,
+    pub message: String,
+}
+
+/// A `Diagnostic` from `lint_devices`, located by index and title.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceDiagnostic {
+    pub index: usize,
+    pub title: String,
+    pub diagnostic: Diagnostic,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{severity} [{:?}]: {}", self.code, self.message)
+    }
+}
+
+impl fmt::Display for DeviceDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{} \"{}\": {}", self.index, self.title, self.diagnostic)
+    }
+}
+
+impl Diagnostic {
+    fn error(code: DiagnosticCode, message: String) -> Self {
+        Self { severity: Severity::Error, code, message }
+    }
+
+    fn warning(code: DiagnosticCode, message: String) -> Self {
+        Self { severity: Severity::Warning, code, message }
+    }
+}
+
+impl EmulatedDevice {
+    /// Consistency checks on a single device definition. Errors make the
+    /// device unusable for emulation; warnings flag data that contradicts
+    /// itself (and would be visible to a fingerprinting site).
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        use DiagnosticCode::*;
+        let mut out = Vec::new();
+        let screen = &self.screen;
+
+        if self.title.trim().is_empty() {
+            out.push(Diagnostic::error(EmptyTitle, "title is empty".to_string()));
+        }
+        if !(screen.device_pixel_ratio.is_finite() && screen.device_pixel_ratio > 0.0) {
+            out.push(Diagnostic::error(
+                InvalidDevicePixelRatio,
+                format!("device-pixel-ratio {} is not positive", screen.device_pixel_ratio),
+            ));
+        }
+
+        let postures = [
+            ("horizontal", Some(&screen.horizontal)),
+            ("vertical", Some(&screen.vertical)),
+            ("horizontal-spanned", screen.horizontal_spanned.as_ref()),
+            ("vertical-spanned", screen.vertical_spanned.as_ref()),
+        ];
+        for (name, orientation) in postures {
+            if let Some(o) = orientation.filter(|o| o.width <= 0 || o.height <= 0) {
+                out.push(Diagnostic::error(
+                    NonPositiveSize,
+                    format!("{name} is {}x{}", o.width, o.height),
+                ));
+            }
+        }
+
+        if is_same_size(&screen.horizontal, &screen.vertical) {
+            // Nest Hub and Lumia 550 ship like this in DevTools: rotation is a no-op.
+            out.push(Diagnostic::warning(
+                VerticalNotTransposed,
+                format!(
+                    "vertical equals horizontal ({}x{}), the device cannot rotate",
+                    screen.horizontal.width, screen.horizontal.height
+                ),
+            ));
+        } else if !is_transposed(&screen.horizontal, &screen.vertical) {
+            out.push(Diagnostic::error(
+                VerticalNotTransposed,
+                format!(
+                    "vertical {}x{} is not horizontal {}x{} transposed",
+                    screen.vertical.width,
+                    screen.vertical.height,
+                    screen.horizontal.width,
+                    screen.horizontal.height
+                ),
+            ));
+        }
+        if let (Some(h), Some(v)) = (&screen.horizontal_spanned, &screen.vertical_spanned) {
+            if !is_transposed(h, v) {
+                out.push(Diagnostic::error(
+                    SpannedNotTransposed,
+                    format!(
+                        "vertical-spanned {}x{} is not horizontal-spanned {}x{} transposed",
+                        v.width, v.height, h.width, h.height
+                    ),
+                ));
+            }
+        }
+
+        let multi_screen = self.dual_screen || self.foldable_screen;
+        if multi_screen && screen.vertical_spanned.is_none() {
+            out.push(Diagnostic::error(
+                MissingVerticalSpanned,
+                "dual-screen/foldable device has no vertical-spanned screen".to_string(),
+            ));
+        }
+        let has_spanned = screen.vertical_spanned.is_some() || screen.horizontal_spanned.is_some();
+        if !multi_screen && has_spanned {
+            out.push(Diagnostic::warning(
+                UnexpectedSpanned,
+                "spanned screen on a device that is neither dual-screen nor foldable".to_string(),
+            ));
+        }
+
+        for capability in self.capabilities.unknown() {
+            out.push(Diagnostic::warning(
+                UnknownCapability,
+                format!("unknown capability \"{capability}\""),
+            ));
+        }
+
+        if let Some(metadata) = &self.user_agent_metadata {
+            let mobile = self.capabilities.mobile();
+            // DevTools' Android tablets do this on purpose: a mobile viewport with
+            // `Sec-CH-UA-Mobile: ?0`, so this is only a warning.
+            if mobile != metadata.mobile {
+                out.push(Diagnostic::warning(
+                    MobileMismatch,
+                    format!(
+                        "capabilities say mobile={mobile} but user-agent-metadata says mobile={}",
+                        metadata.mobile
+                    ),
+                ));
+            }
+            if let Some(platform) = ua_platform(&self.user_agent) {
+                if !platform.eq_ignore_ascii_case(&metadata.platform) {
+                    out.push(Diagnostic::error(
+                        PlatformMismatch,
+                        format!(
+                            "user-agent platform is {platform} but user-agent-metadata says \"{}\"",
+                            metadata.platform
+                        ),
+                    ));
+                }
+            }
+        }
+        out
+    }
+}
+
+/// `validate()` for every device plus cross-device checks: duplicate titles
+/// (which make title lookups ambiguous) and duplicate `order` values.
+pub fn lint_devices(devices: &[EmulatedDevice]) -> Vec<DeviceDiagnostic> {
+    let mut out = Vec::new();
+    let located = |index: usize, diagnostic: Diagnostic| DeviceDiagnostic {
+        index,
+        title: devices[index].title.clone(),
+        diagnostic,
+    };
+
+    for (index, device) in devices.iter().enumerate() {
+        out.extend(device.validate().into_iter().map(|d| located(index, d)));
+    }
+
+    let mut titles: HashMap<&str, usize> = HashMap::new();
+    let mut orders: HashMap<i32, usize> = HashMap::new();
+    for (index, device) in devices.iter().enumerate() {
+        if let Some(&first) = titles.get(device.title.as_str()) {
+            out.push(located(
+                index,
+                Diagnostic::error(
+                    DiagnosticCode::DuplicateTitle,
+                    format!("title already used by device #{first}"),
+                ),
+            ));
+        } else {
+            titles.insert(&device.title, index);
+        }
+        if let Some(order) = device.order {
+            if let Some(&first) = orders.get(&order) {
+                out.push(located(
+                    index,
+                    Diagnostic::warning(
+                        DiagnosticCode::DuplicateOrder,
+                        format!("order {order} already used by device #{first}"),
+                    ),
+                ));
+            } else {
+                orders.insert(order, index);
+            }
+        }
+    }
+    out.sort_by_key(|d| d.index);
+    out
+}
+
+impl DeviceRegistry {
+    pub fn lint(&self) -> Vec<DeviceDiagnostic> {
+        lint_devices(self.devices())
+    }
+}
+
+fn is_transposed(a: &Orientation, b: &Orientation) -> bool {
+    a.width == b.height && a.height == b.width
+}
+
+fn is_same_size(a: &Orientation, b: &Orientation) -> bool {
+    a.width == b.width && a.height == b.height && a.width != a.height
+}
+
+// The `Sec-CH-UA-Platform` value Chrome would report for this UA string, if
+// the UA names a platform we recognize.
+fn ua_platform(user_agent: &str) -> Option<&'static str> {
+    let platform = user_agent.split_once('(')?.1.split_once(')')?.0;
+    if platform.contains("Android") {
+        Some("Android")
+    } else if platform.contains("CrOS") {
+        Some("Chrome OS")
+    } else if platform.contains("Windows") {
+        Some("Windows")
+    } else if platform.contains("Macintosh") {
+        Some("macOS")
+    } else if platform.contains("iPhone") || platform.contains("iPad") {
+        Some("iOS")
+    } else if platform.contains("Linux") {
+        Some("Linux")
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device_struct::Capabilities;
+    use DiagnosticCode::*;
+
+    fn pixel() -> EmulatedDevice {
+        DeviceRegistry::embedded().get("Pixel 3 XL").unwrap().clone()
+    }
+
+    fn foldable() -> EmulatedDevice {
+        DeviceRegistry::embedded().get("Galaxy Z Fold 5").unwrap().clone()
+    }
+
+    fn codes(device: &EmulatedDevice) -> Vec<(Severity, DiagnosticCode)> {
+        device.validate().into_iter().map(|d| (d.severity, d.code)).collect()
+    }
+
+    #[test]
+    fn embedded_registry_only_error_is_duplicate_ipad_pro() {
+        let lint = DeviceRegistry::embedded().lint();
+        let errors: Vec<_> =
+            lint.iter().filter(|d| d.diagnostic.severity == Severity::Error).collect();
+        assert_eq!(errors.len(), 1, "{errors:?}");
+        assert_eq!(errors[0].title, "iPad Pro");
+        assert_eq!(errors[0].diagnostic.code, DuplicateTitle);
+
+        // The rest are DevTools' deliberate quirks: non-rotating smart
+        // displays and Android tablets with a desktop-class Sec-CH-UA-Mobile.
+        assert!(lint
+            .iter()
+            .filter(|d| d.diagnostic.severity == Severity::Warning)
+            .all(|d| matches!(d.diagnostic.code, VerticalNotTransposed | MobileMismatch)));
+    }
+
+    #[test]
+    fn valid_devices_have_no_diagnostics() {
+        assert_eq!(codes(&pixel()), []);
+        assert_eq!(codes(&foldable()), []);
+    }
+
+    #[test]
+    fn empty_title_and_non_positive_size() {
+        let mut device = pixel();
+        device.title = "  ".into();
+        device.screen.horizontal.height = 0;
+        device.screen.vertical.width = 0;
+        assert_eq!(
+            codes(&device),
+            [
+                (Severity::Error, EmptyTitle),
+                (Severity::Error, NonPositiveSize),
+                (Severity::Error, NonPositiveSize)
+            ]
+        );
+    }
+
+    #[test]
+    fn non_positive_device_pixel_ratio() {
+        for dpr in [0.0, -1.0, f64::NAN] {
+            let mut device = pixel();
+            device.screen.device_pixel_ratio = dpr;
+            assert_eq!(codes(&device), [(Severity::Error, InvalidDevicePixelRatio)], "{dpr}");
+        }
+    }
+
+    #[test]
+    fn vertical_not_transposed() {
+        let mut device = pixel();
+        device.screen.vertical.height += 1;
+        assert_eq!(codes(&device), [(Severity::Error, VerticalNotTransposed)]);
+
+        // Identical orientations mean the device cannot rotate: only a warning.
+        let mut device = pixel();
+        device.screen.vertical = device.screen.horizontal.clone();
+        assert_eq!(codes(&device), [(Severity::Warning, VerticalNotTransposed)]);
+    }
+
+    #[test]
+    fn spanned_screens() {
+        let mut device = foldable();
+        device.screen.horizontal_spanned.as_mut().unwrap().width += 1;
+        assert_eq!(codes(&device), [(Severity::Error, SpannedNotTransposed)]);
+
+        let mut device = foldable();
+        device.foldable_screen = false;
+        device.dual_screen = true;
+        device.screen.vertical_spanned = None;
+        assert_eq!(codes(&device), [(Severity::Error, MissingVerticalSpanned)]);
+
+        let mut device = foldable();
+        device.foldable_screen = false;
+        assert_eq!(codes(&device), [(Severity::Warning, UnexpectedSpanned)]);
+    }
+
+    #[test]
+    fn metadata_mismatches() {
+        let mut device = pixel();
+        device.user_agent_metadata.as_mut().unwrap().mobile = false;
+        assert_eq!(codes(&device), [(Severity::Warning, MobileMismatch)]);
+
+        let mut device = pixel();
+        device.user_agent_metadata.as_mut().unwrap().platform = "iOS".into();
+        assert_eq!(codes(&device), [(Severity::Error, PlatformMismatch)]);
+    }
+
+    #[test]
+    fn unknown_capability() {
+        let mut device = pixel();
+        device.capabilities = serde_json::from_value::<Capabilities>(serde_json::json!([
+            "touch", "mobile", "stylus"
+        ]))
+        .unwrap();
+        let diagnostics = device.validate();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, UnknownCapability);
+        assert!(diagnostics[0].message.contains("stylus"));
+    }
+
+    #[test]
+    fn duplicate_title_and_order() {
+        let mut second = pixel();
+        second.title = "Pixel 3 XL".into();
+        let lint = lint_devices(&[pixel(), second]);
+        let found: Vec<_> = lint.iter().map(|d| (d.index, d.diagnostic.code)).collect();
+        assert_eq!(found, [(1, DuplicateTitle), (1, DuplicateOrder)]);
+        assert_eq!(
+            lint[0].to_string(),
+            "#1 \"Pixel 3 XL\": error [DuplicateTitle]: title already used by device #0"
+        );
+    }
+}
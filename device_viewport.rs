@@ -0,0 +1,284 @@
+use http::header::{HeaderMap, HeaderName, HeaderValue};
+
+use crate::device_export::{DeviceOrientation, ScreenOrientation};
+use crate::device_struct::{EmulatedDevice, Insets, Mode, Orientation};
+
+// Screen and viewport numbers for one posture of a device, computed the way
+// DevTools' DeviceModeModel does: the screen is the orientation's size, the
+// page viewport is the screen minus the active mode's insets (status and
+// navigation bars), and physical pixels are CSS pixels times the DPR.
+//
+// Everything a page can observe (`window.screen`, `innerWidth`,
+// `devicePixelRatio`) and the viewport client hints come from the same
+// `ScreenMetrics`, so they cannot disagree.
+
+pub const SEC_CH_VIEWPORT_WIDTH: HeaderName = HeaderName::from_static("sec-ch-viewport-width");
+pub const SEC_CH_DPR: HeaderName = HeaderName::from_static("sec-ch-dpr");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Posture {
+    Portrait,
+    Landscape,
+    VerticalSpanned,
+    HorizontalSpanned,
+}
+
+impl Posture {
+    pub const ALL: [Posture; 4] = [
+        Posture::Portrait,
+        Posture::Landscape,
+        Posture::VerticalSpanned,
+        Posture::HorizontalSpanned,
+    ];
+
+    /// The `orientation` name DevTools uses in `screen` keys and `modes`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Posture::Portrait => "vertical",
+            Posture::Landscape => "horizontal",
+            Posture::VerticalSpanned => "vertical-spanned",
+            Posture::HorizontalSpanned => "horizontal-spanned",
+        }
+    }
+
+    /// Spanned postures rotate like their unspanned counterparts.
+    pub fn device_orientation(self) -> DeviceOrientation {
+        match self {
+            Posture::Portrait | Posture::VerticalSpanned => DeviceOrientation::Portrait,
+            Posture::Landscape | Posture::HorizontalSpanned => DeviceOrientation::Landscape,
+        }
+    }
+
+    /// `screen.orientation.type` and `.angle`.
+    pub fn screen_orientation(self) -> ScreenOrientation {
+        let (orientation_type, angle) = match self.device_orientation() {
+            DeviceOrientation::Portrait => ("portraitPrimary", 0),
+            DeviceOrientation::Landscape => ("landscapePrimary", 90),
+        };
+        ScreenOrientation { orientation_type: orientation_type.to_string(), angle }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScreenMetrics {
+    pub posture: Posture,
+    /// `screen.width` / `screen.height` (and `availWidth` / `availHeight`).
+    pub screen_width: i32,
+    pub screen_height: i32,
+    /// `innerWidth` / `innerHeight`; the width is also `Sec-CH-Viewport-Width`.
+    pub viewport_width: i32,
+    pub viewport_height: i32,
+    /// Top-left corner of the viewport on the screen (CDP `positionX`/`Y`).
+    pub viewport_x: i32,
+    pub viewport_y: i32,
+    /// Screen size in device pixels.
+    pub physical_width: i32,
+    pub physical_height: i32,
+    /// `devicePixelRatio`, also `Sec-CH-DPR`.
+    pub device_pixel_ratio: f64,
+    pub orientation: ScreenOrientation,
+    /// Screen area the page cannot draw under, from the device mode's insets.
+    pub safe_area_insets: Insets,
+    /// Device frame around the screen, from the orientation's outline. Not
+    /// visible to the page; only used to draw the device bezel.
+    pub outline_insets: Insets,
+}
+
+impl EmulatedDevice {
+    /// Postures this device defines a screen for, in `Posture::ALL` order.
+    pub fn postures(&self) -> Vec<Posture> {
+        Posture::ALL.into_iter().filter(|p| self.orientation_for(*p).is_some()).collect()
+    }
+
+    pub fn orientation_for(&self, posture: Posture) -> Option<&Orientation> {
+        match posture {
+            Posture::Portrait => Some(&self.screen.vertical),
+            Posture::Landscape => Some(&self.screen.horizontal),
+            Posture::VerticalSpanned => self.screen.vertical_spanned.as_ref(),
+            Posture::HorizontalSpanned => self.screen.horizontal_spanned.as_ref(),
+        }
+    }
+
+    /// Modes for a posture; the first one is what DevTools selects on rotation.
+    pub fn modes_for(&self, posture: Posture) -> impl Iterator<Item = &Mode> {
+        self.modes.iter().filter(move |m| m.orientation == posture.as_str())
+    }
+
+    /// Metrics in the posture's default mode, or `None` if the device has no
+    /// screen for that posture (only foldables have spanned ones).
+    pub fn screen_metrics(&self, posture: Posture) -> Option<ScreenMetrics> {
+        let mode = self.modes_for(posture).next();
+        self.metrics(posture, mode)
+    }
+
+    /// Metrics in a specific mode (e.g. "keyboard up"). `None` if the mode's
+    /// orientation is not a posture this device has.
+    pub fn screen_metrics_in_mode(&self, mode: &Mode) -> Option<ScreenMetrics> {
+        let posture = Posture::ALL.into_iter().find(|p| p.as_str() == mode.orientation)?;
+        self.metrics(posture, Some(mode))
+    }
+
+    fn metrics(&self, posture: Posture, mode: Option<&Mode>) -> Option<ScreenMetrics> {
+        let orientation = self.orientation_for(posture)?;
+        let dpr = self.screen.device_pixel_ratio;
+        let insets = mode.and_then(|m| m.insets).unwrap_or_default();
+        let outline_insets =
+            orientation.outline.as_ref().and_then(|o| o.insets).unwrap_or_default();
+        Some(ScreenMetrics {
+            posture,
+            screen_width: orientation.width,
+            screen_height: orientation.height,
+            viewport_width: (orientation.width - insets.left - insets.right).max(0),
+            viewport_height: (orientation.height - insets.top - insets.bottom).max(0),
+            viewport_x: insets.left,
+            viewport_y: insets.top,
+            physical_width: to_physical(orientation.width, dpr),
+            physical_height: to_physical(orientation.height, dpr),
+            device_pixel_ratio: dpr,
+            orientation: posture.screen_orientation(),
+            safe_area_insets: insets,
+            outline_insets,
+        })
+    }
+}
+
+impl ScreenMetrics {
+    /// `Sec-CH-Viewport-Width` and `Sec-CH-DPR`.
+    /// Chrome serializes the DPR as the shortest decimal, so 3.0 is "3".
+    pub fn append_viewport_hints(&self, headers: &mut HeaderMap) {
+        headers.insert(SEC_CH_VIEWPORT_WIDTH, HeaderValue::from(self.viewport_width));
+        let dpr = HeaderValue::from_str(&self.device_pixel_ratio.to_string())
+            .expect("a formatted f64 is a valid header value");
+        headers.insert(SEC_CH_DPR, dpr);
+    }
+}
+
+fn to_physical(css: i32, dpr: f64) -> i32 {
+    (f64::from(css) * dpr).round() as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device_export::SetDeviceMetricsOverride;
+    use crate::device_registry::DeviceRegistry;
+
+    fn device(title: &str) -> &'static EmulatedDevice {
+        DeviceRegistry::embedded().get(title).unwrap()
+    }
+
+    fn header<'a>(headers: &'a HeaderMap, name: &HeaderName) -> &'a str {
+        headers.get(name).unwrap().to_str().unwrap()
+    }
+
+    // window.screen, innerWidth/innerHeight, devicePixelRatio, the viewport
+    // hints and the CDP override all describe the same posture.
+    fn assert_consistent(device: &EmulatedDevice, metrics: &ScreenMetrics) {
+        let orientation = device.orientation_for(metrics.posture).unwrap();
+        let insets = metrics.safe_area_insets;
+        assert_eq!(
+            (metrics.screen_width, metrics.screen_height),
+            (orientation.width, orientation.height)
+        );
+        assert_eq!(metrics.viewport_width, metrics.screen_width - insets.left - insets.right);
+        assert_eq!(metrics.viewport_height, metrics.screen_height - insets.top - insets.bottom);
+        assert_eq!((metrics.viewport_x, metrics.viewport_y), (insets.left, insets.top));
+        assert_eq!(metrics.device_pixel_ratio, device.screen.device_pixel_ratio);
+        assert_eq!(metrics.orientation, metrics.posture.screen_orientation());
+
+        let mut headers = HeaderMap::new();
+        metrics.append_viewport_hints(&mut headers);
+        assert_eq!(headers.len(), 2);
+        assert_eq!(header(&headers, &SEC_CH_VIEWPORT_WIDTH), metrics.viewport_width.to_string());
+        let dpr: f64 = header(&headers, &SEC_CH_DPR).parse().unwrap();
+        assert_eq!(dpr, metrics.device_pixel_ratio);
+
+        let cdp =
+            SetDeviceMetricsOverride::from_screen_metrics(metrics, device.capabilities.mobile());
+        assert_eq!((cdp.width, cdp.height), (metrics.viewport_width, metrics.viewport_height));
+        assert_eq!(cdp.screen_width, Some(metrics.screen_width));
+        assert_eq!(cdp.screen_height, Some(metrics.screen_height));
+        assert_eq!((cdp.position_x, cdp.position_y), (Some(insets.left), Some(insets.top)));
+        assert_eq!(cdp.device_scale_factor, dpr);
+        assert_eq!(cdp.orientation(), metrics.posture.device_orientation());
+    }
+
+    #[test]
+    fn every_posture_of_every_device_is_consistent() {
+        for device in DeviceRegistry::embedded().devices() {
+            for posture in device.postures() {
+                let metrics = device.screen_metrics(posture).unwrap();
+                assert_eq!(metrics.posture, posture);
+                assert_consistent(device, &metrics);
+            }
+            for mode in &device.modes {
+                assert_consistent(device, &device.screen_metrics_in_mode(mode).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn foldable_spanned_postures() {
+        let fold = device("Galaxy Z Fold 5");
+        assert_eq!(fold.postures(), Posture::ALL);
+
+        let spanned = fold.screen_metrics(Posture::VerticalSpanned).unwrap();
+        assert_eq!((spanned.screen_width, spanned.screen_height), (690, 829));
+        assert_eq!((spanned.physical_width, spanned.physical_height), (1811, 2176));
+        assert_eq!(spanned.orientation.orientation_type, "portraitPrimary");
+
+        let spanned = fold.screen_metrics(Posture::HorizontalSpanned).unwrap();
+        assert_eq!((spanned.screen_width, spanned.screen_height), (829, 690));
+        assert_eq!(spanned.orientation.angle, 90);
+
+        let phone = device("Pixel 7");
+        assert_eq!(phone.postures(), [Posture::Portrait, Posture::Landscape]);
+        assert_eq!(phone.screen_metrics(Posture::VerticalSpanned), None);
+    }
+
+    #[test]
+    fn fractional_device_pixel_ratio() {
+        let metrics = device("Pixel 7").screen_metrics(Posture::Portrait).unwrap();
+        assert_eq!(metrics.device_pixel_ratio, 2.625);
+        // 412 x 2.625 = 1081.5 and 915 x 2.625 = 2401.875
+        assert_eq!((metrics.physical_width, metrics.physical_height), (1082, 2402));
+
+        let mut headers = HeaderMap::new();
+        metrics.append_viewport_hints(&mut headers);
+        assert_eq!(header(&headers, &SEC_CH_DPR), "2.625");
+        assert_eq!(header(&headers, &SEC_CH_VIEWPORT_WIDTH), "412");
+
+        let mut headers = HeaderMap::new();
+        device("iPhone 12 Pro")
+            .screen_metrics(Posture::Portrait)
+            .unwrap()
+            .append_viewport_hints(&mut headers);
+        assert_eq!(header(&headers, &SEC_CH_DPR), "3");
+    }
+
+    #[test]
+    fn mode_insets_shrink_the_viewport() {
+        let nexus = device("Nexus 5");
+        let metrics = nexus.screen_metrics(Posture::Portrait).unwrap();
+        assert_eq!((metrics.screen_width, metrics.screen_height), (360, 640));
+        assert_eq!((metrics.viewport_width, metrics.viewport_height), (360, 567));
+        assert_eq!(metrics.viewport_y, 25);
+
+        let keyboard = nexus
+            .modes
+            .iter()
+            .find(|m| m.title == "keyboard" && m.orientation == "horizontal")
+            .unwrap();
+        let metrics = nexus.screen_metrics_in_mode(keyboard).unwrap();
+        assert_eq!(metrics.posture, Posture::Landscape);
+        assert_eq!((metrics.viewport_width, metrics.viewport_height), (598, 78));
+    }
+
+    #[test]
+    fn outline_is_not_visible_to_the_page() {
+        let iphone = device("iPhone 6/7/8");
+        let metrics = iphone.screen_metrics(Posture::Portrait).unwrap();
+        assert_eq!(metrics.outline_insets.top, 105);
+        assert_eq!((metrics.viewport_width, metrics.viewport_height), (375, 667));
+    }
+}
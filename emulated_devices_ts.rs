@@ -0,0 +1,420 @@
+use serde_json::{Map, Number, Value};
+use thiserror::Error;
+
+use crate::device_struct::EmulatedDevice;
+
+// Replaces extract_devices.py. Parses the `emulatedDevices` array literal of
+// front_end/models/emulation/EmulatedDevices.ts without evaluating TypeScript:
+// only the JSON-like subset DevTools uses is accepted (quoted or bare keys,
+// single/double quoted strings, comments, trailing commas).
+
+const ARRAY_NAME: &str = "emulatedDevices";
+
+#[derive(Debug, Error, PartialEq)]
+#[error("{line}:{column}: {message}")]
+pub struct ImportError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+/// Parses every entry of `emulatedDevices` into an `EmulatedDevice`.
+/// `%s` user-agent templates are kept as-is.
+pub fn import_emulated_devices(source: &str) -> Result<Vec<EmulatedDevice>, ImportError> {
+    let entries = parse_entries(source)?;
+    entries
+        .into_iter()
+        .map(|(offset, value)| {
+            serde_json::from_value(value)
+                .map_err(|e| error_at(source, offset, format!("invalid device entry: {e}")))
+        })
+        .collect()
+}
+
+/// The raw array as JSON, keeping fields `EmulatedDevice` does not model
+/// (spanned-screen `hinge`s), for regenerating `all_devices.json`.
+pub fn import_emulated_devices_json(source: &str) -> Result<Value, ImportError> {
+    let entries = parse_entries(source)?;
+    Ok(Value::Array(entries.into_iter().map(|(_, value)| value).collect()))
+}
+
+fn parse_entries(source: &str) -> Result<Vec<(usize, Value)>, ImportError> {
+    let start = find_array(source).ok_or_else(|| ImportError {
+        line: 1,
+        column: 1,
+        message: format!("could not find `{ARRAY_NAME}` array"),
+    })?;
+    let mut parser = Parser { src: source, pos: start };
+    parser.array_entries()
+}
+
+// Position of the `[` that opens `emulatedDevices = [`, allowing a type
+// annotation between the name and `=`.
+fn find_array(source: &str) -> Option<usize> {
+    let mut from = 0;
+    while let Some(found) = source[from..].find(ARRAY_NAME) {
+        let name_end = from + found + ARRAY_NAME.len();
+        let rest = &source[name_end..];
+        if let Some(eq) = rest.find('=') {
+            let before_eq = rest[..eq].trim();
+            let after_eq = rest[eq + 1..].trim_start();
+            if (before_eq.is_empty() || before_eq.starts_with(':')) && after_eq.starts_with('[') {
+                return Some(source.len() - after_eq.len());
+            }
+        }
+        from = name_end;
+    }
+    None
+}
+
+fn error_at(source: &str, offset: usize, message: String) -> ImportError {
+    let before = &source[..offset.min(source.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().map_or(0, |l| l.chars().count()) + 1;
+    ImportError { line, column, message }
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, message: impl Into<String>) -> ImportError {
+        error_at(self.src, self.pos, message.into())
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), ImportError> {
+        self.skip_trivia()?;
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.bump();
+                Ok(())
+            }
+            Some(c) => Err(self.error(format!("expected `{expected}`, found `{c}`"))),
+            None => Err(self.error(format!("expected `{expected}`, found end of file"))),
+        }
+    }
+
+    fn skip_trivia(&mut self) -> Result<(), ImportError> {
+        loop {
+            let rest = &self.src[self.pos..];
+            if rest.starts_with("//") {
+                self.pos += rest.find('\n').unwrap_or(rest.len());
+            } else if rest.starts_with("/*") {
+                let end =
+                    rest.find("*/").ok_or_else(|| self.error("unterminated block comment"))?;
+                self.pos += end + 2;
+            } else if self.peek().is_some_and(char::is_whitespace) {
+                self.bump();
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    // Top-level array; remembers where each entry starts for error reporting.
+    fn array_entries(&mut self) -> Result<Vec<(usize, Value)>, ImportError> {
+        self.expect('[')?;
+        let mut entries = Vec::new();
+        loop {
+            self.skip_trivia()?;
+            if self.peek() == Some(']') {
+                self.bump();
+                return Ok(entries);
+            }
+            let offset = self.pos;
+            entries.push((offset, self.value()?));
+            self.separator(']')?;
+        }
+    }
+
+    // After an element: either `,` (possibly trailing) or the closing bracket.
+    fn separator(&mut self, close: char) -> Result<(), ImportError> {
+        self.skip_trivia()?;
+        match self.peek() {
+            Some(',') => {
+                self.bump();
+                Ok(())
+            }
+            Some(c) if c == close => Ok(()),
+            Some(c) => Err(self.error(format!("expected `,` or `{close}`, found `{c}`"))),
+            None => Err(self.error(format!("expected `,` or `{close}`, found end of file"))),
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, ImportError> {
+        self.skip_trivia()?;
+        match self.peek() {
+            Some('{') => self.object(),
+            Some('[') => self.array(),
+            Some('\'' | '"') => self.string().map(Value::String),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.number(),
+            Some(c) if is_ident_start(c) => {
+                let start = self.pos;
+                let ident = self.identifier();
+                match ident {
+                    "true" => Ok(Value::Bool(true)),
+                    "false" => Ok(Value::Bool(false)),
+                    "null" | "undefined" => Ok(Value::Null),
+                    other => {
+                        Err(error_at(self.src, start, format!("unsupported expression `{other}`")))
+                    }
+                }
+            }
+            Some(c) => Err(self.error(format!("unexpected `{c}`"))),
+            None => Err(self.error("unexpected end of file")),
+        }
+    }
+
+    fn object(&mut self) -> Result<Value, ImportError> {
+        self.expect('{')?;
+        let mut map = Map::new();
+        loop {
+            self.skip_trivia()?;
+            let key = match self.peek() {
+                Some('}') => {
+                    self.bump();
+                    return Ok(Value::Object(map));
+                }
+                Some('\'' | '"') => self.string()?,
+                Some(c) if is_ident_start(c) => self.identifier().to_string(),
+                Some(c) => return Err(self.error(format!("expected property name, found `{c}`"))),
+                None => return Err(self.error("unterminated object")),
+            };
+            self.expect(':')?;
+            let value = self.value()?;
+            map.insert(key, value);
+            self.separator('}')?;
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, ImportError> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        loop {
+            self.skip_trivia()?;
+            if self.peek() == Some(']') {
+                self.bump();
+                return Ok(Value::Array(items));
+            }
+            items.push(self.value()?);
+            self.separator(']')?;
+        }
+    }
+
+    fn identifier(&mut self) -> &'a str {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| is_ident_start(c) || c.is_ascii_digit()) {
+            self.bump();
+        }
+        &self.src[start..self.pos]
+    }
+
+    fn string(&mut self) -> Result<String, ImportError> {
+        let start = self.pos;
+        let quote = self.bump().expect("caller checked for a quote");
+        let mut out = String::new();
+        loop {
+            match self.bump() {
+                Some(c) if c == quote => return Ok(out),
+                Some('\\') => match self.bump() {
+                    Some('n') => out.push('\n'),
+                    Some('t') => out.push('\t'),
+                    Some('r') => out.push('\r'),
+                    Some('0') => out.push('\0'),
+                    Some('\n') => {} // line continuation
+                    Some('u') => out.push(self.hex_escape(4)?),
+                    Some('x') => out.push(self.hex_escape(2)?),
+                    Some(c) => out.push(c),
+                    None => break,
+                },
+                Some('\n') | None => break,
+                Some(c) => out.push(c),
+            }
+        }
+        Err(error_at(self.src, start, "unterminated string".to_string()))
+    }
+
+    fn hex_escape(&mut self, digits: usize) -> Result<char, ImportError> {
+        let start = self.pos;
+        let hex =
+            self.src.get(start..start + digits).ok_or_else(|| self.error("truncated escape"))?;
+        let code = u32::from_str_radix(hex, 16).map_err(|_| self.error("invalid escape"))?;
+        self.pos += digits;
+        char::from_u32(code).ok_or_else(|| error_at(self.src, start, "invalid escape".to_string()))
+    }
+
+    fn number(&mut self) -> Result<Value, ImportError> {
+        let start = self.pos;
+        if self.peek() == Some('-') {
+            self.bump();
+        }
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-'))
+        {
+            self.bump();
+        }
+        let text = &self.src[start..self.pos];
+        let number = match text.parse::<i64>() {
+            Ok(int) => Some(Number::from(int)),
+            Err(_) => text.parse::<f64>().ok().and_then(Number::from_f64),
+        };
+        number
+            .map(Value::Number)
+            .ok_or_else(|| error_at(self.src, start, format!("invalid number `{text}`")))
+    }
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_' || c == '$'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Two entries as they appear in DevTools' EmulatedDevices.ts, kept in its
+    // formatting: single quotes, trailing commas, wrapped strings, comments.
+    const EXCERPT: &str = r#"
+// Copyright 2015 The Chromium Authors
+import * as Common from '../../core/common/common.js';
+
+/* eslint-disable @stylistic/quotes */
+const emulatedDevices = [
+  {
+    'order': 10,
+    'show-by-default': true,
+    'title': 'iPhone SE',
+    'screen': {
+      'horizontal': {
+        'width': 667,
+        'height': 375,
+      },
+      'device-pixel-ratio': 2,
+      'vertical': {
+        'width': 375,
+        'height': 667,
+      },
+    },
+    'capabilities': ['touch', 'mobile'],
+    'user-agent':
+        'Mozilla/5.0 (iPhone; CPU iPhone OS 18_5 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/18.5 Mobile/15E148 Safari/604.1',
+    'type': 'phone',
+  },
+  {
+    'order': 16,
+    'show-by-default': false,
+    'title': 'Pixel 3 XL',
+    'screen': {
+      'horizontal': {
+        'width': 786,
+        'height': 393,
+      },
+      'device-pixel-ratio': 2.75,
+      'vertical': {
+        'width': 393,
+        'height': 786,
+      },
+    },
+    'capabilities': ['touch', 'mobile'],
+    'user-agent':
+        'Mozilla/5.0 (Linux; Android 11; Pixel 3) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/%s Mobile Safari/537.36',
+    'user-agent-metadata': {'platform': 'Android', 'platformVersion': '11', 'architecture': '', 'model': 'Pixel 3', 'mobile': true},
+    'type': 'phone',
+  },
+];
+"#;
+
+    fn embedded_json() -> Vec<Value> {
+        serde_json::from_str(include_str!("all_devices.json")).unwrap()
+    }
+
+    fn import_err(source: &str) -> ImportError {
+        import_emulated_devices_json(source).unwrap_err()
+    }
+
+    #[test]
+    fn excerpt_matches_all_devices_json() {
+        let imported = import_emulated_devices_json(EXCERPT).unwrap();
+        let imported = imported.as_array().unwrap();
+        assert_eq!(imported.len(), 2);
+
+        let embedded = embedded_json();
+        for entry in imported {
+            let expected = embedded.iter().find(|d| d["title"] == entry["title"]).unwrap();
+            assert_eq!(entry, expected, "{}", entry["title"]);
+        }
+
+        let devices = import_emulated_devices(EXCERPT).unwrap();
+        assert_eq!(devices[0].title, "iPhone SE");
+        assert_eq!(devices[1].user_agent_metadata.as_ref().unwrap().model, "Pixel 3");
+    }
+
+    #[test]
+    fn accepts_ts_syntax() {
+        let source = r#"
+            export const emulatedDevices: EmulatedDeviceJSON[] = [
+              // line comment
+              {title: 'single', "order": 1, /* block
+                 comment */ 'flags': [true, false, null,],},
+              {'title': "double \"quoted\" A",},
+            ];
+        "#;
+        let value = import_emulated_devices_json(source).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!([
+                {"title": "single", "order": 1, "flags": [true, false, null]},
+                {"title": "double \"quoted\" A"},
+            ])
+        );
+    }
+
+    #[test]
+    fn keeps_user_agent_templates() {
+        let devices = import_emulated_devices(EXCERPT).unwrap();
+        assert!(devices[1].user_agent.contains("Chrome/%s Mobile"));
+    }
+
+    #[test]
+    fn reports_line_and_column() {
+        let unterminated = "const emulatedDevices = [\n  {'title': 'oops\n  },\n];";
+        assert_eq!(
+            import_err(unterminated),
+            ImportError { line: 2, column: 13, message: "unterminated string".into() }
+        );
+
+        let bad_token = "const emulatedDevices = [\n  {'order': 1 'title': 'x'},\n];";
+        let err = import_err(bad_token);
+        assert_eq!((err.line, err.column), (2, 15));
+        assert_eq!(err.message, "expected `,` or `}`, found `'`");
+
+        let identifier = "const emulatedDevices = [\n  {'title': kTitle},\n];";
+        let err = import_err(identifier);
+        assert_eq!((err.line, err.column), (2, 13));
+        assert_eq!(err.message, "unsupported expression `kTitle`");
+
+        let err = import_err("const devices = [];");
+        assert_eq!((err.line, err.column), (1, 1));
+    }
+
+    #[test]
+    fn invalid_entry_points_at_entry_start() {
+        let source = "const emulatedDevices = [\n  {'title': 1},\n];";
+        let err = import_emulated_devices(source).unwrap_err();
+        assert_eq!((err.line, err.column), (2, 3));
+        assert!(err.message.starts_with("invalid device entry"), "{}", err.message);
+    }
+}
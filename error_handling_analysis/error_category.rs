@@ -0,0 +1,409 @@
+use crate::rust_errors::HttpError;
+
+// Classification of HttpError, mirroring the helpers in net/base/net_errors.cc
+// and the retry rules of HttpNetworkTransaction::HandleIOError.
+
+/// `HttpNetworkTransaction::kMaxRetryAttempts`.
+pub const MAX_RETRY_ATTEMPTS: u32 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCategory {
+    /// -1..-99: not specific to networking (`ERR_FAILED`, `ERR_ABORTED`).
+    Generic,
+    Connection,
+    Tls,
+    Certificate,
+    Http,
+    Proxy,
+    Dns,
+    Cache,
+    Quic,
+    Http2,
+    /// -500 and -700 ranges, and unknown codes.
+    Other,
+}
+
+impl ErrorCategory {
+    pub const ALL: [ErrorCategory; 11] = [
+        ErrorCategory::Generic,
+        ErrorCategory::Connection,
+        ErrorCategory::Tls,
+        ErrorCategory::Certificate,
+        ErrorCategory::Http,
+        ErrorCategory::Proxy,
+        ErrorCategory::Dns,
+        ErrorCategory::Cache,
+        ErrorCategory::Quic,
+        ErrorCategory::Http2,
+        ErrorCategory::Other,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCategory::Generic => "generic",
+            ErrorCategory::Connection => "connection",
+            ErrorCategory::Tls => "tls",
+            ErrorCategory::Certificate => "certificate",
+            ErrorCategory::Http => "http",
+            ErrorCategory::Proxy => "proxy",
+            ErrorCategory::Dns => "dns",
+            ErrorCategory::Cache => "cache",
+            ErrorCategory::Quic => "quic",
+            ErrorCategory::Http2 => "http2",
+            ErrorCategory::Other => "other",
+        }
+    }
+}
+
+/// What `HandleIOError` does with an error on an in-flight request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryAction {
+    /// Reset the connection and send the request again.
+    Resend,
+    /// Resend with TLS early data disabled.
+    ResendWithoutEarlyData,
+    /// Surface the error to the caller.
+    Fail,
+}
+
+/// The transaction state `HandleIOError` looks at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RetryContext {
+    /// The stream ran on a keep-alive socket (`IsConnectionReused`).
+    pub connection_reused: bool,
+    /// Response headers were already received.
+    pub received_headers: bool,
+    /// Retries already spent on HTTP/2 and QUIC errors.
+    pub retry_attempts: u32,
+}
+
+impl HttpError {
+    /// Ranges from net_error_list.h, with the -100 and -300 ranges split
+    /// further by protocol.
+    pub fn category(&self) -> ErrorCategory {
+        use HttpError::*;
+        match self {
+            SslProtocolError
+            | SslClientAuthCertNeeded
+            | SslVersionOrCipherMismatch
+            | SslRenegotiationRequested
+            | BadSslClientAuthCert
+            | AlpnNegotiationFailed
+            | SslNoRenegotiation
+            | SslDecompressionFailureAlert
+            | SslBadRecordMacAlert
+            | SslClientAuthPrivateKeyAccessDenied
+            | SslClientAuthCertNoPrivateKey
+            | SslClientAuthSignatureFailed
+            | SslHandshakeNotCompleted
+            | SslBadPeerPublicKey
+            | ClientAuthCertTypeUnsupported
+            | SslDecryptErrorAlert
+            | SslServerCertChanged
+            | SslUnrecognizedNameAlert
+            | SslClientAuthCertBadFormat
+            | SslServerCertBadFormat
+            | SslObsoleteCipher
+            | SslClientAuthNoCommonAlgorithms
+            | EarlyDataRejected
+            | WrongVersionOnEarlyData
+            | Tls13DowngradeDetected
+            | SslKeyUsageIncompatible
+            | InvalidEchConfigList
+            | EchNotNegotiated
+            | EchFallbackCertificateInvalid => ErrorCategory::Tls,
+
+            SslPinnedKeyNotInCertChain
+            | CtSthParsingFailed
+            | CtSthIncomplete
+            | CtConsistencyProofParsingFailed => ErrorCategory::Certificate,
+
+            TunnelConnectionFailed
+            | ProxyAuthUnsupported
+            | SocksConnectionFailed
+            | SocksConnectionHostUnreachable
+            | ProxyAuthRequested
+            | ProxyConnectionFailed
+            | MandatoryProxyConfigurationFailed
+            | ProxyCertificateInvalid
+            | UnableToReuseConnectionForProxyAuth
+            | ProxyUnableToConnectToDestination
+            | ProxyDelegateCanceledConnectRequest
+            | ProxyDelegateCanceledConnectResponse
+            | UnexpectedProxyAuth
+            | PacScriptFailed
+            | NoSupportedProxies
+            | PacNotInDhcp
+            | ProxyAuthRequestedWithNoConnection
+            | ProxyHttp11Required
+            | PacScriptTerminated
+            | ProxyRequired => ErrorCategory::Proxy,
+
+            NameNotResolved
+            | HostResolverQueueTooLarge
+            | NameResolutionFailed
+            | IcannNameCollision => ErrorCategory::Dns,
+
+            Http2ProtocolError
+            | IncompleteHttp2Headers
+            | Http2ServerRefusedStream
+            | Http2PingFailed
+            | Http2InadequateTransportSecurity
+            | Http2FlowControlError
+            | Http2FrameSizeError
+            | Http2CompressionError
+            | Http11Required
+            | Http2RstStreamNoErrorReceived
+            | Http2PushedStreamNotAvailable
+            | Http2ClaimedPushedStreamResetByServer
+            | Http2StreamClosed
+            | Http2ClientRefusedStream
+            | Http2PushedResponseDoesNotMatch => ErrorCategory::Http2,
+
+            QuicProtocolError
+            | QuicHandshakeFailed
+            | QuicCertRootNotKnown
+            | QuicGoawayRequestCanBeRetried => ErrorCategory::Quic,
+
+            other => match other.as_i32() {
+                -99..=-1 => ErrorCategory::Generic,
+                -199..=-100 => ErrorCategory::Connection,
+                -299..=-200 => ErrorCategory::Certificate,
+                -399..=-300 => ErrorCategory::Http,
+                -499..=-400 => ErrorCategory::Cache,
+                -899..=-800 => ErrorCategory::Dns,
+                _ => ErrorCategory::Other,
+            },
+        }
+    }
+
+    /// `IsCertificateError`: `ERR_CERT_BEGIN` (-200) down to, but excluding,
+    /// `ERR_CERT_END` (-220), plus `ERR_SSL_PINNED_KEY_NOT_IN_CERT_CHAIN`.
+    pub fn is_certificate_error(&self) -> bool {
+        let code = self.as_i32();
+        (code <= HttpError::CertCommonNameInvalid.as_i32() && code > HttpError::CertEnd.as_i32())
+            || *self == HttpError::SslPinnedKeyNotInCertChain
+    }
+
+    /// `IsClientCertificateError`: the client certificate or its key is
+    /// unusable, as opposed to the server asking for one.
+    pub fn is_client_certificate_error(&self) -> bool {
+        matches!(
+            self,
+            HttpError::BadSslClientAuthCert
+                | HttpError::SslClientAuthPrivateKeyAccessDenied
+                | HttpError::SslClientAuthCertNoPrivateKey
+                | HttpError::SslClientAuthSignatureFailed
+                | HttpError::SslClientAuthNoCommonAlgorithms
+        )
+    }
+
+    /// `IsHostnameResolutionError`. Like Chromium, only `ERR_NAME_NOT_RESOLVED`;
+    /// `ERR_NAME_RESOLUTION_FAILED` is a resolver-side failure (no usable DNS
+    /// configuration) rather than a name that does not exist.
+    pub fn is_hostname_resolution_error(&self) -> bool {
+        *self == HttpError::NameNotResolved
+    }
+
+    pub fn is_proxy_error(&self) -> bool {
+        self.category() == ErrorCategory::Proxy
+    }
+
+    pub fn is_dns_error(&self) -> bool {
+        self.category() == ErrorCategory::Dns
+    }
+
+    /// Errors that mean a keep-alive socket was closed by the server while we
+    /// were reusing it. `HandleIOError` resends these when
+    /// `ShouldResendRequest()` holds, see `retry_action`.
+    pub fn is_retryable_on_reused_socket(&self) -> bool {
+        matches!(
+            self,
+            HttpError::ConnectionReset
+                | HttpError::ConnectionClosed
+                | HttpError::ConnectionAborted
+                | HttpError::SocketNotConnected
+                | HttpError::EmptyResponse
+        )
+    }
+
+    /// `HttpNetworkTransaction::HandleIOError`.
+    pub fn retry_action(&self, context: RetryContext) -> RetryAction {
+        match self {
+            // ShouldResendRequest(): only on a proven (reused) connection, and
+            // only before headers arrived, so a fresh socket cannot loop.
+            e if e.is_retryable_on_reused_socket() => {
+                if context.connection_reused && !context.received_headers {
+                    RetryAction::Resend
+                } else {
+                    RetryAction::Fail
+                }
+            }
+            HttpError::EarlyDataRejected | HttpError::WrongVersionOnEarlyData => {
+                RetryAction::ResendWithoutEarlyData
+            }
+            HttpError::Http2PingFailed
+            | HttpError::Http2ServerRefusedStream
+            | HttpError::QuicHandshakeFailed
+            | HttpError::QuicGoawayRequestCanBeRetried => {
+                if context.retry_attempts < MAX_RETRY_ATTEMPTS {
+                    RetryAction::Resend
+                } else {
+                    RetryAction::Fail
+                }
+            }
+            // Chromium also marks the QUIC alternative service broken, so the
+            // resend goes over TCP; that is the caller's to do here.
+            HttpError::QuicProtocolError => {
+                if !context.received_headers && context.retry_attempts < MAX_RETRY_ATTEMPTS {
+                    RetryAction::Resend
+                } else {
+                    RetryAction::Fail
+                }
+            }
+            _ => RetryAction::Fail,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRESH: RetryContext =
+        RetryContext { connection_reused: false, received_headers: false, retry_attempts: 0 };
+    const REUSED: RetryContext = RetryContext { connection_reused: true, ..FRESH };
+
+    #[test]
+    fn range_edges() {
+        for (code, expected) in [
+            (-1, ErrorCategory::Generic),
+            (-99, ErrorCategory::Generic),
+            (-100, ErrorCategory::Connection),
+            (-199, ErrorCategory::Connection),
+            (-200, ErrorCategory::Certificate),
+            (-299, ErrorCategory::Certificate),
+            (-300, ErrorCategory::Http),
+            (-399, ErrorCategory::Http),
+            (-400, ErrorCategory::Cache),
+            (-499, ErrorCategory::Cache),
+            (-500, ErrorCategory::Other),
+            (-799, ErrorCategory::Other),
+            (-800, ErrorCategory::Dns),
+            (-899, ErrorCategory::Dns),
+            (-900, ErrorCategory::Other),
+            (0, ErrorCategory::Other),
+            (1, ErrorCategory::Other),
+        ] {
+            assert_eq!(HttpError::from_i32(code).category(), expected, "{code}");
+        }
+    }
+
+    #[test]
+    fn protocol_splits_override_ranges() {
+        for (error, expected) in [
+            (HttpError::SslProtocolError, ErrorCategory::Tls),
+            (HttpError::TunnelConnectionFailed, ErrorCategory::Proxy),
+            (HttpError::NameNotResolved, ErrorCategory::Dns),
+            (HttpError::SslPinnedKeyNotInCertChain, ErrorCategory::Certificate),
+            (HttpError::PacScriptFailed, ErrorCategory::Proxy),
+            (HttpError::Http2ProtocolError, ErrorCategory::Http2),
+            (HttpError::QuicProtocolError, ErrorCategory::Quic),
+            (HttpError::ConnectionReset, ErrorCategory::Connection),
+        ] {
+            assert_eq!(error.category(), expected, "{error:?}");
+        }
+    }
+
+    #[test]
+    fn every_category_is_reachable() {
+        for category in ErrorCategory::ALL {
+            assert!(
+                HttpError::ALL.iter().any(|e| e.category() == category),
+                "{}",
+                category.as_str()
+            );
+        }
+    }
+
+    #[test]
+    fn predicates() {
+        assert!(HttpError::CertCommonNameInvalid.is_certificate_error());
+        assert!(HttpError::CertSelfSignedLocalNetwork.is_certificate_error());
+        assert!(HttpError::SslPinnedKeyNotInCertChain.is_certificate_error());
+        assert!(!HttpError::CertEnd.is_certificate_error());
+        assert!(!HttpError::SslProtocolError.is_certificate_error());
+
+        assert!(HttpError::BadSslClientAuthCert.is_client_certificate_error());
+        assert!(!HttpError::SslClientAuthCertNeeded.is_client_certificate_error());
+
+        assert!(HttpError::NameNotResolved.is_hostname_resolution_error());
+        assert!(!HttpError::NameResolutionFailed.is_hostname_resolution_error());
+
+        assert!(HttpError::PacScriptFailed.is_proxy_error());
+        assert!(!HttpError::ConnectionRefused.is_proxy_error());
+        assert!(HttpError::DnsTimedOut.is_dns_error());
+        assert!(HttpError::NameNotResolved.is_dns_error());
+        assert!(!HttpError::TimedOut.is_dns_error());
+
+        assert!(HttpError::EmptyResponse.is_retryable_on_reused_socket());
+        assert!(!HttpError::ConnectionRefused.is_retryable_on_reused_socket());
+    }
+
+    #[test]
+    fn reused_socket_errors_resend_only_before_headers() {
+        let headers = RetryContext { received_headers: true, ..REUSED };
+        for error in [
+            HttpError::ConnectionReset,
+            HttpError::ConnectionClosed,
+            HttpError::ConnectionAborted,
+            HttpError::SocketNotConnected,
+            HttpError::EmptyResponse,
+        ] {
+            assert_eq!(error.retry_action(REUSED), RetryAction::Resend, "{error:?}");
+            assert_eq!(error.retry_action(FRESH), RetryAction::Fail, "{error:?}");
+            assert_eq!(error.retry_action(headers), RetryAction::Fail, "{error:?}");
+        }
+    }
+
+    #[test]
+    fn early_data_rejection_resends_without_early_data() {
+        for error in [HttpError::EarlyDataRejected, HttpError::WrongVersionOnEarlyData] {
+            assert_eq!(error.retry_action(FRESH), RetryAction::ResendWithoutEarlyData);
+            assert_eq!(error.retry_action(REUSED), RetryAction::ResendWithoutEarlyData);
+        }
+    }
+
+    #[test]
+    fn http2_and_quic_errors_resend_up_to_the_limit() {
+        let exhausted = RetryContext { retry_attempts: MAX_RETRY_ATTEMPTS, ..FRESH };
+        let last = RetryContext { retry_attempts: MAX_RETRY_ATTEMPTS - 1, ..FRESH };
+        for error in [
+            HttpError::Http2PingFailed,
+            HttpError::Http2ServerRefusedStream,
+            HttpError::QuicHandshakeFailed,
+            HttpError::QuicGoawayRequestCanBeRetried,
+        ] {
+            assert_eq!(error.retry_action(FRESH), RetryAction::Resend, "{error:?}");
+            assert_eq!(error.retry_action(last), RetryAction::Resend, "{error:?}");
+            assert_eq!(error.retry_action(exhausted), RetryAction::Fail, "{error:?}");
+        }
+    }
+
+    #[test]
+    fn quic_protocol_error_resends_only_before_headers() {
+        let error = HttpError::QuicProtocolError;
+        assert_eq!(error.retry_action(FRESH), RetryAction::Resend);
+        let headers = RetryContext { received_headers: true, ..FRESH };
+        assert_eq!(error.retry_action(headers), RetryAction::Fail);
+        let exhausted = RetryContext { retry_attempts: MAX_RETRY_ATTEMPTS, ..FRESH };
+        assert_eq!(error.retry_action(exhausted), RetryAction::Fail);
+    }
+
+    #[test]
+    fn other_errors_fail() {
+        for error in [HttpError::ConnectionRefused, HttpError::Failed, HttpError::Unknown(-999)] {
+            assert_eq!(error.retry_action(REUSED), RetryAction::Fail, "{error:?}");
+        }
+    }
+}
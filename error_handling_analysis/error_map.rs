@@ -0,0 +1,387 @@
+use std::error::Error as StdError;
+use std::io;
+
+use crate::rust_errors::HttpError;
+
+// Conversions from the errors of the crates the stack is built on (tokio/std
+// I/O, boring, h2, hyper) into Chromium codes, following the mappings Chromium
+// applies to the equivalent failures of its own sockets and parsers.
+
+// SSL_ERROR_EARLY_DATA_REJECTED from BoringSSL's ssl.h; `boring` has no
+// constant for it.
+const SSL_ERROR_EARLY_DATA_REJECTED: i32 = 15;
+
+// ERR_lib_error_string(ERR_LIB_SSL) in BoringSSL.
+const SSL_LIBRARY: &str = "SSL routines";
+
+/// `MapSystemError` (net/base/net_errors_posix.cc): the mapping Chromium uses
+/// for socket reads and writes. A timed-out read is `ERR_TIMED_OUT`; use
+/// `map_connect_error` for `connect()` failures.
+///
+/// OS errors are mapped by errno, case by case as in Chromium: std's
+/// `ErrorKind` is coarser and files several errnos Chromium distinguishes
+/// (`EROFS`, `EDQUOT`, `ECANCELED`, ...) under kinds of their own. The kind is
+/// only used for errors that carry no errno, and off unix.
+///
+/// Errno 0 is `net::OK`. `HttpError` has no variant for success, so it comes
+/// back as `Unknown(0)`, which `NetErrorCode::from` turns into `OK`.
+pub fn map_system_error(error: &io::Error) -> HttpError {
+    // An HttpError smuggled through an io::Error (e.g. by a wrapped stream)
+    // comes back unchanged.
+    if let Some(inner) = error.get_ref().and_then(|e| e.downcast_ref::<HttpError>()) {
+        return *inner;
+    }
+    #[cfg(unix)]
+    if let Some(errno) = error.raw_os_error() {
+        return map_errno(errno);
+    }
+    use io::ErrorKind::*;
+    match error.kind() {
+        WouldBlock => HttpError::IoPending,
+        PermissionDenied => HttpError::AccessDenied,
+        NetworkDown => HttpError::InternetDisconnected,
+        TimedOut => HttpError::TimedOut,
+        ConnectionReset | BrokenPipe => HttpError::ConnectionReset,
+        ConnectionAborted => HttpError::ConnectionAborted,
+        ConnectionRefused => HttpError::ConnectionRefused,
+        HostUnreachable | NetworkUnreachable => HttpError::AddressUnreachable,
+        AddrNotAvailable => HttpError::AddressInvalid,
+        AddrInUse => HttpError::AddressInUse,
+        NotConnected => HttpError::SocketNotConnected,
+        InvalidInput => HttpError::InvalidArgument,
+        NotFound | NotADirectory => HttpError::FileNotFound,
+        AlreadyExists => HttpError::FileExists,
+        StorageFull | QuotaExceeded => HttpError::FileNoSpace,
+        IsADirectory | ReadOnlyFilesystem => HttpError::AccessDenied,
+        ResourceBusy => HttpError::InsufficientResources,
+        FileTooLarge => HttpError::FileTooBig,
+        InvalidFilename => HttpError::FilePathTooLong,
+        OutOfMemory => HttpError::OutOfMemory,
+        Unsupported => HttpError::NotImplemented,
+        // Not an OS error: tokio and hyper report a read of 0 bytes in the
+        // middle of a message this way, which Chromium sees as a closed socket.
+        UnexpectedEof => HttpError::ConnectionClosed,
+        _ => HttpError::Failed,
+    }
+}
+
+/// `MapConnectError` (net/socket/socket_posix.cc): like `map_system_error`,
+/// but a timeout is `ERR_CONNECTION_TIMED_OUT`, `EACCES` is a firewall
+/// denial and anything unrecognized is `ERR_CONNECTION_FAILED`.
+pub fn map_connect_error(error: &io::Error) -> HttpError {
+    let code = match map_connect_errno(error) {
+        Some(code) => code,
+        // No errno, e.g. a timeout around the connect future.
+        None => match error.kind() {
+            io::ErrorKind::TimedOut => HttpError::ConnectionTimedOut,
+            io::ErrorKind::PermissionDenied => HttpError::NetworkAccessDenied,
+            _ => map_system_error(error),
+        },
+    };
+    match code {
+        HttpError::Failed => HttpError::ConnectionFailed,
+        other => other,
+    }
+}
+
+// The errnos `MapConnectError` handles itself before deferring to
+// `MapSystemError`.
+#[cfg(unix)]
+fn map_connect_errno(error: &io::Error) -> Option<HttpError> {
+    Some(match error.raw_os_error()? {
+        libc::EINPROGRESS => HttpError::IoPending,
+        libc::EACCES => HttpError::NetworkAccessDenied,
+        libc::ETIMEDOUT => HttpError::ConnectionTimedOut,
+        _ => map_system_error(error),
+    })
+}
+
+#[cfg(not(unix))]
+fn map_connect_errno(_error: &io::Error) -> Option<HttpError> {
+    None
+}
+
+// The switch in net_errors_posix.cc, in its order.
+#[cfg(unix)]
+fn map_errno(errno: i32) -> HttpError {
+    match errno {
+        0 => HttpError::Unknown(0),
+        e if e == libc::EAGAIN || e == libc::EWOULDBLOCK => HttpError::IoPending,
+        libc::EACCES => HttpError::AccessDenied,
+        libc::ENETDOWN => HttpError::InternetDisconnected,
+        libc::ETIMEDOUT => HttpError::TimedOut,
+        libc::ECONNRESET | libc::ENETRESET | libc::EPIPE => HttpError::ConnectionReset,
+        libc::ECONNABORTED => HttpError::ConnectionAborted,
+        libc::ECONNREFUSED => HttpError::ConnectionRefused,
+        libc::EHOSTUNREACH | libc::EHOSTDOWN | libc::ENETUNREACH | libc::EAFNOSUPPORT => {
+            HttpError::AddressUnreachable
+        }
+        libc::EADDRNOTAVAIL => HttpError::AddressInvalid,
+        libc::EMSGSIZE => HttpError::MsgTooBig,
+        libc::ENOTCONN => HttpError::SocketNotConnected,
+        libc::EISCONN => HttpError::SocketIsConnected,
+        libc::EINVAL => HttpError::InvalidArgument,
+        libc::EADDRINUSE => HttpError::AddressInUse,
+        libc::E2BIG => HttpError::InvalidArgument,
+        libc::EBADF | libc::ENOTSOCK => HttpError::InvalidHandle,
+        libc::EBUSY => HttpError::InsufficientResources,
+        libc::ECANCELED => HttpError::Aborted,
+        libc::EDQUOT => HttpError::FileNoSpace,
+        libc::EEXIST => HttpError::FileExists,
+        libc::EFAULT => HttpError::InvalidArgument,
+        libc::EFBIG => HttpError::FileTooBig,
+        libc::EISDIR => HttpError::AccessDenied,
+        libc::ELOOP | libc::ENODEV | libc::ENOENT | libc::ENOTDIR => HttpError::FileNotFound,
+        libc::EMFILE | libc::ENFILE | libc::ENOLCK | libc::EUSERS => {
+            HttpError::InsufficientResources
+        }
+        libc::ENAMETOOLONG => HttpError::FilePathTooLong,
+        libc::ENOBUFS => HttpError::NoBufferSpace,
+        libc::ENOMEM => HttpError::OutOfMemory,
+        libc::ENOSPC => HttpError::FileNoSpace,
+        libc::ENOSYS | libc::ENOTSUP => HttpError::NotImplemented,
+        libc::EPERM | libc::EROFS | libc::ETXTBSY => HttpError::AccessDenied,
+        _ => HttpError::Failed,
+    }
+}
+
+impl From<io::Error> for HttpError {
+    fn from(error: io::Error) -> Self {
+        map_system_error(&error)
+    }
+}
+
+/// `MapOpenSSLErrorWithDetails` (net/ssl/openssl_ssl_util.cc).
+pub fn map_openssl_error(error: &boring::ssl::Error) -> HttpError {
+    let code = error.code();
+    if code == boring::ssl::ErrorCode::WANT_READ || code == boring::ssl::ErrorCode::WANT_WRITE {
+        HttpError::IoPending
+    } else if code.as_raw() == SSL_ERROR_EARLY_DATA_REJECTED {
+        HttpError::EarlyDataRejected
+    } else if code == boring::ssl::ErrorCode::ZERO_RETURN {
+        // close_notify from the peer.
+        HttpError::ConnectionClosed
+    } else if code == boring::ssl::ErrorCode::SYSCALL {
+        // The transport failed; report its error rather than a TLS one.
+        error.io_error().map_or(HttpError::ConnectionClosed, map_system_error)
+    } else if code == boring::ssl::ErrorCode::SSL {
+        // Walk the error queue for the first SSL-library entry.
+        let errors = error.ssl_error().map(|stack| stack.errors()).unwrap_or_default();
+        errors.iter().enumerate().find(|(_, e)| e.library() == Some(SSL_LIBRARY)).map_or(
+            HttpError::SslProtocolError,
+            |(i, e)| {
+                let next = errors.get(i + 1).and_then(|e| e.reason());
+                map_openssl_reason(e.reason().unwrap_or_default(), next)
+            },
+        )
+    } else {
+        HttpError::SslProtocolError
+    }
+}
+
+/// `MapOpenSSLErrorSSL`, keyed on the BoringSSL reason string (the `SSL_R_`
+/// constant without its prefix). `next` is the reason of the following queue
+/// entry, which distinguishes a ClientHello rejection from other handshake
+/// failures.
+pub fn map_openssl_reason(reason: &str, next: Option<&str>) -> HttpError {
+    match reason {
+        "READ_TIMEOUT_EXPIRED" => HttpError::TimedOut,
+        "UNKNOWN_CERTIFICATE_TYPE"
+        | "UNKNOWN_CIPHER_TYPE"
+        | "UNKNOWN_KEY_EXCHANGE_TYPE"
+        | "UNKNOWN_SSL_VERSION" => HttpError::NotImplemented,
+        "NO_CIPHER_MATCH"
+        | "NO_SHARED_CIPHER"
+        | "TLSV1_ALERT_INSUFFICIENT_SECURITY"
+        | "TLSV1_ALERT_PROTOCOL_VERSION"
+        | "UNSUPPORTED_PROTOCOL" => HttpError::SslVersionOrCipherMismatch,
+        "SSLV3_ALERT_BAD_CERTIFICATE"
+        | "SSLV3_ALERT_UNSUPPORTED_CERTIFICATE"
+        | "SSLV3_ALERT_CERTIFICATE_REVOKED"
+        | "SSLV3_ALERT_CERTIFICATE_EXPIRED"
+        | "SSLV3_ALERT_CERTIFICATE_UNKNOWN"
+        | "TLSV1_ALERT_ACCESS_DENIED"
+        | "TLSV1_ALERT_UNKNOWN_CA" => HttpError::BadSslClientAuthCert,
+        "TLSV1_ALERT_CERTIFICATE_REQUIRED" => HttpError::SslClientAuthCertNeeded,
+        "SSLV3_ALERT_DECOMPRESSION_FAILURE" => HttpError::SslDecompressionFailureAlert,
+        "SSLV3_ALERT_BAD_RECORD_MAC" => HttpError::SslBadRecordMacAlert,
+        "TLSV1_ALERT_DECRYPT_ERROR" => HttpError::SslDecryptErrorAlert,
+        "TLSV1_UNRECOGNIZED_NAME" => HttpError::SslUnrecognizedNameAlert,
+        "SERVER_CERT_CHANGED" => HttpError::SslServerCertChanged,
+        "WRONG_VERSION_ON_EARLY_DATA" => HttpError::WrongVersionOnEarlyData,
+        "TLS13_DOWNGRADE" => HttpError::Tls13DowngradeDetected,
+        "ECH_REJECTED" => HttpError::EchNotNegotiated,
+        // Servers send handshake_failure for a ClientHello with no cipher in
+        // common; Chromium reports that like NSS did (crbug.com/446505).
+        "SSLV3_ALERT_HANDSHAKE_FAILURE" if next == Some("HANDSHAKE_FAILURE_ON_CLIENT_HELLO") => {
+            HttpError::SslVersionOrCipherMismatch
+        }
+        "KEY_USAGE_BIT_INCORRECT" => HttpError::SslKeyUsageIncompatible,
+        _ => HttpError::SslProtocolError,
+    }
+}
+
+impl From<boring::ssl::Error> for HttpError {
+    fn from(error: boring::ssl::Error) -> Self {
+        map_openssl_error(&error)
+    }
+}
+
+/// HTTP/2 stream and session failures, as `SpdySession` reports them.
+///
+/// A peer's RST_STREAM maps like `SpdySession::OnRstStream`; a GOAWAY closes
+/// the affected streams with `ERR_HTTP2_SERVER_REFUSED_STREAM` (retryable)
+/// unless it asks for HTTP/1.1. Errors h2 raises itself map through the
+/// inverse of `MapNetErrorToGoAwayStatus`.
+pub fn map_h2_error(error: &h2::Error) -> HttpError {
+    use h2::Reason;
+    if let Some(io) = error.get_io() {
+        return map_system_error(io);
+    }
+    let Some(reason) = error.reason() else {
+        return HttpError::Http2ProtocolError;
+    };
+    if reason == Reason::HTTP_1_1_REQUIRED {
+        return HttpError::Http11Required;
+    }
+    if error.is_go_away() && error.is_remote() {
+        return HttpError::Http2ServerRefusedStream;
+    }
+    if error.is_reset() && error.is_remote() {
+        return match reason {
+            Reason::NO_ERROR => HttpError::Http2RstStreamNoErrorReceived,
+            Reason::REFUSED_STREAM => HttpError::Http2ServerRefusedStream,
+            _ => HttpError::Http2ProtocolError,
+        };
+    }
+    match reason {
+        Reason::FLOW_CONTROL_ERROR => HttpError::Http2FlowControlError,
+        Reason::FRAME_SIZE_ERROR => HttpError::Http2FrameSizeError,
+        Reason::COMPRESSION_ERROR => HttpError::Http2CompressionError,
+        Reason::INADEQUATE_SECURITY => HttpError::Http2InadequateTransportSecurity,
+        Reason::STREAM_CLOSED => HttpError::Http2StreamClosed,
+        Reason::REFUSED_STREAM => HttpError::Http2ClientRefusedStream,
+        _ => HttpError::Http2ProtocolError,
+    }
+}
+
+impl From<h2::Error> for HttpError {
+    fn from(error: h2::Error) -> Self {
+        map_h2_error(&error)
+    }
+}
+
+/// hyper wraps the transport and h2 errors it hit; those are mapped first.
+/// The remaining kinds correspond to `HttpStreamParser` failures.
+pub fn map_hyper_error(error: &hyper::Error) -> HttpError {
+    let mut source = error.source();
+    while let Some(cause) = source {
+        if let Some(h2) = cause.downcast_ref::<h2::Error>() {
+            return map_h2_error(h2);
+        }
+        if let Some(io) = cause.downcast_ref::<io::Error>() {
+            return map_system_error(io);
+        }
+        source = cause.source();
+    }
+    // hyper only exposes `is_parse_too_large` to servers, so an oversized
+    // response head is reported as a plain parse error.
+    if error.is_parse() || error.is_parse_status() {
+        HttpError::InvalidHttpResponse
+    } else if error.is_incomplete_message() {
+        // The socket closed before a full response. On a reused keep-alive
+        // socket this is the close/reuse race `ShouldResendRequest` retries.
+        HttpError::EmptyResponse
+    } else if error.is_timeout() {
+        HttpError::TimedOut
+    } else if error.is_canceled() || error.is_body_write_aborted() {
+        HttpError::Aborted
+    } else if error.is_closed() {
+        HttpError::ConnectionClosed
+    } else {
+        HttpError::Failed
+    }
+}
+
+impl From<hyper::Error> for HttpError {
+    fn from(error: hyper::Error) -> Self {
+        map_hyper_error(&error)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::error_serde::NetErrorCode;
+
+    fn os_error(errno: i32) -> io::Error {
+        io::Error::from_raw_os_error(errno)
+    }
+
+    #[test]
+    fn errnos_std_has_kinds_for_map_like_chromium() {
+        for (errno, expected) in [
+            (libc::EROFS, HttpError::AccessDenied),
+            (libc::EPERM, HttpError::AccessDenied),
+            (libc::EDQUOT, HttpError::FileNoSpace),
+            (libc::EBUSY, HttpError::InsufficientResources),
+            (libc::ENOTDIR, HttpError::FileNotFound),
+            (libc::EISDIR, HttpError::AccessDenied),
+            (libc::E2BIG, HttpError::InvalidArgument),
+            (libc::EFBIG, HttpError::FileTooBig),
+            (libc::ECANCELED, HttpError::Aborted),
+            (libc::EPIPE, HttpError::ConnectionReset),
+            (libc::EAGAIN, HttpError::IoPending),
+        ] {
+            assert_eq!(map_system_error(&os_error(errno)), expected, "errno {errno}");
+        }
+    }
+
+    #[test]
+    fn errno_zero_is_ok() {
+        let code = map_system_error(&os_error(0));
+        assert_eq!(code, HttpError::Unknown(0));
+        assert_eq!(NetErrorCode::from(code), NetErrorCode::OK);
+    }
+
+    #[test]
+    fn unknown_errno_is_failed() {
+        assert_eq!(map_system_error(&os_error(libc::ESRCH)), HttpError::Failed);
+        assert_eq!(map_connect_error(&os_error(libc::ESRCH)), HttpError::ConnectionFailed);
+    }
+
+    #[test]
+    fn connect_errors() {
+        assert_eq!(map_connect_error(&os_error(libc::EACCES)), HttpError::NetworkAccessDenied);
+        assert_eq!(map_connect_error(&os_error(libc::EPERM)), HttpError::AccessDenied);
+        assert_eq!(map_connect_error(&os_error(libc::ETIMEDOUT)), HttpError::ConnectionTimedOut);
+        let timeout = io::Error::from(io::ErrorKind::TimedOut);
+        assert_eq!(map_connect_error(&timeout), HttpError::ConnectionTimedOut);
+    }
+
+    #[test]
+    fn errors_without_errno_use_the_kind() {
+        let eof = io::Error::from(io::ErrorKind::UnexpectedEof);
+        assert_eq!(map_system_error(&eof), HttpError::ConnectionClosed);
+        let smuggled = io::Error::other(HttpError::Http2ProtocolError);
+        assert_eq!(map_system_error(&smuggled), HttpError::Http2ProtocolError);
+    }
+
+    #[test]
+    fn openssl_reasons() {
+        for (reason, next, expected) in [
+            ("TLSV1_ALERT_CERTIFICATE_REQUIRED", None, HttpError::SslClientAuthCertNeeded),
+            ("TLSV1_ALERT_UNKNOWN_CA", None, HttpError::BadSslClientAuthCert),
+            ("NO_SHARED_CIPHER", None, HttpError::SslVersionOrCipherMismatch),
+            (
+                "SSLV3_ALERT_HANDSHAKE_FAILURE",
+                Some("HANDSHAKE_FAILURE_ON_CLIENT_HELLO"),
+                HttpError::SslVersionOrCipherMismatch,
+            ),
+            ("SSLV3_ALERT_HANDSHAKE_FAILURE", None, HttpError::SslProtocolError),
+            ("SOMETHING_NEW", None, HttpError::SslProtocolError),
+        ] {
+            assert_eq!(map_openssl_reason(reason, next), expected, "{reason}");
+        }
+    }
+}
@@ -0,0 +1,692 @@
+use std::fmt;
+
+use crate::error_category::ErrorCategory;
+use crate::rust_errors::HttpError;
+
+// What Chrome tells the user about a failed load, after the net error page
+// (components/error_page/common/localized_error.cc, kNetErrorOptionsTable):
+// a heading, a one-line summary, optional details and the "Try:" list. As in
+// the resource strings, `$1` in a heading or summary stands for the host.
+//
+// `Severity` and `Remediation` are not part of the page; they are the same
+// judgement reduced to something automation can branch on.
+
+const NOT_AVAILABLE: &str = "This site can't be reached";
+const PAGE_NOT_WORKING: &str = "This page isn't working";
+const INSECURE_CONNECTION: &str = "This site can't provide a secure connection";
+const NOT_PRIVATE: &str = "Your connection is not private";
+const INTERNET_DISCONNECTED: &str = "No internet";
+const CONNECTION_INTERRUPTED: &str = "Your connection was interrupted";
+const BLOCKED: &str = "$1 is blocked";
+
+const SUMMARY_NOT_AVAILABLE: &str = "The webpage at $1 might be temporarily down or it may have \
+                                     moved permanently to a new web address.";
+const SUMMARY_INVALID_RESPONSE: &str = "$1 sent an invalid response.";
+
+/// One entry of the "Try:" list (`SUGGEST_*` in localized_error.cc).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Suggestion {
+    Reload,
+    CheckConnection,
+    CheckProxyFirewall,
+    CheckProxyFirewallDns,
+    ProxyConfig,
+    FirewallConfig,
+    SecureDnsConfig,
+    OfflineChecks,
+    DiagnoseTool,
+    UnsupportedCipher,
+    ContactAdministrator,
+    DisableExtension,
+    ClearCookies,
+    RepostReload,
+}
+
+impl Suggestion {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Suggestion::Reload => "reload",
+            Suggestion::CheckConnection => "check-connection",
+            Suggestion::CheckProxyFirewall => "check-proxy-firewall",
+            Suggestion::CheckProxyFirewallDns => "check-proxy-firewall-dns",
+            Suggestion::ProxyConfig => "proxy-config",
+            Suggestion::FirewallConfig => "firewall-config",
+            Suggestion::SecureDnsConfig => "secure-dns-config",
+            Suggestion::OfflineChecks => "offline-checks",
+            Suggestion::DiagnoseTool => "diagnose-tool",
+            Suggestion::UnsupportedCipher => "unsupported-cipher",
+            Suggestion::ContactAdministrator => "contact-administrator",
+            Suggestion::DisableExtension => "disable-extension",
+            Suggestion::ClearCookies => "clear-cookies",
+            Suggestion::RepostReload => "repost-reload",
+        }
+    }
+
+    /// The hint as shown under "Try:".
+    pub fn text(self) -> &'static str {
+        match self {
+            Suggestion::Reload => "Reloading the page",
+            Suggestion::CheckConnection => {
+                "Checking the connection: check any cables and reboot any routers, modems, or \
+                 other network devices you may be using"
+            }
+            Suggestion::CheckProxyFirewall => {
+                "Checking the proxy and the firewall: if the site is reachable from other \
+                 devices, make sure the proxy settings are correct and that the firewall allows \
+                 the connection"
+            }
+            Suggestion::CheckProxyFirewallDns => {
+                "Checking the proxy, firewall, and DNS configuration"
+            }
+            Suggestion::ProxyConfig => {
+                "Checking the proxy address, or contacting the network administrator to make \
+                 sure the proxy server is working"
+            }
+            Suggestion::FirewallConfig => {
+                "Allowing the client to access the network in your firewall or antivirus settings"
+            }
+            Suggestion::SecureDnsConfig => {
+                "Checking the Secure DNS settings, or contacting the Secure DNS provider"
+            }
+            Suggestion::OfflineChecks => {
+                "Checking the network cables, modem, and router, and reconnecting to Wi-Fi"
+            }
+            Suggestion::DiagnoseTool => "Running network diagnostics",
+            Suggestion::UnsupportedCipher => {
+                "The site may only offer protocol versions or cipher suites that are no longer \
+                 considered secure; only its administrator can fix this"
+            }
+            Suggestion::ContactAdministrator => "Contacting the system admin",
+            Suggestion::DisableExtension => "Disabling your extensions",
+            Suggestion::ClearCookies => "Clearing your cookies",
+            Suggestion::RepostReload => {
+                "Reloading to send the form data again, which repeats any action the page \
+                 previously performed"
+            }
+        }
+    }
+}
+
+impl fmt::Display for Suggestion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.text())
+    }
+}
+
+/// The error page for one `HttpError`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErrorPage {
+    pub code: HttpError,
+    pub heading: &'static str,
+    pub summary: &'static str,
+    pub details: Option<&'static str>,
+    pub suggestions: &'static [Suggestion],
+}
+
+impl ErrorPage {
+    pub fn heading_for(&self, host: &str) -> String {
+        self.heading.replace("$1", host)
+    }
+
+    pub fn summary_for(&self, host: &str) -> String {
+        self.summary.replace("$1", host)
+    }
+
+    /// The page as plain text, in the order Chrome lays it out.
+    pub fn render(&self, host: &str) -> String {
+        let mut out = format!("{}\n{}\n", self.heading_for(host), self.summary_for(host));
+        if let Some(details) = self.details {
+            out.push_str(details);
+            out.push('\n');
+        }
+        if !self.suggestions.is_empty() {
+            out.push_str("Try:\n");
+            for suggestion in self.suggestions {
+                out.push_str(&format!("  - {suggestion}\n"));
+            }
+        }
+        match self.code.name() {
+            Some(name) => out.push_str(name),
+            None => out.push_str(&self.code.as_i32().to_string()),
+        }
+        out
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Severity {
+    /// Not a failure of the site or the network (`ERR_IO_PENDING`,
+    /// `ERR_ABORTED`, cache misses).
+    Info,
+    /// Transient; retrying is expected to help.
+    Warning,
+    Error,
+    /// The connection may be under attack: certificate, pinning and
+    /// downgrade failures.
+    Critical,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+/// What automation should do about an error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Remediation {
+    /// Nothing to do automatically; report the error.
+    None,
+    /// Retry right away, on a new connection.
+    Retry,
+    /// Retry after a backoff: the server or a resolver is slow or throttling.
+    RetryLater,
+    /// Local connectivity is down.
+    CheckNetwork,
+    CheckFirewall,
+    CheckProxy,
+    CheckDns,
+    /// TLS versions, cipher suites or ECH configuration do not match the
+    /// server's.
+    CheckTlsConfig,
+    /// The server certificate failed verification; do not bypass.
+    CheckCertificate,
+    ProvideClientCertificate,
+    ProvideCredentials,
+    ClearCookies,
+    /// Load from the network instead of the cache.
+    BypassCache,
+    /// The request itself is invalid (URL, scheme, port, method).
+    FixRequest,
+    /// The server or proxy sent something malformed.
+    ReportServer,
+}
+
+impl Remediation {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Remediation::None => "none",
+            Remediation::Retry => "retry",
+            Remediation::RetryLater => "retry-later",
+            Remediation::CheckNetwork => "check-network",
+            Remediation::CheckFirewall => "check-firewall",
+            Remediation::CheckProxy => "check-proxy",
+            Remediation::CheckDns => "check-dns",
+            Remediation::CheckTlsConfig => "check-tls-config",
+            Remediation::CheckCertificate => "check-certificate",
+            Remediation::ProvideClientCertificate => "provide-client-certificate",
+            Remediation::ProvideCredentials => "provide-credentials",
+            Remediation::ClearCookies => "clear-cookies",
+            Remediation::BypassCache => "bypass-cache",
+            Remediation::FixRequest => "fix-request",
+            Remediation::ReportServer => "report-server",
+        }
+    }
+}
+
+const fn page(
+    code: HttpError,
+    heading: &'static str,
+    summary: &'static str,
+    details: Option<&'static str>,
+    suggestions: &'static [Suggestion],
+) -> ErrorPage {
+    ErrorPage { code, heading, summary, details, suggestions }
+}
+
+impl HttpError {
+    /// The page Chrome shows for this error. Errors without an entry of their
+    /// own get the generic "This site can't be reached" page.
+    pub fn error_page(&self) -> ErrorPage {
+        use HttpError::*;
+        use Suggestion::*;
+        let code = *self;
+        match self {
+            ConnectionRefused => page(
+                code,
+                NOT_AVAILABLE,
+                "$1 refused to connect.",
+                None,
+                &[CheckConnection, CheckProxyFirewall],
+            ),
+            TimedOut | ConnectionTimedOut => page(
+                code,
+                NOT_AVAILABLE,
+                "$1 took too long to respond.",
+                None,
+                &[CheckConnection, CheckProxyFirewall, DiagnoseTool],
+            ),
+            ConnectionReset => page(
+                code,
+                NOT_AVAILABLE,
+                "The connection was reset.",
+                None,
+                &[CheckConnection, CheckProxyFirewall, DiagnoseTool],
+            ),
+            ConnectionClosed => page(
+                code,
+                NOT_AVAILABLE,
+                "$1 unexpectedly closed the connection.",
+                None,
+                &[CheckConnection, CheckProxyFirewall, DiagnoseTool],
+            ),
+            ConnectionAborted | ConnectionFailed | SocketNotConnected => page(
+                code,
+                NOT_AVAILABLE,
+                "The connection to $1 was interrupted.",
+                None,
+                &[CheckConnection, CheckProxyFirewall, DiagnoseTool],
+            ),
+            AddressUnreachable | AddressInvalid => page(
+                code,
+                NOT_AVAILABLE,
+                "$1 is unreachable.",
+                None,
+                &[CheckConnection, CheckProxyFirewall],
+            ),
+            DnsSecureResolverHostnameResolutionFailed | DnsSecureProbeRecordInvalid => page(
+                code,
+                NOT_AVAILABLE,
+                "$1's server IP address could not be found.",
+                Some("The Secure DNS resolver could not be reached."),
+                &[SecureDnsConfig, CheckConnection],
+            ),
+            IcannNameCollision => page(
+                code,
+                NOT_AVAILABLE,
+                "$1 resolves to a name-collision address (127.0.53.53).",
+                Some("The name is reserved while ICANN resolves a conflict with private networks."),
+                &[ContactAdministrator],
+            ),
+            InternetDisconnected => page(
+                code,
+                INTERNET_DISCONNECTED,
+                "You're not connected to the internet.",
+                None,
+                &[OfflineChecks, DiagnoseTool],
+            ),
+            NetworkChanged => page(
+                code,
+                CONNECTION_INTERRUPTED,
+                "A network change was detected.",
+                None,
+                &[Reload],
+            ),
+            NetworkIoSuspended => {
+                page(code, CONNECTION_INTERRUPTED, "The device went to sleep.", None, &[Reload])
+            }
+            NetworkAccessDenied | NetworkAccessRevoked => page(
+                code,
+                "Your Internet access is blocked",
+                "Firewall or antivirus software may have blocked the connection.",
+                None,
+                &[FirewallConfig, ContactAdministrator],
+            ),
+            TemporarilyThrottled => page(
+                code,
+                NOT_AVAILABLE,
+                "Requests to $1 have been temporarily throttled.",
+                None,
+                &[Reload],
+            ),
+            SslVersionOrCipherMismatch | SslObsoleteVersionOrCipher | SslObsoleteCipher => page(
+                code,
+                INSECURE_CONNECTION,
+                "$1 uses an unsupported protocol.",
+                Some(
+                    "The client and server don't support a common SSL protocol version or cipher \
+                     suite.",
+                ),
+                &[UnsupportedCipher],
+            ),
+            SslClientAuthCertNeeded => page(
+                code,
+                INSECURE_CONNECTION,
+                "$1 requires a login certificate, and none was provided.",
+                None,
+                &[ContactAdministrator],
+            ),
+            e if e.is_client_certificate_error() => page(
+                code,
+                INSECURE_CONNECTION,
+                "$1 didn't accept your login certificate, or one may not have been provided.",
+                None,
+                &[ContactAdministrator],
+            ),
+            CertCommonNameInvalid => page(
+                code,
+                NOT_PRIVATE,
+                "Attackers might be trying to steal your information from $1.",
+                Some("The server's certificate is for a different host name."),
+                &[],
+            ),
+            CertDateInvalid => page(
+                code,
+                NOT_PRIVATE,
+                "Attackers might be trying to steal your information from $1.",
+                Some(
+                    "The server's certificate has expired or is not yet valid. Check the device \
+                     clock.",
+                ),
+                &[],
+            ),
+            CertAuthorityInvalid => page(
+                code,
+                NOT_PRIVATE,
+                "Attackers might be trying to steal your information from $1.",
+                Some("The server's certificate is not trusted by the device."),
+                &[],
+            ),
+            CertRevoked => page(
+                code,
+                NOT_PRIVATE,
+                "Attackers might be trying to steal your information from $1.",
+                Some("The server's certificate has been revoked."),
+                &[],
+            ),
+            e if e.is_certificate_error() || *e == CertKnownInterceptionBlocked => page(
+                code,
+                NOT_PRIVATE,
+                "Attackers might be trying to steal your information from $1.",
+                Some("The server's certificate could not be verified."),
+                &[],
+            ),
+            TooManyRedirects => page(
+                code,
+                PAGE_NOT_WORKING,
+                "$1 redirected you too many times.",
+                None,
+                &[ClearCookies],
+            ),
+            EmptyResponse => {
+                page(code, PAGE_NOT_WORKING, "$1 didn't send any data.", None, &[Reload])
+            }
+            ResponseHeadersMultipleContentLength
+            | ResponseHeadersMultipleContentDisposition
+            | ResponseHeadersMultipleLocation => page(
+                code,
+                PAGE_NOT_WORKING,
+                SUMMARY_INVALID_RESPONSE,
+                Some(
+                    "The response contained duplicate headers. This is generally the result of a \
+                     misconfigured website or proxy; only its administrator can fix it.",
+                ),
+                &[ContactAdministrator],
+            ),
+            InvalidResponse
+            | InvalidHttpResponse
+            | InvalidChunkedEncoding
+            | IncompleteChunkedEncoding
+            | ContentLengthMismatch
+            | ResponseHeadersTruncated
+            | ContentDecodingFailed => {
+                page(code, PAGE_NOT_WORKING, SUMMARY_INVALID_RESPONSE, None, &[Reload])
+            }
+            CacheMiss => page(
+                code,
+                "Confirm Form Resubmission",
+                "This webpage requires data that you entered earlier in order to be properly \
+                 displayed.",
+                None,
+                &[RepostReload],
+            ),
+            BlockedByClient => page(
+                code,
+                "This page has been blocked by an extension",
+                "An extension blocked the request to $1.",
+                None,
+                &[DisableExtension],
+            ),
+            BlockedByAdministrator => page(
+                code,
+                BLOCKED,
+                "Your organization doesn't allow you to view this site.",
+                None,
+                &[ContactAdministrator],
+            ),
+            BlockedByResponse | BlockedByCsp | BlockedByOrb => {
+                page(code, BLOCKED, "$1 refused to connect.", None, &[])
+            }
+            FileNotFound => page(
+                code,
+                "Your file couldn't be accessed",
+                "It may have been moved, edited, or deleted.",
+                None,
+                &[Reload],
+            ),
+            AccessDenied => page(
+                code,
+                "Access to $1 was denied",
+                "You don't have authorization to view this page.",
+                None,
+                &[],
+            ),
+            e => match e.category() {
+                ErrorCategory::Dns => page(
+                    code,
+                    NOT_AVAILABLE,
+                    "$1's server IP address could not be found.",
+                    None,
+                    &[CheckConnection, CheckProxyFirewallDns, DiagnoseTool],
+                ),
+                ErrorCategory::Proxy => page(
+                    code,
+                    INTERNET_DISCONNECTED,
+                    "There is something wrong with the proxy server, or the address is incorrect.",
+                    None,
+                    &[ProxyConfig, ContactAdministrator],
+                ),
+                ErrorCategory::Tls => {
+                    page(code, INSECURE_CONNECTION, SUMMARY_INVALID_RESPONSE, None, &[])
+                }
+                ErrorCategory::Http2 | ErrorCategory::Quic => {
+                    page(code, PAGE_NOT_WORKING, SUMMARY_INVALID_RESPONSE, None, &[Reload])
+                }
+                _ => page(code, NOT_AVAILABLE, SUMMARY_NOT_AVAILABLE, None, &[Reload]),
+            },
+        }
+    }
+
+    pub fn severity(&self) -> Severity {
+        use HttpError::*;
+        match self {
+            IoPending | Aborted | ContextShutDown | CacheMiss | DnsCacheMiss | CacheRace => {
+                Severity::Info
+            }
+            SslPinnedKeyNotInCertChain
+            | Tls13DowngradeDetected
+            | CertKnownInterceptionBlocked
+            | SslServerCertChanged
+            | InsecureResponse => Severity::Critical,
+            e if e.is_certificate_error() => Severity::Critical,
+            e => match e.remediation() {
+                Remediation::Retry | Remediation::RetryLater => Severity::Warning,
+                _ => Severity::Error,
+            },
+        }
+    }
+
+    pub fn remediation(&self) -> Remediation {
+        use HttpError::*;
+        match self {
+            IoPending | Aborted | ContextShutDown | DnsRequestCancelled => Remediation::None,
+            SslClientAuthCertNeeded => Remediation::ProvideClientCertificate,
+            e if e.is_client_certificate_error() => Remediation::ProvideClientCertificate,
+            e if e.is_certificate_error() => Remediation::CheckCertificate,
+            SslPinnedKeyNotInCertChain | CertKnownInterceptionBlocked | SslServerCertChanged => {
+                Remediation::CheckCertificate
+            }
+            ConnectionReset
+            | ConnectionClosed
+            | ConnectionAborted
+            | SocketNotConnected
+            | EmptyResponse
+            | NetworkChanged
+            | NetworkIoSuspended
+            | EarlyDataRejected
+            | WrongVersionOnEarlyData
+            | Http2ServerRefusedStream
+            | Http2PingFailed
+            | QuicGoawayRequestCanBeRetried
+            | CacheRace => Remediation::Retry,
+            TimedOut
+            | ConnectionTimedOut
+            | ConnectionRefused
+            | TemporarilyThrottled
+            | InsufficientResources
+            | NoBufferSpace
+            | PreconnectMaxSocketLimit
+            | HostResolverQueueTooLarge
+            | DnsTimedOut
+            | DnsServerFailed
+            | WsThrottleQueueTooLarge
+            | CacheLockTimeout => Remediation::RetryLater,
+            InternetDisconnected | AddressUnreachable | ConnectionFailed => {
+                Remediation::CheckNetwork
+            }
+            NetworkAccessDenied | NetworkAccessRevoked => Remediation::CheckFirewall,
+            ProxyAuthRequested
+            | ProxyAuthUnsupported
+            | ProxyAuthRequestedWithNoConnection
+            | InvalidAuthCredentials
+            | MissingAuthCredentials
+            | UnsupportedAuthScheme
+            | MisconfiguredAuthEnvironment => Remediation::ProvideCredentials,
+            TooManyRedirects => Remediation::ClearCookies,
+            InvalidUrl | DisallowedUrlScheme | UnknownUrlScheme | InvalidRedirect
+            | UnsafeRedirect | UnsafePort | InvalidArgument | MethodNotSupported => {
+                Remediation::FixRequest
+            }
+            InvalidResponse
+            | InvalidHttpResponse
+            | InvalidChunkedEncoding
+            | IncompleteChunkedEncoding
+            | ContentLengthMismatch
+            | ResponseHeadersTruncated
+            | ResponseHeadersTooBig
+            | ResponseHeadersMultipleContentLength
+            | ResponseHeadersMultipleContentDisposition
+            | ResponseHeadersMultipleLocation
+            | ContentDecodingFailed
+            | ContentDecodingInitFailed
+            | UnexpectedProxyAuth => Remediation::ReportServer,
+            e => match e.category() {
+                ErrorCategory::Dns => Remediation::CheckDns,
+                ErrorCategory::Proxy => Remediation::CheckProxy,
+                ErrorCategory::Tls => Remediation::CheckTlsConfig,
+                ErrorCategory::Cache => Remediation::BypassCache,
+                ErrorCategory::Http2 | ErrorCategory::Quic => Remediation::ReportServer,
+                _ => Remediation::None,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_connection_refused() {
+        assert_eq!(
+            HttpError::ConnectionRefused.error_page().render("example.com"),
+            "This site can't be reached\n\
+             example.com refused to connect.\n\
+             Try:\n  \
+             - Checking the connection: check any cables and reboot any routers, modems, or \
+             other network devices you may be using\n  \
+             - Checking the proxy and the firewall: if the site is reachable from other \
+             devices, make sure the proxy settings are correct and that the firewall allows \
+             the connection\n\
+             ERR_CONNECTION_REFUSED"
+        );
+    }
+
+    #[test]
+    fn headings_and_details() {
+        let page = HttpError::CertDateInvalid.error_page();
+        assert_eq!(page.heading_for("example.com"), NOT_PRIVATE);
+        assert!(page.details.unwrap().contains("expired"));
+        assert!(page.suggestions.is_empty());
+
+        let page = HttpError::BlockedByAdministrator.error_page();
+        assert_eq!(page.heading_for("example.com"), "example.com is blocked");
+
+        let page = HttpError::NameNotResolved.error_page();
+        assert_eq!(
+            page.summary_for("example.com"),
+            "example.com's server IP address could not be found."
+        );
+        assert_eq!(
+            page.suggestions,
+            [
+                Suggestion::CheckConnection,
+                Suggestion::CheckProxyFirewallDns,
+                Suggestion::DiagnoseTool
+            ]
+        );
+
+        let page = HttpError::Http2ProtocolError.error_page();
+        assert_eq!((page.heading, page.summary), (PAGE_NOT_WORKING, SUMMARY_INVALID_RESPONSE));
+    }
+
+    #[test]
+    fn severity_and_remediation() {
+        for (error, severity, remediation) in [
+            (HttpError::Aborted, Severity::Info, Remediation::None),
+            (HttpError::CacheMiss, Severity::Info, Remediation::BypassCache),
+            (HttpError::ConnectionReset, Severity::Warning, Remediation::Retry),
+            (HttpError::ConnectionTimedOut, Severity::Warning, Remediation::RetryLater),
+            (HttpError::InternetDisconnected, Severity::Error, Remediation::CheckNetwork),
+            (HttpError::NameNotResolved, Severity::Error, Remediation::CheckDns),
+            (HttpError::TunnelConnectionFailed, Severity::Error, Remediation::CheckProxy),
+            (HttpError::SslVersionOrCipherMismatch, Severity::Error, Remediation::CheckTlsConfig),
+            (
+                HttpError::SslClientAuthCertNeeded,
+                Severity::Error,
+                Remediation::ProvideClientCertificate,
+            ),
+            (HttpError::CertAuthorityInvalid, Severity::Critical, Remediation::CheckCertificate),
+            (
+                HttpError::SslPinnedKeyNotInCertChain,
+                Severity::Critical,
+                Remediation::CheckCertificate,
+            ),
+            (HttpError::Tls13DowngradeDetected, Severity::Critical, Remediation::CheckTlsConfig),
+            (HttpError::TooManyRedirects, Severity::Error, Remediation::ClearCookies),
+            (HttpError::InvalidUrl, Severity::Error, Remediation::FixRequest),
+            (HttpError::InvalidHttpResponse, Severity::Error, Remediation::ReportServer),
+        ] {
+            assert_eq!(error.severity(), severity, "{error:?}");
+            assert_eq!(error.remediation(), remediation, "{error:?}");
+        }
+    }
+
+    #[test]
+    fn unknown_codes() {
+        let unknown = HttpError::Unknown(-12345);
+        let page = unknown.error_page();
+        assert_eq!((page.heading, page.summary), (NOT_AVAILABLE, SUMMARY_NOT_AVAILABLE));
+        assert!(page.render("example.com").ends_with("\n-12345"));
+        assert_eq!(unknown.severity(), Severity::Error);
+        assert_eq!(unknown.remediation(), Remediation::None);
+
+        // An unlisted code still gets its range's treatment.
+        assert_eq!(HttpError::Unknown(-899).remediation(), Remediation::CheckDns);
+    }
+
+    #[test]
+    fn every_code_has_a_page() {
+        for error in HttpError::ALL {
+            let page = error.error_page();
+            assert_eq!(page.code, *error);
+            assert!(!page.heading.is_empty() && !page.summary.is_empty(), "{error:?}");
+            assert!(page.render("example.com").ends_with(error.name().unwrap()));
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use serde::de::{self, Deserializer};
+use serde::ser::{SerializeStruct, Serializer};
+use serde::{Deserialize, Serialize};
+
+use crate::rust_errors::HttpError;
+
+// Stable wire forms of HttpError for the job coordinator and the C++ side.
+//
+// The code is the identity in every form: it is what Chromium logs and what
+// `Unknown` carries, so an error from a newer Chromium survives a round trip
+// through an older build unchanged; any i32 reads back as the same value.
+// Names are informational.
+
+/// `{"code": -101, "name": "ERR_CONNECTION_RESET"}`. `name` is `null` for
+/// `Unknown` codes. On input the code wins; an object with only a name is
+/// accepted for hand-written configs.
+impl Serialize for HttpError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("HttpError", 2)?;
+        state.serialize_field("code", &self.as_i32())?;
+        state.serialize_field("name", &self.name())?;
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for HttpError {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Repr {
+            #[serde(default)]
+            code: Option<i32>,
+            #[serde(default)]
+            name: Option<String>,
+        }
+        match Repr::deserialize(deserializer)? {
+            Repr { code: Some(code), .. } => Ok(HttpError::from_i32(code)),
+            Repr { code: None, name: Some(name) } => name.parse().map_err(de::Error::custom),
+            Repr { code: None, name: None } => Err(de::Error::missing_field("code")),
+        }
+    }
+}
+
+/// The bare code, as in an `int32` protobuf field or `net_error` in NetLog
+/// JSON: `#[serde(with = "crate::error_serde::as_code")]`.
+pub mod as_code {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(error: &HttpError, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i32(error.as_i32())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<HttpError, D::Error> {
+        i32::deserialize(deserializer).map(HttpError::from_i32)
+    }
+}
+
+/// `as_code` for `Option<HttpError>`; `null` is `None`.
+pub mod as_optional_code {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        error: &Option<HttpError>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        error.map(|e| e.as_i32()).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<HttpError>, D::Error> {
+        Ok(Option::<i32>::deserialize(deserializer)?.map(HttpError::from_i32))
+    }
+}
+
+/// A net error as the C++ side sees it: a plain `int`, `net::OK` (0) on
+/// success. ABI-identical to `int32_t`, so it can cross `extern "C"`
+/// signatures directly.
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NetErrorCode(pub i32);
+
+impl NetErrorCode {
+    pub const OK: NetErrorCode = NetErrorCode(0);
+
+    pub const fn is_ok(self) -> bool {
+        self.0 == NetErrorCode::OK.0
+    }
+
+    /// `None` for `OK`. Every other value, known or not, is an error carrying
+    /// that code, so nothing is lost on the way back to C++.
+    pub const fn error(self) -> Option<HttpError> {
+        if self.is_ok() {
+            None
+        } else {
+            Some(HttpError::from_i32(self.0))
+        }
+    }
+
+    pub fn into_result(self) -> Result<(), HttpError> {
+        self.error().map_or(Ok(()), Err)
+    }
+}
+
+impl From<HttpError> for NetErrorCode {
+    fn from(error: HttpError) -> Self {
+        NetErrorCode(error.as_i32())
+    }
+}
+
+impl From<Result<(), HttpError>> for NetErrorCode {
+    fn from(result: Result<(), HttpError>) -> Self {
+        result.map_or_else(NetErrorCode::from, |()| NetErrorCode::OK)
+    }
+}
+
+/// Whether `code` is a net error this build knows by name.
+#[no_mangle]
+pub extern "C" fn net_error_is_known(code: NetErrorCode) -> bool {
+    matches!(code.error(), Some(e) if e.name().is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Record {
+        #[serde(with = "as_code")]
+        error: HttpError,
+        #[serde(with = "as_optional_code", default)]
+        previous: Option<HttpError>,
+    }
+
+    #[test]
+    fn object_form_round_trips() {
+        for error in [HttpError::ConnectionReset, HttpError::Unknown(-12345), HttpError::Unknown(7)]
+        {
+            let value = serde_json::to_value(error).unwrap();
+            assert_eq!(value["code"], error.as_i32());
+            assert_eq!(serde_json::from_value::<HttpError>(value).unwrap(), error);
+        }
+        assert_eq!(
+            serde_json::to_value(HttpError::ConnectionReset).unwrap(),
+            json!({"code": -101, "name": "ERR_CONNECTION_RESET"})
+        );
+        assert_eq!(
+            serde_json::to_value(HttpError::Unknown(-12345)).unwrap(),
+            json!({"code": -12345, "name": null})
+        );
+    }
+
+    #[test]
+    fn object_form_input() {
+        let parse = |value| serde_json::from_value::<HttpError>(value);
+        // The code wins over a mismatched name.
+        assert_eq!(
+            parse(json!({"code": -105, "name": "ERR_CONNECTION_RESET"})).unwrap(),
+            HttpError::NameNotResolved
+        );
+        assert_eq!(parse(json!({"name": "ERR_TIMED_OUT"})).unwrap(), HttpError::TimedOut);
+        assert!(parse(json!({"name": "ERR_NOT_A_THING"})).is_err());
+        assert!(parse(json!({})).is_err());
+    }
+
+    #[test]
+    fn integer_form_round_trips() {
+        let record = Record { error: HttpError::Unknown(-999), previous: Some(HttpError::Aborted) };
+        let value = serde_json::to_value(&record).unwrap();
+        assert_eq!(value, json!({"error": -999, "previous": -3}));
+        assert_eq!(serde_json::from_value::<Record>(value).unwrap(), record);
+
+        let record: Record =
+            serde_json::from_value(json!({"error": -101, "previous": null})).unwrap();
+        assert_eq!(record, Record { error: HttpError::ConnectionReset, previous: None });
+    }
+
+    #[test]
+    fn only_zero_is_ok() {
+        assert!(NetErrorCode::OK.is_ok());
+        assert_eq!(NetErrorCode::OK.into_result(), Ok(()));
+        for code in [-101, -12345, 1, 42] {
+            let code = NetErrorCode(code);
+            assert!(!code.is_ok());
+            let error = code.error().unwrap();
+            assert_eq!(NetErrorCode::from(error), code);
+            assert_eq!(NetErrorCode::from(code.into_result()), code);
+        }
+        assert_eq!(NetErrorCode(-101).error(), Some(HttpError::ConnectionReset));
+    }
+
+    #[test]
+    fn ffi_net_error_is_known() {
+        assert!(net_error_is_known(NetErrorCode(-101)));
+        assert!(net_error_is_known(NetErrorCode::from(HttpError::DnsTimedOut)));
+        assert!(!net_error_is_known(NetErrorCode::OK));
+        assert!(!net_error_is_known(NetErrorCode(-12345)));
+        assert!(!net_error_is_known(NetErrorCode(5)));
+    }
+}
@@ -0,0 +1,544 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, AtomicU64, Ordering};
+
+use url::Url;
+
+use crate::error_category::ErrorCategory;
+use crate::net_error::{NetError, Phase};
+use crate::rust_errors::HttpError;
+
+// Error counts per origin, the in-process equivalent of Chromium's
+// `Net.ErrorCodesForMainFrame*` sparse histograms, split by origin and phase.
+//
+// Recording is lock-free. Each origin owns a fixed table with one counter per
+// (code, phase), indexed through the `net_errors!` table, and is found in an
+// append-only hash map: a fixed set of buckets, each a linked list that only
+// grows by compare-and-swap on its head. Nothing is ever unlinked, so entries
+// stay valid without locks or epochs; `clear` zeroes counters instead of
+// removing origins. Codes missing from `net_error_list.h` go to a per-origin
+// append-only list of their own.
+
+/// Origin label for errors without a URL.
+pub const UNKNOWN_ORIGIN: &str = "unknown";
+
+const BUCKETS: usize = 64;
+const CODES: usize = HttpError::ALL.len();
+// Slot 0 is "no phase", then `Phase::ALL`.
+const PHASES: usize = Phase::ALL.len() + 1;
+
+const _: () = {
+    let mut i = 0;
+    while i < Phase::ALL.len() {
+        assert!(Phase::ALL[i] as usize == i, "Phase::ALL is out of declaration order");
+        i += 1;
+    }
+};
+
+fn phase_slot(phase: Option<Phase>) -> usize {
+    phase.map_or(0, |phase| phase as usize + 1)
+}
+
+fn slot_phase(slot: usize) -> Option<Phase> {
+    slot.checked_sub(1).map(|i| Phase::ALL[i])
+}
+
+// A singly linked list that only grows. Nodes are pushed with a CAS on the
+// head and freed when the list is dropped, so `&T`s handed out live as long
+// as the list.
+struct AppendList<T> {
+    head: AtomicPtr<Node<T>>,
+}
+
+struct Node<T> {
+    value: T,
+    next: *mut Node<T>,
+}
+
+// SAFETY: nodes are only reachable through the list, which hands out shared
+// references; `T: Sync` makes sharing them across threads sound.
+unsafe impl<T: Send + Sync> Send for AppendList<T> {}
+unsafe impl<T: Send + Sync> Sync for AppendList<T> {}
+
+impl<T> Default for AppendList<T> {
+    fn default() -> Self {
+        Self { head: AtomicPtr::new(ptr::null_mut()) }
+    }
+}
+
+impl<T> AppendList<T> {
+    fn iter(&self) -> impl Iterator<Item = &T> {
+        Self::walk(self.head.load(Ordering::Acquire), ptr::null_mut())
+    }
+
+    // Nodes from `from` up to, not including, `to`.
+    fn walk<'a>(from: *mut Node<T>, to: *mut Node<T>) -> impl Iterator<Item = &'a T>
+    where
+        T: 'a,
+    {
+        let mut node = from;
+        std::iter::from_fn(move || {
+            if node == to {
+                return None;
+            }
+            // SAFETY: `node` was published by a Release CAS and read with
+            // Acquire, and nodes are not freed before the list is dropped.
+            let current = unsafe { &*node };
+            node = current.next;
+            Some(&current.value)
+        })
+    }
+
+    /// The first entry `matches` accepts, pushing `make()` if there is none.
+    /// Racing pushes of the same entry are resolved to the one that won.
+    fn find_or_push(&self, matches: impl Fn(&T) -> bool, make: impl FnOnce() -> T) -> &T {
+        let mut head = self.head.load(Ordering::Acquire);
+        if let Some(found) = Self::walk(head, ptr::null_mut()).find(|v| matches(v)) {
+            return found;
+        }
+        let node = Box::into_raw(Box::new(Node { value: make(), next: head }));
+        loop {
+            match self.head.compare_exchange_weak(head, node, Ordering::AcqRel, Ordering::Acquire) {
+                // SAFETY: `node` is now owned by the list.
+                Ok(_) => return unsafe { &(*node).value },
+                Err(current) => {
+                    // Only the nodes pushed since `head` can be new matches.
+                    if let Some(found) = Self::walk(current, head).find(|v| matches(v)) {
+                        // SAFETY: the CAS failed, so `node` was never shared.
+                        drop(unsafe { Box::from_raw(node) });
+                        return found;
+                    }
+                    // SAFETY: as above, `node` is still exclusively ours.
+                    unsafe { (*node).next = current };
+                    head = current;
+                }
+            }
+        }
+    }
+}
+
+impl<T> Drop for AppendList<T> {
+    fn drop(&mut self) {
+        let mut node = *self.head.get_mut();
+        while !node.is_null() {
+            // SAFETY: `&mut self` rules out other users; each node is freed once.
+            let boxed = unsafe { Box::from_raw(node) };
+            node = boxed.next;
+        }
+    }
+}
+
+struct UnknownCount {
+    code: i32,
+    phase: Option<Phase>,
+    count: AtomicU64,
+}
+
+struct OriginStats {
+    origin: String,
+    requests: AtomicU64,
+    // `CODES * PHASES` counters: code index times `PHASES` plus phase slot.
+    known: Box<[AtomicU64]>,
+    unknown: AppendList<UnknownCount>,
+}
+
+impl OriginStats {
+    fn new(origin: &str) -> Self {
+        Self {
+            origin: origin.to_string(),
+            requests: AtomicU64::new(0),
+            known: (0..CODES * PHASES).map(|_| AtomicU64::new(0)).collect(),
+            unknown: AppendList::default(),
+        }
+    }
+
+    fn counter(&self, code: HttpError, phase: Option<Phase>) -> &AtomicU64 {
+        match code.index() {
+            Some(index) => &self.known[index * PHASES + phase_slot(phase)],
+            None => {
+                let code = code.as_i32();
+                &self
+                    .unknown
+                    .find_or_push(
+                        |e| e.code == code && e.phase == phase,
+                        || UnknownCount { code, phase, count: AtomicU64::new(0) },
+                    )
+                    .count
+            }
+        }
+    }
+
+    fn reset(&self) {
+        self.requests.store(0, Ordering::Relaxed);
+        let unknown = self.unknown.iter().map(|e| &e.count);
+        for counter in self.known.iter().chain(unknown) {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+pub struct ErrorStats {
+    origins: [AppendList<OriginStats>; BUCKETS],
+}
+
+impl Default for ErrorStats {
+    fn default() -> Self {
+        Self { origins: std::array::from_fn(|_| AppendList::default()) }
+    }
+}
+
+impl ErrorStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Serialized origin of `url` (`https://example.com:8443`), the key the
+    /// recorder uses.
+    pub fn origin_of(url: &Url) -> String {
+        url.origin().ascii_serialization()
+    }
+
+    pub fn record_success(&self, origin: &str) {
+        self.origin(origin).requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Counts a failed request; it also counts towards the origin's requests.
+    pub fn record_error(&self, origin: &str, phase: Option<Phase>, code: HttpError) {
+        let stats = self.origin(origin);
+        stats.requests.fetch_add(1, Ordering::Relaxed);
+        stats.counter(code, phase).fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record(&self, origin: &str, phase: Option<Phase>, result: Result<(), HttpError>) {
+        match result {
+            Ok(()) => self.record_success(origin),
+            Err(code) => self.record_error(origin, phase, code),
+        }
+    }
+
+    pub fn record_net_error(&self, error: &NetError) {
+        let origin = error.url().map(Self::origin_of);
+        self.record_error(origin.as_deref().unwrap_or(UNKNOWN_ORIGIN), error.phase(), error.code());
+    }
+
+    fn origin(&self, origin: &str) -> &OriginStats {
+        let mut hasher = DefaultHasher::new();
+        origin.hash(&mut hasher);
+        let bucket = &self.origins[hasher.finish() as usize % BUCKETS];
+        bucket.find_or_push(|stats| stats.origin == origin, || OriginStats::new(origin))
+    }
+
+    /// A point-in-time copy. Counters keep moving while it is taken, so the
+    /// totals of different origins are not from one instant. Origins without
+    /// requests since the last `clear` are left out.
+    pub fn snapshot(&self) -> StatsSnapshot {
+        let mut snapshot = StatsSnapshot::default();
+        for origin in self.origins.iter().flat_map(AppendList::iter) {
+            let requests = origin.requests.load(Ordering::Relaxed);
+            if requests == 0 {
+                continue;
+            }
+            snapshot.requests.insert(origin.origin.clone(), requests);
+            let known = origin
+                .known
+                .iter()
+                .enumerate()
+                .map(|(i, count)| (HttpError::ALL[i / PHASES], slot_phase(i % PHASES), count));
+            let unknown =
+                origin.unknown.iter().map(|e| (HttpError::Unknown(e.code), e.phase, &e.count));
+            for (code, phase, count) in known.chain(unknown) {
+                let count = count.load(Ordering::Relaxed);
+                if count > 0 {
+                    snapshot.errors.push(ErrorCount {
+                        origin: origin.origin.clone(),
+                        code,
+                        phase,
+                        count,
+                    });
+                }
+            }
+        }
+        snapshot.errors.sort_by(|a, b| {
+            (&a.origin, a.code.as_i32(), a.phase.map(Phase::as_str)).cmp(&(
+                &b.origin,
+                b.code.as_i32(),
+                b.phase.map(Phase::as_str),
+            ))
+        });
+        snapshot
+    }
+
+    /// Zeroes every counter. Origins stay allocated (the map never shrinks)
+    /// but drop out of snapshots until they record again. Increments racing
+    /// with `clear` may land on either side of it.
+    pub fn clear(&self) {
+        for origin in self.origins.iter().flat_map(AppendList::iter) {
+            origin.reset();
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorCount {
+    pub origin: String,
+    pub code: HttpError,
+    pub phase: Option<Phase>,
+    pub count: u64,
+}
+
+/// Errors of one category as a share of an origin's requests.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CategoryRate {
+    pub origin: String,
+    pub category: ErrorCategory,
+    pub errors: u64,
+    pub requests: u64,
+    pub rate: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StatsSnapshot {
+    /// Requests per origin, successful or not.
+    pub requests: BTreeMap<String, u64>,
+    /// Sorted by origin, code and phase.
+    pub errors: Vec<ErrorCount>,
+}
+
+impl StatsSnapshot {
+    /// Errors of `code` at `origin`, over all phases.
+    pub fn count(&self, origin: &str, code: HttpError) -> u64 {
+        self.errors.iter().filter(|e| e.origin == origin && e.code == code).map(|e| e.count).sum()
+    }
+
+    /// Every origin/category pair with at least one error, in origin then
+    /// `ErrorCategory::ALL` order.
+    pub fn category_rates(&self) -> Vec<CategoryRate> {
+        let mut errors: BTreeMap<(&str, usize), u64> = BTreeMap::new();
+        for error in &self.errors {
+            let category = error.code.category();
+            let index = ErrorCategory::ALL.iter().position(|c| *c == category).unwrap_or_default();
+            *errors.entry((error.origin.as_str(), index)).or_default() += error.count;
+        }
+        errors
+            .into_iter()
+            .map(|((origin, index), errors)| {
+                let requests = self.requests.get(origin).copied().unwrap_or_default();
+                CategoryRate {
+                    origin: origin.to_string(),
+                    category: ErrorCategory::ALL[index],
+                    errors,
+                    requests,
+                    rate: if requests == 0 { 0.0 } else { errors as f64 / requests as f64 },
+                }
+            })
+            .collect()
+    }
+
+    /// Prometheus text exposition format (version 0.0.4).
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+        out.push_str("# HELP net_requests_total Requests completed, by origin.\n");
+        out.push_str("# TYPE net_requests_total counter\n");
+        for (origin, count) in &self.requests {
+            let _ = writeln!(out, "net_requests_total{{origin=\"{}\"}} {count}", escape(origin));
+        }
+        out.push_str("# HELP net_errors_total Net errors, by origin, phase and code.\n");
+        out.push_str("# TYPE net_errors_total counter\n");
+        for error in &self.errors {
+            let _ = writeln!(
+                out,
+                "net_errors_total{{origin=\"{}\",phase=\"{}\",code=\"{}\",name=\"{}\",category=\"{}\"}} {}",
+                escape(&error.origin),
+                error.phase.map_or("unknown", Phase::as_str),
+                error.code.as_i32(),
+                error.code.name().unwrap_or(""),
+                error.code.category().as_str(),
+                error.count,
+            );
+        }
+        out.push_str("# HELP net_error_rate Errors per request, by origin and category.\n");
+        out.push_str("# TYPE net_error_rate gauge\n");
+        for rate in self.category_rates() {
+            let _ = writeln!(
+                out,
+                "net_error_rate{{origin=\"{}\",category=\"{}\"}} {}",
+                escape(&rate.origin),
+                rate.category.as_str(),
+                rate.rate,
+            );
+        }
+        out
+    }
+}
+
+// Label values escape backslash, double quote and newline.
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::thread;
+
+    use super::*;
+
+    const EXAMPLE: &str = "https://example.com";
+    const OTHER: &str = "https://other.example";
+
+    fn stats() -> ErrorStats {
+        let stats = ErrorStats::new();
+        stats.record_success(EXAMPLE);
+        stats.record_success(EXAMPLE);
+        stats.record_error(EXAMPLE, Some(Phase::Tls), HttpError::SslProtocolError);
+        stats.record_error(EXAMPLE, Some(Phase::Connect), HttpError::ConnectionRefused);
+        stats.record_error(EXAMPLE, Some(Phase::Read), HttpError::ConnectionReset);
+        stats.record(OTHER, Some(Phase::Dns), Err(HttpError::NameNotResolved));
+        stats.record(OTHER, None, Err(HttpError::Unknown(-12345)));
+        stats
+    }
+
+    #[test]
+    fn snapshot_is_sorted_and_complete() {
+        let snapshot = stats().snapshot();
+        assert_eq!(
+            snapshot.requests,
+            BTreeMap::from([(EXAMPLE.to_string(), 5), (OTHER.to_string(), 2)])
+        );
+        let errors: Vec<_> =
+            snapshot.errors.iter().map(|e| (e.origin.as_str(), e.code, e.phase, e.count)).collect();
+        assert_eq!(
+            errors,
+            [
+                (EXAMPLE, HttpError::SslProtocolError, Some(Phase::Tls), 1),
+                (EXAMPLE, HttpError::ConnectionRefused, Some(Phase::Connect), 1),
+                (EXAMPLE, HttpError::ConnectionReset, Some(Phase::Read), 1),
+                (OTHER, HttpError::Unknown(-12345), None, 1),
+                (OTHER, HttpError::NameNotResolved, Some(Phase::Dns), 1),
+            ]
+        );
+        assert_eq!(snapshot.count(EXAMPLE, HttpError::ConnectionReset), 1);
+        assert_eq!(snapshot.count(OTHER, HttpError::ConnectionReset), 0);
+    }
+
+    #[test]
+    fn count_sums_phases() {
+        let stats = ErrorStats::new();
+        for phase in [None, Some(Phase::Dns), Some(Phase::Read), Some(Phase::Read)] {
+            stats.record_error(EXAMPLE, phase, HttpError::TimedOut);
+        }
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.errors.len(), 3);
+        assert_eq!(snapshot.count(EXAMPLE, HttpError::TimedOut), 4);
+    }
+
+    #[test]
+    fn record_net_error_uses_the_url_origin() {
+        let stats = ErrorStats::new();
+        let url = Url::parse("https://example.com:8443/path?q").unwrap();
+        stats.record_net_error(&NetError::new(HttpError::ConnectionReset).with_url(url));
+        stats.record_net_error(&NetError::new(HttpError::ConnectionReset));
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.count("https://example.com:8443", HttpError::ConnectionReset), 1);
+        assert_eq!(snapshot.count(UNKNOWN_ORIGIN, HttpError::ConnectionReset), 1);
+    }
+
+    #[test]
+    fn category_rates() {
+        let rates = stats().snapshot().category_rates();
+        let rates: Vec<_> = rates
+            .iter()
+            .map(|r| (r.origin.as_str(), r.category, r.errors, r.requests, r.rate))
+            .collect();
+        assert_eq!(
+            rates,
+            [
+                (EXAMPLE, ErrorCategory::Connection, 2, 5, 0.4),
+                (EXAMPLE, ErrorCategory::Tls, 1, 5, 0.2),
+                (OTHER, ErrorCategory::Dns, 1, 2, 0.5),
+                (OTHER, ErrorCategory::Other, 1, 2, 0.5),
+            ]
+        );
+    }
+
+    #[test]
+    fn prometheus_text() {
+        let stats = ErrorStats::new();
+        stats.record_success("https://a.example");
+        stats.record_error("https://a.example", Some(Phase::Tls), HttpError::SslProtocolError);
+        stats.record_error("weird\"origin\\\n", None, HttpError::Unknown(-12345));
+        assert_eq!(
+            stats.snapshot().to_prometheus(),
+            "# HELP net_requests_total Requests completed, by origin.\n\
+             # TYPE net_requests_total counter\n\
+             net_requests_total{origin=\"https://a.example\"} 2\n\
+             net_requests_total{origin=\"weird\\\"origin\\\\\\n\"} 1\n\
+             # HELP net_errors_total Net errors, by origin, phase and code.\n\
+             # TYPE net_errors_total counter\n\
+             net_errors_total{origin=\"https://a.example\",phase=\"tls\",code=\"-107\",\
+             name=\"ERR_SSL_PROTOCOL_ERROR\",category=\"tls\"} 1\n\
+             net_errors_total{origin=\"weird\\\"origin\\\\\\n\",phase=\"unknown\",code=\"-12345\",\
+             name=\"\",category=\"other\"} 1\n\
+             # HELP net_error_rate Errors per request, by origin and category.\n\
+             # TYPE net_error_rate gauge\n\
+             net_error_rate{origin=\"https://a.example\",category=\"tls\"} 0.5\n\
+             net_error_rate{origin=\"weird\\\"origin\\\\\\n\",category=\"other\"} 1\n"
+        );
+    }
+
+    #[test]
+    fn clear_zeroes_everything() {
+        let stats = stats();
+        stats.clear();
+        assert_eq!(stats.snapshot(), StatsSnapshot::default());
+
+        stats.record_error(OTHER, None, HttpError::Unknown(-12345));
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.requests, BTreeMap::from([(OTHER.to_string(), 1)]));
+        assert_eq!(snapshot.count(OTHER, HttpError::Unknown(-12345)), 1);
+    }
+
+    #[test]
+    fn concurrent_recording_loses_nothing() {
+        const THREADS: usize = 8;
+        const PER_THREAD: usize = 1000;
+        let stats = Arc::new(ErrorStats::new());
+        let threads: Vec<_> = (0..THREADS)
+            .map(|t| {
+                let stats = Arc::clone(&stats);
+                thread::spawn(move || {
+                    for i in 0..PER_THREAD {
+                        // Many origins and unknown codes first seen concurrently.
+                        let origin = format!("https://host{}.example", i % 100);
+                        stats.record_error(&origin, Some(Phase::Read), HttpError::ConnectionReset);
+                        stats.record_error(
+                            &origin,
+                            None,
+                            HttpError::Unknown(-10_000 - (i % 7) as i32),
+                        );
+                        stats.record_success(&origin);
+                        if t == 0 && i % 100 == 0 {
+                            let _ = stats.snapshot();
+                        }
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.requests.len(), 100);
+        assert!(snapshot.requests.values().all(|&r| r == (3 * THREADS * PER_THREAD / 100) as u64));
+        let total = |pred: fn(&ErrorCount) -> bool| -> u64 {
+            snapshot.errors.iter().filter(|e| pred(e)).map(|e| e.count).sum()
+        };
+        assert_eq!(total(|e| e.code == HttpError::ConnectionReset), (THREADS * PER_THREAD) as u64);
+        assert_eq!(total(|e| e.code.name().is_none()), (THREADS * PER_THREAD) as u64);
+        // Each origin/unknown code pair got exactly one entry.
+        assert_eq!(snapshot.errors.iter().filter(|e| e.code.name().is_none()).count(), 100 * 7);
+    }
+}
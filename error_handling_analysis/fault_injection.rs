@@ -0,0 +1,425 @@
+use std::io;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::task::{ready, Context, Poll};
+
+use dashmap::DashMap;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use crate::net_error::Phase;
+use crate::rust_errors::HttpError;
+
+// Scripted failures for testing retry and fallback paths.
+//
+// A `FaultInjector` holds rules such as "the third connection to origin X
+// fails with ERR_CONNECTION_RESET after 512 bytes". The connect path asks it
+// for a `ConnectionFaults` once per connection attempt and then checks each
+// phase against it; `Send` and `Read` faults are applied by wrapping the
+// socket in a `FaultyStream`.
+//
+// Injected socket errors are `io::Error`s of the matching kind that carry the
+// `HttpError` itself, so `map_system_error` (and hyper's source chain) yields
+// exactly the scripted code. Timeout codes are not returned at all: the phase
+// or stream stalls, and whatever timeout guards it fires as it would for a
+// real unresponsive peer.
+//
+// Probabilistic rules are decided from the seed, the origin, the connection
+// number and the rule index. Connection numbers are counted per origin, in
+// the order connects start, so a run replays identically as long as each
+// origin sees its connects in the same order.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FaultRule {
+    origin: Option<String>,
+    phase: Phase,
+    connection: Option<u32>,
+    after_bytes: u64,
+    probability: f64,
+    error: HttpError,
+}
+
+impl FaultRule {
+    /// Fails every connection in `phase` with `error`.
+    pub fn new(phase: Phase, error: HttpError) -> Self {
+        Self { origin: None, phase, connection: None, after_bytes: 0, probability: 1.0, error }
+    }
+
+    /// Only connections to `origin`, as serialized by `ErrorStats::origin_of`.
+    pub fn with_origin(mut self, origin: impl Into<String>) -> Self {
+        self.origin = Some(origin.into());
+        self
+    }
+
+    /// Only the `n`th connection to the origin, counting from 1.
+    pub fn with_connection(mut self, n: u32) -> Self {
+        self.connection = Some(n);
+        self
+    }
+
+    /// For `Send` and `Read`: let this many bytes through before failing.
+    pub fn with_after_bytes(mut self, bytes: u64) -> Self {
+        self.after_bytes = bytes;
+        self
+    }
+
+    pub fn with_probability(mut self, probability: f64) -> Self {
+        self.probability = probability.clamp(0.0, 1.0);
+        self
+    }
+
+    fn matches(&self, origin: &str, connection: u32) -> bool {
+        self.origin.as_deref().is_none_or(|o| o == origin)
+            && self.connection.is_none_or(|n| n == connection)
+    }
+}
+
+pub struct FaultInjector {
+    seed: u64,
+    rules: Vec<FaultRule>,
+    connections: DashMap<String, AtomicU32>,
+}
+
+impl FaultInjector {
+    pub fn new(seed: u64) -> Self {
+        Self { seed, rules: Vec::new(), connections: DashMap::new() }
+    }
+
+    /// Rules are tried in the order they were added; the first one that
+    /// fires wins its phase.
+    pub fn with_rule(mut self, rule: FaultRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Counts a new connection attempt to `origin` and decides its faults.
+    pub fn connection(&self, origin: &str) -> ConnectionFaults {
+        let connection = match self.connections.get(origin) {
+            Some(count) => count.fetch_add(1, Ordering::Relaxed) + 1,
+            None => {
+                self.connections
+                    .entry(origin.to_string())
+                    .or_default()
+                    .fetch_add(1, Ordering::Relaxed)
+                    + 1
+            }
+        };
+        let mut faults = ConnectionFaults { connection, faults: Vec::new() };
+        for (index, rule) in self.rules.iter().enumerate() {
+            if faults.faults.iter().any(|f| f.phase == rule.phase)
+                || !rule.matches(origin, connection)
+            {
+                continue;
+            }
+            if rule.probability >= 1.0 || self.roll(origin, connection, index) < rule.probability {
+                faults.faults.push(Fault {
+                    phase: rule.phase,
+                    after_bytes: rule.after_bytes,
+                    error: rule.error,
+                });
+            }
+        }
+        faults
+    }
+
+    /// Forgets connection counts, so a replay starts again from connection 1.
+    pub fn reset(&self) {
+        self.connections.clear();
+    }
+
+    // Uniform in [0, 1), a function of the inputs only.
+    fn roll(&self, origin: &str, connection: u32, rule: usize) -> f64 {
+        // FNV-1a, so the value does not depend on std's hasher.
+        let mut hash = 0xcbf2_9ce4_8422_2325_u64;
+        for byte in origin.bytes() {
+            hash = (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3);
+        }
+        let mixed =
+            splitmix64(self.seed ^ hash ^ splitmix64(u64::from(connection) << 32 | rule as u64));
+        (mixed >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+fn splitmix64(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Fault {
+    phase: Phase,
+    after_bytes: u64,
+    error: HttpError,
+}
+
+/// The faults decided for one connection.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConnectionFaults {
+    connection: u32,
+    faults: Vec<Fault>,
+}
+
+impl ConnectionFaults {
+    /// No faults; for connections made without an injector.
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    /// Which connection to the origin this is, counting from 1.
+    pub fn connection(&self) -> u32 {
+        self.connection
+    }
+
+    /// The error to fail `phase` with, if any. `Send` and `Read` faults are
+    /// only reported here when they fire before the first byte, and timeouts
+    /// are reported by `stalls` instead.
+    pub fn check(&self, phase: Phase) -> Result<(), HttpError> {
+        match self.fault(phase) {
+            Some(fault) if fault.after_bytes == 0 && !is_timeout(fault.error) => Err(fault.error),
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether `phase` has a scripted timeout: it should hang until its own
+    /// timeout fires.
+    pub fn stalls(&self, phase: Phase) -> bool {
+        self.fault(phase).is_some_and(|f| f.after_bytes == 0 && is_timeout(f.error))
+    }
+
+    /// Applies the `Send` and `Read` faults to `stream`.
+    pub fn wrap<S>(&self, stream: S) -> FaultyStream<S> {
+        let limit = |phase| {
+            self.fault(phase).map(|f| ByteLimit { remaining: f.after_bytes, error: f.error })
+        };
+        FaultyStream { inner: stream, send: limit(Phase::Send), read: limit(Phase::Read) }
+    }
+
+    fn fault(&self, phase: Phase) -> Option<&Fault> {
+        self.faults.iter().find(|f| f.phase == phase)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ByteLimit {
+    remaining: u64,
+    error: HttpError,
+}
+
+impl ByteLimit {
+    // How much of `len` may pass, or the error once the budget is spent. A
+    // spent timeout budget never becomes ready, like a peer that went quiet;
+    // the caller's own read or write timeout ends the wait.
+    fn allow(&self, len: usize) -> Poll<io::Result<usize>> {
+        if self.remaining > 0 {
+            Poll::Ready(Ok(len.min(usize::try_from(self.remaining).unwrap_or(usize::MAX))))
+        } else if is_timeout(self.error) {
+            Poll::Pending
+        } else {
+            Poll::Ready(Err(socket_error(self.error)))
+        }
+    }
+}
+
+fn is_timeout(error: HttpError) -> bool {
+    matches!(error, HttpError::TimedOut | HttpError::ConnectionTimedOut | HttpError::DnsTimedOut)
+}
+
+/// The `io::Error` a socket would return for `error`, carrying `error` so it
+/// maps back unchanged.
+pub fn socket_error(error: HttpError) -> io::Error {
+    use io::ErrorKind;
+    let kind = match error {
+        HttpError::ConnectionReset => ErrorKind::ConnectionReset,
+        HttpError::ConnectionAborted => ErrorKind::ConnectionAborted,
+        HttpError::ConnectionRefused => ErrorKind::ConnectionRefused,
+        HttpError::ConnectionClosed | HttpError::EmptyResponse => ErrorKind::UnexpectedEof,
+        HttpError::TimedOut | HttpError::ConnectionTimedOut => ErrorKind::TimedOut,
+        HttpError::SocketNotConnected => ErrorKind::NotConnected,
+        HttpError::AddressUnreachable => ErrorKind::HostUnreachable,
+        HttpError::InternetDisconnected => ErrorKind::NetworkDown,
+        HttpError::AccessDenied | HttpError::NetworkAccessDenied => ErrorKind::PermissionDenied,
+        _ => ErrorKind::Other,
+    };
+    io::Error::new(kind, error)
+}
+
+/// A socket that fails after a scripted number of bytes in either direction.
+#[derive(Debug)]
+pub struct FaultyStream<S> {
+    inner: S,
+    send: Option<ByteLimit>,
+    read: Option<ByteLimit>,
+}
+
+impl<S> FaultyStream<S> {
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for FaultyStream<S> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let Some(limit) = &mut this.read else {
+            return Pin::new(&mut this.inner).poll_read(cx, buf);
+        };
+        let allowed = ready!(limit.allow(buf.remaining()))?;
+        let mut limited = ReadBuf::new(buf.initialize_unfilled_to(allowed));
+        ready!(Pin::new(&mut this.inner).poll_read(cx, &mut limited))?;
+        let n = limited.filled().len();
+        buf.advance(n);
+        limit.remaining -= n as u64;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for FaultyStream<S> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        data: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let Some(limit) = &mut this.send else {
+            return Pin::new(&mut this.inner).poll_write(cx, data);
+        };
+        let allowed = ready!(limit.allow(data.len()))?;
+        let n = ready!(Pin::new(&mut this.inner).poll_write(cx, &data[..allowed]))?;
+        limit.remaining -= n as u64;
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+
+    const ORIGIN: &str = "https://example.com";
+
+    fn decisions(injector: &FaultInjector, origin: &str, n: usize) -> Vec<bool> {
+        (0..n).map(|_| injector.connection(origin).check(Phase::Connect).is_err()).collect()
+    }
+
+    fn flaky(seed: u64) -> FaultInjector {
+        FaultInjector::new(seed).with_rule(
+            FaultRule::new(Phase::Connect, HttpError::ConnectionReset).with_probability(0.5),
+        )
+    }
+
+    #[test]
+    fn same_seed_replays() {
+        let first = decisions(&flaky(7), ORIGIN, 200);
+        assert_eq!(decisions(&flaky(7), ORIGIN, 200), first);
+        assert_ne!(decisions(&flaky(8), ORIGIN, 200), first);
+        let failures = first.iter().filter(|&&f| f).count();
+        assert!((60..140).contains(&failures), "{failures}");
+
+        // Counts are per origin, and `reset` starts the replay over.
+        let injector = flaky(7);
+        decisions(&injector, "https://other.example", 10);
+        assert_eq!(decisions(&injector, ORIGIN, 200), first);
+        injector.reset();
+        assert_eq!(decisions(&injector, ORIGIN, 200), first);
+    }
+
+    #[test]
+    fn rules_are_per_phase_origin_and_connection() {
+        let injector = FaultInjector::new(1)
+            .with_rule(FaultRule::new(Phase::Tls, HttpError::SslProtocolError).with_connection(2))
+            .with_rule(FaultRule::new(Phase::Tls, HttpError::CertDateInvalid))
+            .with_rule(FaultRule::new(Phase::Dns, HttpError::NameNotResolved).with_origin(ORIGIN));
+
+        let first = injector.connection(ORIGIN);
+        assert_eq!(first.connection(), 1);
+        assert_eq!(first.check(Phase::Dns), Err(HttpError::NameNotResolved));
+        assert_eq!(first.check(Phase::Tls), Err(HttpError::CertDateInvalid));
+        assert_eq!(first.check(Phase::Connect), Ok(()));
+
+        // The first rule that fires wins its phase.
+        let second = injector.connection(ORIGIN);
+        assert_eq!(second.check(Phase::Tls), Err(HttpError::SslProtocolError));
+
+        let other = injector.connection("https://other.example");
+        assert_eq!(other.connection(), 1);
+        assert_eq!(other.check(Phase::Dns), Ok(()));
+        assert_eq!(ConnectionFaults::none().check(Phase::Tls), Ok(()));
+    }
+
+    #[test]
+    fn timeouts_stall_instead_of_failing() {
+        let injector = FaultInjector::new(1)
+            .with_rule(FaultRule::new(Phase::Connect, HttpError::ConnectionTimedOut));
+        let faults = injector.connection(ORIGIN);
+        assert_eq!(faults.check(Phase::Connect), Ok(()));
+        assert!(faults.stalls(Phase::Connect));
+        assert!(!faults.stalls(Phase::Tls));
+    }
+
+    #[tokio::test]
+    async fn read_fails_after_bytes() {
+        let injector = FaultInjector::new(1)
+            .with_rule(FaultRule::new(Phase::Read, HttpError::ConnectionReset).with_after_bytes(5));
+        let faults = injector.connection(ORIGIN);
+        assert_eq!(faults.check(Phase::Read), Ok(()));
+
+        let (client, mut server) = tokio::io::duplex(64);
+        server.write_all(b"hello world").await.unwrap();
+        let mut stream = faults.wrap(client);
+        let mut buf = [0u8; 64];
+        assert_eq!(stream.read(&mut buf).await.unwrap(), 5);
+        assert_eq!(&buf[..5], b"hello");
+        let error = stream.read(&mut buf).await.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::ConnectionReset);
+        assert_eq!(crate::error_map::map_system_error(&error), HttpError::ConnectionReset);
+    }
+
+    #[tokio::test]
+    async fn send_fails_after_bytes() {
+        let injector = FaultInjector::new(1).with_rule(
+            FaultRule::new(Phase::Send, HttpError::ConnectionAborted).with_after_bytes(3),
+        );
+        let (client, mut server) = tokio::io::duplex(64);
+        let mut stream = injector.connection(ORIGIN).wrap(client);
+        assert_eq!(stream.write(b"abcdef").await.unwrap(), 3);
+        let error = stream.write(b"def").await.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::ConnectionAborted);
+
+        let mut received = [0u8; 3];
+        server.read_exact(&mut received).await.unwrap();
+        assert_eq!(&received, b"abc");
+    }
+
+    #[tokio::test]
+    async fn read_timeout_stalls_after_bytes() {
+        let injector = FaultInjector::new(1)
+            .with_rule(FaultRule::new(Phase::Read, HttpError::TimedOut).with_after_bytes(2));
+        let (client, mut server) = tokio::io::duplex(64);
+        server.write_all(b"abcd").await.unwrap();
+        let mut stream = injector.connection(ORIGIN).wrap(client);
+        let mut buf = [0u8; 8];
+        assert_eq!(stream.read(&mut buf).await.unwrap(), 2);
+        let stalled = tokio::time::timeout(Duration::from_millis(50), stream.read(&mut buf)).await;
+        assert!(stalled.is_err(), "read should hang until the caller's timeout");
+    }
+}
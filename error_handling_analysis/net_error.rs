@@ -0,0 +1,238 @@
+use std::error::Error as StdError;
+use std::fmt;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use url::Url;
+
+use crate::error_map::{
+    map_connect_error, map_h2_error, map_hyper_error, map_openssl_error, map_system_error,
+};
+use crate::rust_errors::HttpError;
+
+/// Where in the request a failure happened.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Phase {
+    Dns,
+    Connect,
+    /// The TLS handshake with an HTTPS proxy.
+    ProxyTls,
+    /// The SOCKS handshake with a proxy.
+    Socks,
+    /// The CONNECT tunnel through an HTTP proxy.
+    Tunnel,
+    Tls,
+    Send,
+    Read,
+}
+
+impl Phase {
+    /// In declaration order, so `phase as usize` indexes it.
+    pub const ALL: [Phase; 8] = [
+        Phase::Dns,
+        Phase::Connect,
+        Phase::ProxyTls,
+        Phase::Socks,
+        Phase::Tunnel,
+        Phase::Tls,
+        Phase::Send,
+        Phase::Read,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Phase::Dns => "dns",
+            Phase::Connect => "connect",
+            Phase::ProxyTls => "proxy_tls",
+            Phase::Socks => "socks",
+            Phase::Tunnel => "tunnel",
+            Phase::Tls => "tls",
+            Phase::Send => "send",
+            Phase::Read => "read",
+        }
+    }
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// An `HttpError` with the context needed to debug it: the request URL, the
+/// remote endpoint, the phase, the attempt number and the OS/TLS/protocol
+/// error it was mapped from, exposed through `source()`.
+///
+/// Equality looks at the code only, so `NetError` can be matched against
+/// `HttpError` values (`err == HttpError::ConnectionReset`).
+#[derive(Clone)]
+pub struct NetError {
+    code: HttpError,
+    url: Option<Url>,
+    endpoint: Option<SocketAddr>,
+    phase: Option<Phase>,
+    attempt: u32,
+    source: Option<Arc<dyn StdError + Send + Sync + 'static>>,
+}
+
+impl NetError {
+    pub fn new(code: HttpError) -> Self {
+        Self { code, url: None, endpoint: None, phase: None, attempt: 0, source: None }
+    }
+
+    /// Maps an I/O error for `phase`; connect failures use `MapConnectError`.
+    pub fn from_io(phase: Phase, error: io::Error) -> Self {
+        let code = match phase {
+            Phase::Connect => map_connect_error(&error),
+            _ => map_system_error(&error),
+        };
+        Self::new(code).with_phase(phase).with_source(error)
+    }
+
+    pub fn with_url(mut self, url: Url) -> Self {
+        self.url = Some(url);
+        self
+    }
+
+    pub fn with_endpoint(mut self, endpoint: SocketAddr) -> Self {
+        self.endpoint = Some(endpoint);
+        self
+    }
+
+    pub fn with_phase(mut self, phase: Phase) -> Self {
+        self.phase = Some(phase);
+        self
+    }
+
+    /// Zero-based; 0 is the first try.
+    pub fn with_attempt(mut self, attempt: u32) -> Self {
+        self.attempt = attempt;
+        self
+    }
+
+    pub fn with_source(mut self, source: impl StdError + Send + Sync + 'static) -> Self {
+        self.source = Some(Arc::new(source));
+        self
+    }
+
+    pub fn code(&self) -> HttpError {
+        self.code
+    }
+
+    pub fn url(&self) -> Option<&Url> {
+        self.url.as_ref()
+    }
+
+    pub fn endpoint(&self) -> Option<SocketAddr> {
+        self.endpoint
+    }
+
+    pub fn phase(&self) -> Option<Phase> {
+        self.phase
+    }
+
+    pub fn attempt(&self) -> u32 {
+        self.attempt
+    }
+}
+
+// Connection reset (TCP RST) [ERR_CONNECTION_RESET] during read from
+// 93.184.216.34:443 for https://example.com/ (attempt 2)
+impl fmt::Display for NetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.code)?;
+        match self.code.name() {
+            Some(name) => write!(f, " [{name}]")?,
+            None => write!(f, " [{}]", self.code.as_i32())?,
+        }
+        if let Some(phase) = self.phase {
+            write!(f, " during {phase}")?;
+        }
+        if let Some(endpoint) = self.endpoint {
+            write!(f, " from {endpoint}")?;
+        }
+        if let Some(url) = &self.url {
+            write!(f, " for {url}")?;
+        }
+        if self.attempt > 0 {
+            write!(f, " (attempt {})", self.attempt + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for NetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NetError")
+            .field("code", &self.code)
+            .field("url", &self.url.as_ref().map(Url::as_str))
+            .field("endpoint", &self.endpoint)
+            .field("phase", &self.phase)
+            .field("attempt", &self.attempt)
+            .field("source", &self.source)
+            .finish()
+    }
+}
+
+impl StdError for NetError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        self.source.as_deref().map(|e| e as &(dyn StdError + 'static))
+    }
+}
+
+impl PartialEq for NetError {
+    fn eq(&self, other: &Self) -> bool {
+        self.code == other.code
+    }
+}
+
+impl Eq for NetError {}
+
+impl PartialEq<HttpError> for NetError {
+    fn eq(&self, other: &HttpError) -> bool {
+        self.code == *other
+    }
+}
+
+impl PartialEq<NetError> for HttpError {
+    fn eq(&self, other: &NetError) -> bool {
+        *self == other.code
+    }
+}
+
+impl From<HttpError> for NetError {
+    fn from(code: HttpError) -> Self {
+        NetError::new(code)
+    }
+}
+
+impl From<NetError> for HttpError {
+    fn from(error: NetError) -> Self {
+        error.code
+    }
+}
+
+impl From<io::Error> for NetError {
+    fn from(error: io::Error) -> Self {
+        NetError::new(map_system_error(&error)).with_source(error)
+    }
+}
+
+impl From<boring::ssl::Error> for NetError {
+    fn from(error: boring::ssl::Error) -> Self {
+        NetError::new(map_openssl_error(&error)).with_phase(Phase::Tls).with_source(error)
+    }
+}
+
+impl From<h2::Error> for NetError {
+    fn from(error: h2::Error) -> Self {
+        NetError::new(map_h2_error(&error)).with_source(error)
+    }
+}
+
+impl From<hyper::Error> for NetError {
+    fn from(error: hyper::Error) -> Self {
+        NetError::new(map_hyper_error(&error)).with_source(error)
+    }
+}
@@ -1,546 +1,363 @@
+use std::str::FromStr;
 
 use thiserror::Error;
 
-#[derive(Debug, Error, PartialEq, Eq, Clone, Copy)]
-pub enum HttpError {
+// Every Chromium net error, in the format of net/base/net_error_list.h.
+//
+// Each entry is `NET_ERROR(NAME, code) => Variant, "description";`: the first
+// half is the line from net_error_list.h verbatim, the second names the Rust
+// variant and its `Display` text. `net_errors!` expands the table into the
+// enum, both directions of the code mapping and `HttpError::ALL`, so adding
+// a code is a one-line change. Codes Chromium has retired are left out and
+// come back as `Unknown`.
+macro_rules! net_errors {
+    ($(NET_ERROR($name:ident, $code:literal) => $variant:ident, $description:literal;)*) => {
+        #[derive(Debug, Error, PartialEq, Eq, Hash, Clone, Copy)]
+        pub enum HttpError {
+            $(
+                #[error($description)]
+                $variant,
+            )*
+            #[error("Unknown error: {0}")]
+            Unknown(i32),
+        }
+
+        impl HttpError {
+            /// Every known error, in `net_error_list.h` order.
+            pub const ALL: &'static [HttpError] = &[$(HttpError::$variant),*];
+
+            pub const fn as_i32(&self) -> i32 {
+                match self {
+                    $(HttpError::$variant => $code,)*
+                    HttpError::Unknown(code) => *code,
+                }
+            }
+
+            /// Chromium's symbolic name, e.g. `ERR_CONNECTION_RESET`, as used in
+            /// NetLog and `net-export` dumps. `None` for `Unknown` codes.
+            pub const fn name(&self) -> Option<&'static str> {
+                match self {
+                    $(HttpError::$variant => Some(concat!("ERR_", stringify!($name))),)*
+                    HttpError::Unknown(_) => None,
+                }
+            }
+
+            /// `From<i32>` usable in const context.
+            // A code listed twice makes the second arm unreachable.
+            #[deny(unreachable_patterns)]
+            pub const fn from_i32(code: i32) -> Self {
+                match code {
+                    $($code => HttpError::$variant,)*
+                    _ => HttpError::Unknown(code),
+                }
+            }
+
+            /// Position in `ALL`; `None` for `Unknown` codes. Used for the
+            /// round-trip check below and to index per-code tables.
+            pub(crate) const fn index(&self) -> Option<usize> {
+                let mut i = 0;
+                $(
+                    if let HttpError::$variant = self {
+                        return Some(i);
+                    }
+                    i += 1;
+                )*
+                let _ = i;
+                None
+            }
+        }
+    };
+}
+
+net_errors! {
+    // Generic Errors
+    NET_ERROR(IO_PENDING, -1) => IoPending, "IO pending";
+    NET_ERROR(FAILED, -2) => Failed, "Generic failure";
+    NET_ERROR(ABORTED, -3) => Aborted, "Operation aborted";
+    NET_ERROR(INVALID_ARGUMENT, -4) => InvalidArgument, "Invalid argument";
+    NET_ERROR(INVALID_HANDLE, -5) => InvalidHandle, "Invalid handle";
+    NET_ERROR(FILE_NOT_FOUND, -6) => FileNotFound, "File not found";
+    NET_ERROR(TIMED_OUT, -7) => TimedOut, "Operation timed out";
+    NET_ERROR(FILE_TOO_BIG, -8) => FileTooBig, "File too big";
+    NET_ERROR(UNEXPECTED, -9) => Unexpected, "Unexpected error";
+    NET_ERROR(ACCESS_DENIED, -10) => AccessDenied, "Access denied";
+    NET_ERROR(NOT_IMPLEMENTED, -11) => NotImplemented, "Not implemented";
+    NET_ERROR(INSUFFICIENT_RESOURCES, -12) => InsufficientResources, "Insufficient resources";
+    NET_ERROR(OUT_OF_MEMORY, -13) => OutOfMemory, "Out of memory";
+    NET_ERROR(UPLOAD_FILE_CHANGED, -14) => UploadFileChanged, "Upload file changed";
+    NET_ERROR(SOCKET_NOT_CONNECTED, -15) => SocketNotConnected, "Socket not connected";
+    NET_ERROR(FILE_EXISTS, -16) => FileExists, "File exists";
+    NET_ERROR(FILE_PATH_TOO_LONG, -17) => FilePathTooLong, "File path too long";
+    NET_ERROR(FILE_NO_SPACE, -18) => FileNoSpace, "No space left for file";
+    NET_ERROR(FILE_VIRUS_INFECTED, -19) => FileVirusInfected, "File virus infected";
+    NET_ERROR(BLOCKED_BY_CLIENT, -20) => BlockedByClient, "Blocked by client";
+    NET_ERROR(NETWORK_CHANGED, -21) => NetworkChanged, "Network changed";
+    NET_ERROR(BLOCKED_BY_ADMINISTRATOR, -22) => BlockedByAdministrator, "Blocked by administrator";
+    NET_ERROR(SOCKET_IS_CONNECTED, -23) => SocketIsConnected, "Socket is already connected";
+    NET_ERROR(UPLOAD_STREAM_REWIND_NOT_SUPPORTED, -25) => UploadStreamRewindNotSupported, "Upload stream rewind not supported";
+    NET_ERROR(CONTEXT_SHUT_DOWN, -26) => ContextShutDown, "Request context shut down";
+    NET_ERROR(BLOCKED_BY_RESPONSE, -27) => BlockedByResponse, "Blocked by response";
+    NET_ERROR(CLEARTEXT_NOT_PERMITTED, -29) => CleartextNotPermitted, "Cleartext traffic not permitted";
+    NET_ERROR(BLOCKED_BY_CSP, -30) => BlockedByCsp, "Blocked by Content Security Policy";
+    NET_ERROR(BLOCKED_BY_ORB, -32) => BlockedByOrb, "Blocked by Opaque Response Blocking";
+    NET_ERROR(NETWORK_ACCESS_REVOKED, -33) => NetworkAccessRevoked, "Network access revoked";
+    NET_ERROR(BLOCKED_BY_FINGERPRINTING_PROTECTION, -34) => BlockedByFingerprintingProtection, "Blocked by fingerprinting protection";
+
     // Connection Errors
-    #[error("Connection closed (TCP FIN)")]
-    ConnectionClosed,
-    #[error("Connection reset (TCP RST)")]
-    ConnectionReset,
-    #[error("Connection refused")]
-    ConnectionRefused,
-    #[error("Connection aborted")]
-    ConnectionAborted,
-    #[error("Connection failed")]
-    ConnectionFailed,
-    #[error("Name not resolved")]
-    NameNotResolved,
-    #[error("Internet disconnected")]
-    InternetDisconnected,
-    #[error("SSL protocol error")]
-    SslProtocolError,
-    #[error("Address invalid")]
-    AddressInvalid,
-    #[error("Address unreachable")]
-    AddressUnreachable,
-    #[error("SSL client auth cert needed")]
-    SslClientAuthCertNeeded,
-    #[error("Tunnel connection failed")]
-    TunnelConnectionFailed,
-    #[error("SSL version or cipher mismatch")]
-    SslVersionOrCipherMismatch,
-    #[error("SSL renegotiation requested")]
-    SslRenegotiationRequested,
-    #[error("Proxy auth unsupported")]
-    ProxyAuthUnsupported,
-    #[error("Bad SSL client auth cert")]
-    BadSslClientAuthCert,
-    #[error("Connection timed out")]
-    ConnectionTimedOut,
-    #[error("Host resolver queue too large")]
-    HostResolverQueueTooLarge,
-    #[error("SOCKS connection failed")]
-    SocksConnectionFailed,
-    #[error("SOCKS connection host unreachable")]
-    SocksConnectionHostUnreachable,
-    #[error("ALPN negotiation failed")]
-    AlpnNegotiationFailed,
-    #[error("SSL no renegotiation")]
-    SslNoRenegotiation,
-    #[error("Winsock unexpected written bytes")]
-    WinsockUnexpectedWrittenBytes,
-    #[error("SSL decompression failure alert")]
-    SslDecompressionFailureAlert,
-    #[error("SSL bad record MAC alert")]
-    SslBadRecordMacAlert,
-    #[error("Proxy auth requested")]
-    ProxyAuthRequested,
-    #[error("Proxy connection failed")]
-    ProxyConnectionFailed,
-    #[error("Mandatory proxy configuration failed")]
-    MandatoryProxyConfigurationFailed,
-    #[error("Preconnect max socket limit")]
-    PreconnectMaxSocketLimit,
-    #[error("SSL client auth private key access denied")]
-    SslClientAuthPrivateKeyAccessDenied,
-    #[error("SSL client auth cert no private key")]
-    SslClientAuthCertNoPrivateKey,
-    #[error("Proxy certificate invalid")]
-    ProxyCertificateInvalid,
-    #[error("Name resolution failed")]
-    NameResolutionFailed,
-    #[error("Network access denied")]
-    NetworkAccessDenied,
-    #[error("Temporarily throttled")]
-    TemporarilyThrottled,
-    #[error("SSL client auth signature failed")]
-    SslClientAuthSignatureFailed,
-    #[error("Message too big")]
-    MsgTooBig,
-    #[error("WebSocket protocol error")]
-    WsProtocolError,
-    #[error("Address in use")]
-    AddressInUse,
-    #[error("SSL pinned key not in cert chain")]
-    SslPinnedKeyNotInCertChain,
-    #[error("Client auth cert type unsupported")]
-    ClientAuthCertTypeUnsupported,
-    #[error("SSL decrypt error alert")]
-    SslDecryptErrorAlert,
-    #[error("WebSocket throttle queue too large")]
-    WsThrottleQueueTooLarge,
-    #[error("SSL server cert changed")]
-    SslServerCertChanged,
-    #[error("SSL unrecognized name alert")]
-    SslUnrecognizedNameAlert,
-    #[error("Socket set receive buffer size error")]
-    SocketSetReceiveBufferSizeError,
-    #[error("Socket set send buffer size error")]
-    SocketSetSendBufferSizeError,
-    #[error("Socket receive buffer size unchangeable")]
-    SocketReceiveBufferSizeUnchangeable,
-    #[error("Socket send buffer size unchangeable")]
-    SocketSendBufferSizeUnchangeable,
-    #[error("SSL client auth cert bad format")]
-    SslClientAuthCertBadFormat,
-    #[error("ICANN name collision")]
-    IcannNameCollision,
-    #[error("SSL server cert bad format")]
-    SslServerCertBadFormat,
-    #[error("CT STH parsing failed")]
-    CtSthParsingFailed,
-    #[error("CT STH incomplete")]
-    CtSthIncomplete,
-    #[error("Unable to reuse connection for proxy auth")]
-    UnableToReuseConnectionForProxyAuth,
-    #[error("CT consistency proof parsing failed")]
-    CtConsistencyProofParsingFailed,
-    #[error("SSL obsolete cipher")]
-    SslObsoleteCipher,
-    #[error("WebSocket upgrade")]
-    WsUpgrade,
-    #[error("ReadIfReady not implemented")]
-    ReadIfReadyNotImplemented,
-    #[error("No buffer space")]
-    NoBufferSpace,
-    #[error("SSL client auth no common algorithms")]
-    SslClientAuthNoCommonAlgorithms,
-    #[error("Early data rejected")]
-    EarlyDataRejected,
-    #[error("Wrong version on early data")]
-    WrongVersionOnEarlyData,
-    #[error("TLS 1.3 downgrade detected")]
-    Tls13DowngradeDetected,
-    #[error("SSL key usage incompatible")]
-    SslKeyUsageIncompatible,
-    #[error("Invalid ECH config list")]
-    InvalidEchConfigList,
-    #[error("ECH not negotiated")]
-    EchNotNegotiated,
-    #[error("ECH fallback certificate invalid")]
-    EchFallbackCertificateInvalid,
-    #[error("Proxy unable to connect to destination")]
-    ProxyUnableToConnectToDestination,
-    #[error("Proxy delegate canceled connect request")]
-    ProxyDelegateCanceledConnectRequest,
-    #[error("Proxy delegate canceled connect response")]
-    ProxyDelegateCanceledConnectResponse,
+    NET_ERROR(CONNECTION_CLOSED, -100) => ConnectionClosed, "Connection closed (TCP FIN)";
+    NET_ERROR(CONNECTION_RESET, -101) => ConnectionReset, "Connection reset (TCP RST)";
+    NET_ERROR(CONNECTION_REFUSED, -102) => ConnectionRefused, "Connection refused";
+    NET_ERROR(CONNECTION_ABORTED, -103) => ConnectionAborted, "Connection aborted";
+    NET_ERROR(CONNECTION_FAILED, -104) => ConnectionFailed, "Connection failed";
+    NET_ERROR(NAME_NOT_RESOLVED, -105) => NameNotResolved, "Name not resolved";
+    NET_ERROR(INTERNET_DISCONNECTED, -106) => InternetDisconnected, "Internet disconnected";
+    NET_ERROR(SSL_PROTOCOL_ERROR, -107) => SslProtocolError, "SSL protocol error";
+    NET_ERROR(ADDRESS_INVALID, -108) => AddressInvalid, "Address invalid";
+    NET_ERROR(ADDRESS_UNREACHABLE, -109) => AddressUnreachable, "Address unreachable";
+    NET_ERROR(SSL_CLIENT_AUTH_CERT_NEEDED, -110) => SslClientAuthCertNeeded, "SSL client auth cert needed";
+    NET_ERROR(TUNNEL_CONNECTION_FAILED, -111) => TunnelConnectionFailed, "Tunnel connection failed";
+    NET_ERROR(SSL_VERSION_OR_CIPHER_MISMATCH, -113) => SslVersionOrCipherMismatch, "SSL version or cipher mismatch";
+    NET_ERROR(SSL_RENEGOTIATION_REQUESTED, -114) => SslRenegotiationRequested, "SSL renegotiation requested";
+    NET_ERROR(PROXY_AUTH_UNSUPPORTED, -115) => ProxyAuthUnsupported, "Proxy auth unsupported";
+    NET_ERROR(BAD_SSL_CLIENT_AUTH_CERT, -117) => BadSslClientAuthCert, "Bad SSL client auth cert";
+    NET_ERROR(CONNECTION_TIMED_OUT, -118) => ConnectionTimedOut, "Connection timed out";
+    NET_ERROR(HOST_RESOLVER_QUEUE_TOO_LARGE, -119) => HostResolverQueueTooLarge, "Host resolver queue too large";
+    NET_ERROR(SOCKS_CONNECTION_FAILED, -120) => SocksConnectionFailed, "SOCKS connection failed";
+    NET_ERROR(SOCKS_CONNECTION_HOST_UNREACHABLE, -121) => SocksConnectionHostUnreachable, "SOCKS connection host unreachable";
+    NET_ERROR(ALPN_NEGOTIATION_FAILED, -122) => AlpnNegotiationFailed, "ALPN negotiation failed";
+    NET_ERROR(SSL_NO_RENEGOTIATION, -123) => SslNoRenegotiation, "SSL no renegotiation";
+    NET_ERROR(WINSOCK_UNEXPECTED_WRITTEN_BYTES, -124) => WinsockUnexpectedWrittenBytes, "Winsock unexpected written bytes";
+    NET_ERROR(SSL_DECOMPRESSION_FAILURE_ALERT, -125) => SslDecompressionFailureAlert, "SSL decompression failure alert";
+    NET_ERROR(SSL_BAD_RECORD_MAC_ALERT, -126) => SslBadRecordMacAlert, "SSL bad record MAC alert";
+    NET_ERROR(PROXY_AUTH_REQUESTED, -127) => ProxyAuthRequested, "Proxy auth requested";
+    NET_ERROR(PROXY_CONNECTION_FAILED, -130) => ProxyConnectionFailed, "Proxy connection failed";
+    NET_ERROR(MANDATORY_PROXY_CONFIGURATION_FAILED, -131) => MandatoryProxyConfigurationFailed, "Mandatory proxy configuration failed";
+    NET_ERROR(PRECONNECT_MAX_SOCKET_LIMIT, -133) => PreconnectMaxSocketLimit, "Preconnect max socket limit";
+    NET_ERROR(SSL_CLIENT_AUTH_PRIVATE_KEY_ACCESS_DENIED, -134) => SslClientAuthPrivateKeyAccessDenied, "SSL client auth private key access denied";
+    NET_ERROR(SSL_CLIENT_AUTH_CERT_NO_PRIVATE_KEY, -135) => SslClientAuthCertNoPrivateKey, "SSL client auth cert no private key";
+    NET_ERROR(PROXY_CERTIFICATE_INVALID, -136) => ProxyCertificateInvalid, "Proxy certificate invalid";
+    NET_ERROR(NAME_RESOLUTION_FAILED, -137) => NameResolutionFailed, "Name resolution failed";
+    NET_ERROR(NETWORK_ACCESS_DENIED, -138) => NetworkAccessDenied, "Network access denied";
+    NET_ERROR(TEMPORARILY_THROTTLED, -139) => TemporarilyThrottled, "Temporarily throttled";
+    NET_ERROR(SSL_CLIENT_AUTH_SIGNATURE_FAILED, -141) => SslClientAuthSignatureFailed, "SSL client auth signature failed";
+    NET_ERROR(MSG_TOO_BIG, -142) => MsgTooBig, "Message too big";
+    NET_ERROR(WS_PROTOCOL_ERROR, -145) => WsProtocolError, "WebSocket protocol error";
+    NET_ERROR(ADDRESS_IN_USE, -147) => AddressInUse, "Address in use";
+    NET_ERROR(SSL_HANDSHAKE_NOT_COMPLETED, -148) => SslHandshakeNotCompleted, "SSL handshake not completed";
+    NET_ERROR(SSL_BAD_PEER_PUBLIC_KEY, -149) => SslBadPeerPublicKey, "SSL bad peer public key";
+    NET_ERROR(SSL_PINNED_KEY_NOT_IN_CERT_CHAIN, -150) => SslPinnedKeyNotInCertChain, "SSL pinned key not in cert chain";
+    NET_ERROR(CLIENT_AUTH_CERT_TYPE_UNSUPPORTED, -151) => ClientAuthCertTypeUnsupported, "Client auth cert type unsupported";
+    NET_ERROR(SSL_DECRYPT_ERROR_ALERT, -153) => SslDecryptErrorAlert, "SSL decrypt error alert";
+    NET_ERROR(WS_THROTTLE_QUEUE_TOO_LARGE, -154) => WsThrottleQueueTooLarge, "WebSocket throttle queue too large";
+    NET_ERROR(SSL_SERVER_CERT_CHANGED, -156) => SslServerCertChanged, "SSL server cert changed";
+    NET_ERROR(SSL_UNRECOGNIZED_NAME_ALERT, -159) => SslUnrecognizedNameAlert, "SSL unrecognized name alert";
+    NET_ERROR(SOCKET_SET_RECEIVE_BUFFER_SIZE_ERROR, -160) => SocketSetReceiveBufferSizeError, "Socket set receive buffer size error";
+    NET_ERROR(SOCKET_SET_SEND_BUFFER_SIZE_ERROR, -161) => SocketSetSendBufferSizeError, "Socket set send buffer size error";
+    NET_ERROR(SOCKET_RECEIVE_BUFFER_SIZE_UNCHANGEABLE, -162) => SocketReceiveBufferSizeUnchangeable, "Socket receive buffer size unchangeable";
+    NET_ERROR(SOCKET_SEND_BUFFER_SIZE_UNCHANGEABLE, -163) => SocketSendBufferSizeUnchangeable, "Socket send buffer size unchangeable";
+    NET_ERROR(SSL_CLIENT_AUTH_CERT_BAD_FORMAT, -164) => SslClientAuthCertBadFormat, "SSL client auth cert bad format";
+    NET_ERROR(ICANN_NAME_COLLISION, -166) => IcannNameCollision, "ICANN name collision";
+    NET_ERROR(SSL_SERVER_CERT_BAD_FORMAT, -167) => SslServerCertBadFormat, "SSL server cert bad format";
+    NET_ERROR(CT_STH_PARSING_FAILED, -168) => CtSthParsingFailed, "CT STH parsing failed";
+    NET_ERROR(CT_STH_INCOMPLETE, -169) => CtSthIncomplete, "CT STH incomplete";
+    NET_ERROR(UNABLE_TO_REUSE_CONNECTION_FOR_PROXY_AUTH, -170) => UnableToReuseConnectionForProxyAuth, "Unable to reuse connection for proxy auth";
+    NET_ERROR(CT_CONSISTENCY_PROOF_PARSING_FAILED, -171) => CtConsistencyProofParsingFailed, "CT consistency proof parsing failed";
+    NET_ERROR(SSL_OBSOLETE_CIPHER, -172) => SslObsoleteCipher, "SSL obsolete cipher";
+    NET_ERROR(WS_UPGRADE, -173) => WsUpgrade, "WebSocket upgrade";
+    NET_ERROR(READ_IF_READY_NOT_IMPLEMENTED, -174) => ReadIfReadyNotImplemented, "ReadIfReady not implemented";
+    NET_ERROR(NO_BUFFER_SPACE, -176) => NoBufferSpace, "No buffer space";
+    NET_ERROR(SSL_CLIENT_AUTH_NO_COMMON_ALGORITHMS, -177) => SslClientAuthNoCommonAlgorithms, "SSL client auth no common algorithms";
+    NET_ERROR(EARLY_DATA_REJECTED, -178) => EarlyDataRejected, "Early data rejected";
+    NET_ERROR(WRONG_VERSION_ON_EARLY_DATA, -179) => WrongVersionOnEarlyData, "Wrong version on early data";
+    NET_ERROR(TLS13_DOWNGRADE_DETECTED, -180) => Tls13DowngradeDetected, "TLS 1.3 downgrade detected";
+    NET_ERROR(SSL_KEY_USAGE_INCOMPATIBLE, -181) => SslKeyUsageIncompatible, "SSL key usage incompatible";
+    NET_ERROR(INVALID_ECH_CONFIG_LIST, -182) => InvalidEchConfigList, "Invalid ECH config list";
+    NET_ERROR(ECH_NOT_NEGOTIATED, -183) => EchNotNegotiated, "ECH not negotiated";
+    NET_ERROR(ECH_FALLBACK_CERTIFICATE_INVALID, -184) => EchFallbackCertificateInvalid, "ECH fallback certificate invalid";
+    NET_ERROR(PROXY_UNABLE_TO_CONNECT_TO_DESTINATION, -186) => ProxyUnableToConnectToDestination, "Proxy unable to connect to destination";
+    NET_ERROR(PROXY_DELEGATE_CANCELED_CONNECT_REQUEST, -187) => ProxyDelegateCanceledConnectRequest, "Proxy delegate canceled connect request";
+    NET_ERROR(PROXY_DELEGATE_CANCELED_CONNECT_RESPONSE, -188) => ProxyDelegateCanceledConnectResponse, "Proxy delegate canceled connect response";
+
+    // Certificate Errors
+    NET_ERROR(CERT_COMMON_NAME_INVALID, -200) => CertCommonNameInvalid, "Certificate common name invalid";
+    NET_ERROR(CERT_DATE_INVALID, -201) => CertDateInvalid, "Certificate date invalid";
+    NET_ERROR(CERT_AUTHORITY_INVALID, -202) => CertAuthorityInvalid, "Certificate authority invalid";
+    NET_ERROR(CERT_CONTAINS_ERRORS, -203) => CertContainsErrors, "Certificate contains errors";
+    NET_ERROR(CERT_NO_REVOCATION_MECHANISM, -204) => CertNoRevocationMechanism, "Certificate has no revocation mechanism";
+    NET_ERROR(CERT_UNABLE_TO_CHECK_REVOCATION, -205) => CertUnableToCheckRevocation, "Unable to check certificate revocation";
+    NET_ERROR(CERT_REVOKED, -206) => CertRevoked, "Certificate revoked";
+    NET_ERROR(CERT_INVALID, -207) => CertInvalid, "Certificate invalid";
+    NET_ERROR(CERT_WEAK_SIGNATURE_ALGORITHM, -208) => CertWeakSignatureAlgorithm, "Certificate weak signature algorithm";
+    NET_ERROR(CERT_NON_UNIQUE_NAME, -210) => CertNonUniqueName, "Certificate non-unique name";
+    NET_ERROR(CERT_WEAK_KEY, -211) => CertWeakKey, "Certificate weak key";
+    NET_ERROR(CERT_NAME_CONSTRAINT_VIOLATION, -212) => CertNameConstraintViolation, "Certificate name constraint violation";
+    NET_ERROR(CERT_VALIDITY_TOO_LONG, -213) => CertValidityTooLong, "Certificate validity too long";
+    NET_ERROR(CERTIFICATE_TRANSPARENCY_REQUIRED, -214) => CertificateTransparencyRequired, "Certificate Transparency required";
+    NET_ERROR(CERT_KNOWN_INTERCEPTION_BLOCKED, -216) => CertKnownInterceptionBlocked, "Certificate known interception blocked";
+    NET_ERROR(SSL_OBSOLETE_VERSION_OR_CIPHER, -218) => SslObsoleteVersionOrCipher, "SSL obsolete version or cipher";
+    NET_ERROR(CERT_SELF_SIGNED_LOCAL_NETWORK, -219) => CertSelfSignedLocalNetwork, "Self-signed certificate on local network";
+    NET_ERROR(CERT_END, -220) => CertEnd, "Certificate error range end";
 
     // HTTP Errors
-    #[error("Invalid URL")]
-    InvalidUrl,
-    #[error("Disallowed URL scheme")]
-    DisallowedUrlScheme,
-    #[error("Unknown URL scheme")]
-    UnknownUrlScheme,
-    #[error("Invalid redirect")]
-    InvalidRedirect,
-    #[error("Too many redirects")]
-    TooManyRedirects,
-    #[error("Unsafe redirect")]
-    UnsafeRedirect,
-    #[error("Unsafe port")]
-    UnsafePort,
-    #[error("Invalid response")]
-    InvalidResponse,
-    #[error("Invalid chunked encoding")]
-    InvalidChunkedEncoding,
-    #[error("Method not supported")]
-    MethodNotSupported,
-    #[error("Unexpected proxy auth")]
-    UnexpectedProxyAuth,
-    #[error("Empty response")]
-    EmptyResponse,
-    #[error("Response headers too big")]
-    ResponseHeadersTooBig,
-    #[error("PAC script failed")]
-    PacScriptFailed,
-    #[error("Request range not satisfiable")]
-    RequestRangeNotSatisfiable,
-    #[error("Malformed identity")]
-    MalformedIdentity,
-    #[error("Content decoding failed")]
-    ContentDecodingFailed,
-    #[error("Network IO suspended")]
-    NetworkIoSuspended,
-    #[error("No supported proxies")]
-    NoSupportedProxies,
-    #[error("HTTP/2 protocol error")]
-    Http2ProtocolError,
-    #[error("Invalid auth credentials")]
-    InvalidAuthCredentials,
-    #[error("Unsupported auth scheme")]
-    UnsupportedAuthScheme,
-    #[error("Encoding detection failed")]
-    EncodingDetectionFailed,
-    #[error("Missing auth credentials")]
-    MissingAuthCredentials,
-    #[error("Unexpected security library status")]
-    UnexpectedSecurityLibraryStatus,
-    #[error("Misconfigured auth environment")]
-    MisconfiguredAuthEnvironment,
-    #[error("Undocumented security library status")]
-    UndocumentedSecurityLibraryStatus,
-    #[error("Response body too big to drain")]
-    ResponseBodyTooBigToDrain,
-    #[error("Response headers multiple Content-Length")]
-    ResponseHeadersMultipleContentLength,
-    #[error("Incomplete HTTP/2 headers")]
-    IncompleteHttp2Headers,
-    #[error("PAC not in DHCP")]
-    PacNotInDhcp,
-    #[error("Response headers multiple Content-Disposition")]
-    ResponseHeadersMultipleContentDisposition,
-    #[error("Response headers multiple Location")]
-    ResponseHeadersMultipleLocation,
-    #[error("HTTP/2 server refused stream")]
-    Http2ServerRefusedStream,
-    #[error("HTTP/2 PING failed")]
-    Http2PingFailed,
-    #[error("Content-Length mismatch")]
-    ContentLengthMismatch,
-    #[error("Incomplete chunked encoding")]
-    IncompleteChunkedEncoding,
-    #[error("QUIC protocol error")]
-    QuicProtocolError,
-    #[error("Response headers truncated")]
-    ResponseHeadersTruncated,
-    #[error("QUIC handshake failed")]
-    QuicHandshakeFailed,
-    #[error("HTTP/2 inadequate transport security")]
-    Http2InadequateTransportSecurity,
-    #[error("HTTP/2 flow control error")]
-    Http2FlowControlError,
-    #[error("HTTP/2 frame size error")]
-    Http2FrameSizeError,
-    #[error("HTTP/2 compression error")]
-    Http2CompressionError,
-    #[error("Proxy auth requested with no connection")]
-    ProxyAuthRequestedWithNoConnection,
-    #[error("HTTP/1.1 required")]
-    Http11Required,
-    #[error("Proxy HTTP/1.1 required")]
-    ProxyHttp11Required,
-    #[error("PAC script terminated")]
-    PacScriptTerminated,
-    #[error("Proxy required")]
-    ProxyRequired,
-    #[error("Invalid HTTP response")]
-    InvalidHttpResponse,
-    #[error("Content decoding init failed")]
-    ContentDecodingInitFailed,
-    #[error("HTTP/2 RST_STREAM NO_ERROR received")]
-    Http2RstStreamNoErrorReceived,
-    #[error("HTTP/2 pushed stream not available")]
-    Http2PushedStreamNotAvailable,
-    #[error("HTTP/2 claimed pushed stream reset by server")]
-    Http2ClaimedPushedStreamResetByServer,
-    #[error("Too many retries")]
-    TooManyRetries,
-    #[error("HTTP/2 stream closed")]
-    Http2StreamClosed,
-    #[error("HTTP/2 client refused stream")]
-    Http2ClientRefusedStream,
-    #[error("HTTP/2 pushed response does not match")]
-    Http2PushedResponseDoesNotMatch,
+    NET_ERROR(INVALID_URL, -300) => InvalidUrl, "Invalid URL";
+    NET_ERROR(DISALLOWED_URL_SCHEME, -301) => DisallowedUrlScheme, "Disallowed URL scheme";
+    NET_ERROR(UNKNOWN_URL_SCHEME, -302) => UnknownUrlScheme, "Unknown URL scheme";
+    NET_ERROR(INVALID_REDIRECT, -303) => InvalidRedirect, "Invalid redirect";
+    NET_ERROR(TOO_MANY_REDIRECTS, -310) => TooManyRedirects, "Too many redirects";
+    NET_ERROR(UNSAFE_REDIRECT, -311) => UnsafeRedirect, "Unsafe redirect";
+    NET_ERROR(UNSAFE_PORT, -312) => UnsafePort, "Unsafe port";
+    NET_ERROR(INVALID_RESPONSE, -320) => InvalidResponse, "Invalid response";
+    NET_ERROR(INVALID_CHUNKED_ENCODING, -321) => InvalidChunkedEncoding, "Invalid chunked encoding";
+    NET_ERROR(METHOD_NOT_SUPPORTED, -322) => MethodNotSupported, "Method not supported";
+    NET_ERROR(UNEXPECTED_PROXY_AUTH, -323) => UnexpectedProxyAuth, "Unexpected proxy auth";
+    NET_ERROR(EMPTY_RESPONSE, -324) => EmptyResponse, "Empty response";
+    NET_ERROR(RESPONSE_HEADERS_TOO_BIG, -325) => ResponseHeadersTooBig, "Response headers too big";
+    NET_ERROR(PAC_SCRIPT_FAILED, -327) => PacScriptFailed, "PAC script failed";
+    NET_ERROR(REQUEST_RANGE_NOT_SATISFIABLE, -328) => RequestRangeNotSatisfiable, "Request range not satisfiable";
+    NET_ERROR(MALFORMED_IDENTITY, -329) => MalformedIdentity, "Malformed identity";
+    NET_ERROR(CONTENT_DECODING_FAILED, -330) => ContentDecodingFailed, "Content decoding failed";
+    NET_ERROR(NETWORK_IO_SUSPENDED, -331) => NetworkIoSuspended, "Network IO suspended";
+    NET_ERROR(ENCODING_CONVERSION_FAILED, -333) => EncodingConversionFailed, "Encoding conversion failed";
+    NET_ERROR(NO_SUPPORTED_PROXIES, -336) => NoSupportedProxies, "No supported proxies";
+    NET_ERROR(HTTP2_PROTOCOL_ERROR, -337) => Http2ProtocolError, "HTTP/2 protocol error";
+    NET_ERROR(INVALID_AUTH_CREDENTIALS, -338) => InvalidAuthCredentials, "Invalid auth credentials";
+    NET_ERROR(UNSUPPORTED_AUTH_SCHEME, -339) => UnsupportedAuthScheme, "Unsupported auth scheme";
+    NET_ERROR(ENCODING_DETECTION_FAILED, -340) => EncodingDetectionFailed, "Encoding detection failed";
+    NET_ERROR(MISSING_AUTH_CREDENTIALS, -341) => MissingAuthCredentials, "Missing auth credentials";
+    NET_ERROR(UNEXPECTED_SECURITY_LIBRARY_STATUS, -342) => UnexpectedSecurityLibraryStatus, "Unexpected security library status";
+    NET_ERROR(MISCONFIGURED_AUTH_ENVIRONMENT, -343) => MisconfiguredAuthEnvironment, "Misconfigured auth environment";
+    NET_ERROR(UNDOCUMENTED_SECURITY_LIBRARY_STATUS, -344) => UndocumentedSecurityLibraryStatus, "Undocumented security library status";
+    NET_ERROR(RESPONSE_BODY_TOO_BIG_TO_DRAIN, -345) => ResponseBodyTooBigToDrain, "Response body too big to drain";
+    NET_ERROR(RESPONSE_HEADERS_MULTIPLE_CONTENT_LENGTH, -346) => ResponseHeadersMultipleContentLength, "Response headers multiple Content-Length";
+    NET_ERROR(INCOMPLETE_HTTP2_HEADERS, -347) => IncompleteHttp2Headers, "Incomplete HTTP/2 headers";
+    NET_ERROR(PAC_NOT_IN_DHCP, -348) => PacNotInDhcp, "PAC not in DHCP";
+    NET_ERROR(RESPONSE_HEADERS_MULTIPLE_CONTENT_DISPOSITION, -349) => ResponseHeadersMultipleContentDisposition, "Response headers multiple Content-Disposition";
+    NET_ERROR(RESPONSE_HEADERS_MULTIPLE_LOCATION, -350) => ResponseHeadersMultipleLocation, "Response headers multiple Location";
+    NET_ERROR(HTTP2_SERVER_REFUSED_STREAM, -351) => Http2ServerRefusedStream, "HTTP/2 server refused stream";
+    NET_ERROR(HTTP2_PING_FAILED, -352) => Http2PingFailed, "HTTP/2 PING failed";
+    NET_ERROR(CONTENT_LENGTH_MISMATCH, -354) => ContentLengthMismatch, "Content-Length mismatch";
+    NET_ERROR(INCOMPLETE_CHUNKED_ENCODING, -355) => IncompleteChunkedEncoding, "Incomplete chunked encoding";
+    NET_ERROR(QUIC_PROTOCOL_ERROR, -356) => QuicProtocolError, "QUIC protocol error";
+    NET_ERROR(RESPONSE_HEADERS_TRUNCATED, -357) => ResponseHeadersTruncated, "Response headers truncated";
+    NET_ERROR(QUIC_HANDSHAKE_FAILED, -358) => QuicHandshakeFailed, "QUIC handshake failed";
+    NET_ERROR(HTTP2_INADEQUATE_TRANSPORT_SECURITY, -360) => Http2InadequateTransportSecurity, "HTTP/2 inadequate transport security";
+    NET_ERROR(HTTP2_FLOW_CONTROL_ERROR, -361) => Http2FlowControlError, "HTTP/2 flow control error";
+    NET_ERROR(HTTP2_FRAME_SIZE_ERROR, -362) => Http2FrameSizeError, "HTTP/2 frame size error";
+    NET_ERROR(HTTP2_COMPRESSION_ERROR, -363) => Http2CompressionError, "HTTP/2 compression error";
+    NET_ERROR(PROXY_AUTH_REQUESTED_WITH_NO_CONNECTION, -364) => ProxyAuthRequestedWithNoConnection, "Proxy auth requested with no connection";
+    NET_ERROR(HTTP_1_1_REQUIRED, -365) => Http11Required, "HTTP/1.1 required";
+    NET_ERROR(PROXY_HTTP_1_1_REQUIRED, -366) => ProxyHttp11Required, "Proxy HTTP/1.1 required";
+    NET_ERROR(PAC_SCRIPT_TERMINATED, -367) => PacScriptTerminated, "PAC script terminated";
+    NET_ERROR(PROXY_REQUIRED, -368) => ProxyRequired, "Proxy required";
+    NET_ERROR(INVALID_HTTP_RESPONSE, -370) => InvalidHttpResponse, "Invalid HTTP response";
+    NET_ERROR(CONTENT_DECODING_INIT_FAILED, -371) => ContentDecodingInitFailed, "Content decoding init failed";
+    NET_ERROR(HTTP2_RST_STREAM_NO_ERROR_RECEIVED, -372) => Http2RstStreamNoErrorReceived, "HTTP/2 RST_STREAM NO_ERROR received";
+    NET_ERROR(HTTP2_PUSHED_STREAM_NOT_AVAILABLE, -373) => Http2PushedStreamNotAvailable, "HTTP/2 pushed stream not available";
+    NET_ERROR(HTTP2_CLAIMED_PUSHED_STREAM_RESET_BY_SERVER, -374) => Http2ClaimedPushedStreamResetByServer, "HTTP/2 claimed pushed stream reset by server";
+    NET_ERROR(TOO_MANY_RETRIES, -375) => TooManyRetries, "Too many retries";
+    NET_ERROR(HTTP2_STREAM_CLOSED, -376) => Http2StreamClosed, "HTTP/2 stream closed";
+    NET_ERROR(HTTP2_CLIENT_REFUSED_STREAM, -377) => Http2ClientRefusedStream, "HTTP/2 client refused stream";
+    NET_ERROR(HTTP2_PUSHED_RESPONSE_DOES_NOT_MATCH, -378) => Http2PushedResponseDoesNotMatch, "HTTP/2 pushed response does not match";
+    NET_ERROR(HTTP_RESPONSE_CODE_FAILURE, -379) => HttpResponseCodeFailure, "HTTP response code failure";
+    NET_ERROR(QUIC_CERT_ROOT_NOT_KNOWN, -380) => QuicCertRootNotKnown, "QUIC certificate root not known";
+    NET_ERROR(QUIC_GOAWAY_REQUEST_CAN_BE_RETRIED, -381) => QuicGoawayRequestCanBeRetried, "QUIC GOAWAY, request can be retried";
+    NET_ERROR(TOO_MANY_ACCEPT_CH_RESTARTS, -382) => TooManyAcceptChRestarts, "Too many Accept-CH restarts";
+    NET_ERROR(INCONSISTENT_IP_ADDRESS_SPACE, -383) => InconsistentIpAddressSpace, "Inconsistent IP address space";
+    NET_ERROR(CACHED_IP_ADDRESS_SPACE_BLOCKED_BY_PRIVATE_NETWORK_ACCESS_POLICY, -384) => CachedIpAddressSpaceBlockedByPrivateNetworkAccessPolicy, "Cached IP address space blocked by Private Network Access policy";
+    NET_ERROR(BLOCKED_BY_PRIVATE_NETWORK_ACCESS_CHECKS, -385) => BlockedByPrivateNetworkAccessChecks, "Blocked by Private Network Access checks";
+    NET_ERROR(ZSTD_WINDOW_SIZE_TOO_BIG, -386) => ZstdWindowSizeTooBig, "Zstd window size too big";
+    NET_ERROR(DICTIONARY_LOAD_FAILED, -387) => DictionaryLoadFailed, "Compression dictionary load failed";
+    NET_ERROR(UNEXPECTED_CONTENT_DICTIONARY_HEADER, -388) => UnexpectedContentDictionaryHeader, "Unexpected Content-Dictionary header";
 
-    #[error("Unknown error: {0}")]
-    Unknown(i32),
-}
+    // Cache Errors
+    NET_ERROR(CACHE_MISS, -400) => CacheMiss, "Cache miss";
+    NET_ERROR(CACHE_READ_FAILURE, -401) => CacheReadFailure, "Cache read failure";
+    NET_ERROR(CACHE_WRITE_FAILURE, -402) => CacheWriteFailure, "Cache write failure";
+    NET_ERROR(CACHE_OPERATION_NOT_SUPPORTED, -403) => CacheOperationNotSupported, "Cache operation not supported";
+    NET_ERROR(CACHE_OPEN_FAILURE, -404) => CacheOpenFailure, "Cache open failure";
+    NET_ERROR(CACHE_CREATE_FAILURE, -405) => CacheCreateFailure, "Cache create failure";
+    NET_ERROR(CACHE_RACE, -406) => CacheRace, "Cache race";
+    NET_ERROR(CACHE_CHECKSUM_READ_FAILURE, -407) => CacheChecksumReadFailure, "Cache checksum read failure";
+    NET_ERROR(CACHE_CHECKSUM_MISMATCH, -408) => CacheChecksumMismatch, "Cache checksum mismatch";
+    NET_ERROR(CACHE_LOCK_TIMEOUT, -409) => CacheLockTimeout, "Cache lock timeout";
+    NET_ERROR(CACHE_AUTH_FAILURE_AFTER_READ, -410) => CacheAuthFailureAfterRead, "Cache auth failure after read";
+    NET_ERROR(CACHE_ENTRY_NOT_SUITABLE, -411) => CacheEntryNotSuitable, "Cache entry not suitable";
+    NET_ERROR(CACHE_DOOM_FAILURE, -412) => CacheDoomFailure, "Cache doom failure";
+    NET_ERROR(CACHE_OPEN_OR_CREATE_FAILURE, -413) => CacheOpenOrCreateFailure, "Cache open or create failure";
 
-impl HttpError {
-    pub fn as_i32(&self) -> i32 {
-        match self {
-            HttpError::ConnectionClosed => -100,
-            HttpError::ConnectionReset => -101,
-            HttpError::ConnectionRefused => -102,
-            HttpError::ConnectionAborted => -103,
-            HttpError::ConnectionFailed => -104,
-            HttpError::NameNotResolved => -105,
-            HttpError::InternetDisconnected => -106,
-            HttpError::SslProtocolError => -107,
-            HttpError::AddressInvalid => -108,
-            HttpError::AddressUnreachable => -109,
-            HttpError::SslClientAuthCertNeeded => -110,
-            HttpError::TunnelConnectionFailed => -111,
-            HttpError::SslVersionOrCipherMismatch => -113,
-            HttpError::SslRenegotiationRequested => -114,
-            HttpError::ProxyAuthUnsupported => -115,
-            HttpError::BadSslClientAuthCert => -117,
-            HttpError::ConnectionTimedOut => -118,
-            HttpError::HostResolverQueueTooLarge => -119,
-            HttpError::SocksConnectionFailed => -120,
-            HttpError::SocksConnectionHostUnreachable => -121,
-            HttpError::AlpnNegotiationFailed => -122,
-            HttpError::SslNoRenegotiation => -123,
-            HttpError::WinsockUnexpectedWrittenBytes => -124,
-            HttpError::SslDecompressionFailureAlert => -125,
-            HttpError::SslBadRecordMacAlert => -126,
-            HttpError::ProxyAuthRequested => -127,
-            HttpError::ProxyConnectionFailed => -130,
-            HttpError::MandatoryProxyConfigurationFailed => -131,
-            HttpError::PreconnectMaxSocketLimit => -133,
-            HttpError::SslClientAuthPrivateKeyAccessDenied => -134,
-            HttpError::SslClientAuthCertNoPrivateKey => -135,
-            HttpError::ProxyCertificateInvalid => -136,
-            HttpError::NameResolutionFailed => -137,
-            HttpError::NetworkAccessDenied => -138,
-            HttpError::TemporarilyThrottled => -139,
-            HttpError::SslClientAuthSignatureFailed => -141,
-            HttpError::MsgTooBig => -142,
-            HttpError::WsProtocolError => -145,
-            HttpError::AddressInUse => -147,
-            HttpError::SslPinnedKeyNotInCertChain => -150,
-            HttpError::ClientAuthCertTypeUnsupported => -151,
-            HttpError::SslDecryptErrorAlert => -153,
-            HttpError::WsThrottleQueueTooLarge => -154,
-            HttpError::SslServerCertChanged => -156,
-            HttpError::SslUnrecognizedNameAlert => -159,
-            HttpError::SocketSetReceiveBufferSizeError => -160,
-            HttpError::SocketSetSendBufferSizeError => -161,
-            HttpError::SocketReceiveBufferSizeUnchangeable => -162,
-            HttpError::SocketSendBufferSizeUnchangeable => -163,
-            HttpError::SslClientAuthCertBadFormat => -164,
-            HttpError::IcannNameCollision => -166,
-            HttpError::SslServerCertBadFormat => -167,
-            HttpError::CtSthParsingFailed => -168,
-            HttpError::CtSthIncomplete => -169,
-            HttpError::UnableToReuseConnectionForProxyAuth => -170,
-            HttpError::CtConsistencyProofParsingFailed => -171,
-            HttpError::SslObsoleteCipher => -172,
-            HttpError::WsUpgrade => -173,
-            HttpError::ReadIfReadyNotImplemented => -174,
-            HttpError::NoBufferSpace => -176,
-            HttpError::SslClientAuthNoCommonAlgorithms => -177,
-            HttpError::EarlyDataRejected => -178,
-            HttpError::WrongVersionOnEarlyData => -179,
-            HttpError::Tls13DowngradeDetected => -180,
-            HttpError::SslKeyUsageIncompatible => -181,
-            HttpError::InvalidEchConfigList => -182,
-            HttpError::EchNotNegotiated => -183,
-            HttpError::EchFallbackCertificateInvalid => -184,
-            HttpError::ProxyUnableToConnectToDestination => -186,
-            HttpError::ProxyDelegateCanceledConnectRequest => -187,
-            HttpError::ProxyDelegateCanceledConnectResponse => -188,
+    // Miscellaneous Errors
+    NET_ERROR(INSECURE_RESPONSE, -501) => InsecureResponse, "Insecure response";
+    NET_ERROR(NO_PRIVATE_KEY_FOR_CERT, -502) => NoPrivateKeyForCert, "No private key for certificate";
+    NET_ERROR(ADD_USER_CERT_FAILED, -503) => AddUserCertFailed, "Adding user certificate failed";
+    NET_ERROR(INVALID_SIGNED_EXCHANGE, -504) => InvalidSignedExchange, "Invalid signed exchange";
+    NET_ERROR(INVALID_WEB_BUNDLE, -505) => InvalidWebBundle, "Invalid Web Bundle";
+    NET_ERROR(TRUST_TOKEN_OPERATION_FAILED, -506) => TrustTokenOperationFailed, "Trust Token operation failed";
+    NET_ERROR(TRUST_TOKEN_OPERATION_SUCCESS_WITHOUT_SENDING_REQUEST, -507) => TrustTokenOperationSuccessWithoutSendingRequest, "Trust Token operation succeeded without sending request";
 
-            HttpError::InvalidUrl => -300,
-            HttpError::DisallowedUrlScheme => -301,
-            HttpError::UnknownUrlScheme => -302,
-            HttpError::InvalidRedirect => -303,
-            HttpError::TooManyRedirects => -310,
-            HttpError::UnsafeRedirect => -311,
-            HttpError::UnsafePort => -312,
-            HttpError::InvalidResponse => -320,
-            HttpError::InvalidChunkedEncoding => -321,
-            HttpError::MethodNotSupported => -322,
-            HttpError::UnexpectedProxyAuth => -323,
-            HttpError::EmptyResponse => -324,
-            HttpError::ResponseHeadersTooBig => -325,
-            HttpError::PacScriptFailed => -327,
-            HttpError::RequestRangeNotSatisfiable => -328,
-            HttpError::MalformedIdentity => -329,
-            HttpError::ContentDecodingFailed => -330,
-            HttpError::NetworkIoSuspended => -331,
-            HttpError::NoSupportedProxies => -336,
-            HttpError::Http2ProtocolError => -337,
-            HttpError::InvalidAuthCredentials => -338,
-            HttpError::UnsupportedAuthScheme => -339,
-            HttpError::EncodingDetectionFailed => -340,
-            HttpError::MissingAuthCredentials => -341,
-            HttpError::UnexpectedSecurityLibraryStatus => -342,
-            HttpError::MisconfiguredAuthEnvironment => -343,
-            HttpError::UndocumentedSecurityLibraryStatus => -344,
-            HttpError::ResponseBodyTooBigToDrain => -345,
-            HttpError::ResponseHeadersMultipleContentLength => -346,
-            HttpError::IncompleteHttp2Headers => -347,
-            HttpError::PacNotInDhcp => -348,
-            HttpError::ResponseHeadersMultipleContentDisposition => -349,
-            HttpError::ResponseHeadersMultipleLocation => -350,
-            HttpError::Http2ServerRefusedStream => -351,
-            HttpError::Http2PingFailed => -352,
-            HttpError::ContentLengthMismatch => -354,
-            HttpError::IncompleteChunkedEncoding => -355,
-            HttpError::QuicProtocolError => -356,
-            HttpError::ResponseHeadersTruncated => -357,
-            HttpError::QuicHandshakeFailed => -358,
-            HttpError::Http2InadequateTransportSecurity => -360,
-            HttpError::Http2FlowControlError => -361,
-            HttpError::Http2FrameSizeError => -362,
-            HttpError::Http2CompressionError => -363,
-            HttpError::ProxyAuthRequestedWithNoConnection => -364,
-            HttpError::Http11Required => -365,
-            HttpError::ProxyHttp11Required => -366,
-            HttpError::PacScriptTerminated => -367,
-            HttpError::ProxyRequired => -368,
-            HttpError::InvalidHttpResponse => -370,
-            HttpError::ContentDecodingInitFailed => -371,
-            HttpError::Http2RstStreamNoErrorReceived => -372,
-            HttpError::Http2PushedStreamNotAvailable => -373,
-            HttpError::Http2ClaimedPushedStreamResetByServer => -374,
-            HttpError::TooManyRetries => -375,
-            HttpError::Http2StreamClosed => -376,
-            HttpError::Http2ClientRefusedStream => -377,
-            HttpError::Http2PushedResponseDoesNotMatch => -378,
-            HttpError::Unknown(code) => *code,
-        }
-    }
+    // Certificate Manager Errors
+    NET_ERROR(IMPORT_CA_CERT_NOT_CA, -703) => ImportCaCertNotCa, "Imported CA certificate is not a CA";
+    NET_ERROR(IMPORT_CERT_ALREADY_EXISTS, -704) => ImportCertAlreadyExists, "Imported certificate already exists";
+    NET_ERROR(IMPORT_CA_CERT_FAILED, -705) => ImportCaCertFailed, "CA certificate import failed";
+    NET_ERROR(IMPORT_SERVER_CERT_FAILED, -706) => ImportServerCertFailed, "Server certificate import failed";
+    NET_ERROR(KEY_GENERATION_FAILED, -710) => KeyGenerationFailed, "Key generation failed";
+    NET_ERROR(PRIVATE_KEY_EXPORT_FAILED, -712) => PrivateKeyExportFailed, "Private key export failed";
+    NET_ERROR(SELF_SIGNED_CERT_GENERATION_FAILED, -713) => SelfSignedCertGenerationFailed, "Self-signed certificate generation failed";
+    NET_ERROR(CERT_DATABASE_CHANGED, -714) => CertDatabaseChanged, "Certificate database changed";
+    NET_ERROR(CERT_VERIFIER_CHANGED, -716) => CertVerifierChanged, "Certificate verifier changed";
+
+    // DNS Errors
+    NET_ERROR(DNS_MALFORMED_RESPONSE, -800) => DnsMalformedResponse, "DNS malformed response";
+    NET_ERROR(DNS_SERVER_REQUIRES_TCP, -801) => DnsServerRequiresTcp, "DNS server requires TCP";
+    NET_ERROR(DNS_SERVER_FAILED, -802) => DnsServerFailed, "DNS server failed";
+    NET_ERROR(DNS_TIMED_OUT, -803) => DnsTimedOut, "DNS timed out";
+    NET_ERROR(DNS_CACHE_MISS, -804) => DnsCacheMiss, "DNS cache miss";
+    NET_ERROR(DNS_SEARCH_EMPTY, -805) => DnsSearchEmpty, "DNS search list empty";
+    NET_ERROR(DNS_SORT_ERROR, -806) => DnsSortError, "DNS sort error";
+    NET_ERROR(DNS_SECURE_RESOLVER_HOSTNAME_RESOLUTION_FAILED, -808) => DnsSecureResolverHostnameResolutionFailed, "Secure DNS resolver hostname resolution failed";
+    NET_ERROR(DNS_NAME_HTTPS_ONLY, -809) => DnsNameHttpsOnly, "DNS name is HTTPS-only";
+    NET_ERROR(DNS_REQUEST_CANCELLED, -810) => DnsRequestCancelled, "DNS request cancelled";
+    NET_ERROR(DNS_NO_MATCHING_SUPPORTED_ALPN, -811) => DnsNoMatchingSupportedAlpn, "DNS no matching supported ALPN";
+    NET_ERROR(DNS_SECURE_PROBE_RECORD_INVALID, -814) => DnsSecureProbeRecordInvalid, "Secure DNS probe record invalid";
 }
 
 impl From<i32> for HttpError {
     fn from(code: i32) -> Self {
-        match code {
-             -100 => HttpError::ConnectionClosed,
-             -101 => HttpError::ConnectionReset,
-             -102 => HttpError::ConnectionRefused,
-             -103 => HttpError::ConnectionAborted,
-             -104 => HttpError::ConnectionFailed,
-             -105 => HttpError::NameNotResolved,
-             -106 => HttpError::InternetDisconnected,
-             -107 => HttpError::SslProtocolError,
-             -108 => HttpError::AddressInvalid,
-             -109 => HttpError::AddressUnreachable,
-             -110 => HttpError::SslClientAuthCertNeeded,
-             -111 => HttpError::TunnelConnectionFailed,
-             -113 => HttpError::SslVersionOrCipherMismatch,
-             -114 => HttpError::SslRenegotiationRequested,
-             -115 => HttpError::ProxyAuthUnsupported,
-             -117 => HttpError::BadSslClientAuthCert,
-             -118 => HttpError::ConnectionTimedOut,
-             -119 => HttpError::HostResolverQueueTooLarge,
-             -120 => HttpError::SocksConnectionFailed,
-             -121 => HttpError::SocksConnectionHostUnreachable,
-             -122 => HttpError::AlpnNegotiationFailed,
-             -123 => HttpError::SslNoRenegotiation,
-             -124 => HttpError::WinsockUnexpectedWrittenBytes,
-             -125 => HttpError::SslDecompressionFailureAlert,
-             -126 => HttpError::SslBadRecordMacAlert,
-             -127 => HttpError::ProxyAuthRequested,
-             -130 => HttpError::ProxyConnectionFailed,
-             -131 => HttpError::MandatoryProxyConfigurationFailed,
-             -133 => HttpError::PreconnectMaxSocketLimit,
-             -134 => HttpError::SslClientAuthPrivateKeyAccessDenied,
-             -135 => HttpError::SslClientAuthCertNoPrivateKey,
-             -136 => HttpError::ProxyCertificateInvalid,
-             -137 => HttpError::NameResolutionFailed,
-             -138 => HttpError::NetworkAccessDenied,
-             -139 => HttpError::TemporarilyThrottled,
-             -141 => HttpError::SslClientAuthSignatureFailed,
-             -142 => HttpError::MsgTooBig,
-             -145 => HttpError::WsProtocolError,
-             -147 => HttpError::AddressInUse,
-             -150 => HttpError::SslPinnedKeyNotInCertChain,
-             -151 => HttpError::ClientAuthCertTypeUnsupported,
-             -153 => HttpError::SslDecryptErrorAlert,
-             -154 => HttpError::WsThrottleQueueTooLarge,
-             -156 => HttpError::SslServerCertChanged,
-             -159 => HttpError::SslUnrecognizedNameAlert,
-             -160 => HttpError::SocketSetReceiveBufferSizeError,
-             -161 => HttpError::SocketSetSendBufferSizeError,
-             -162 => HttpError::SocketReceiveBufferSizeUnchangeable,
-             -163 => HttpError::SocketSendBufferSizeUnchangeable,
-             -164 => HttpError::SslClientAuthCertBadFormat,
-             -166 => HttpError::IcannNameCollision,
-             -167 => HttpError::SslServerCertBadFormat,
-             -168 => HttpError::CtSthParsingFailed,
-             -169 => HttpError::CtSthIncomplete,
-             -170 => HttpError::UnableToReuseConnectionForProxyAuth,
-             -171 => HttpError::CtConsistencyProofParsingFailed,
-             -172 => HttpError::SslObsoleteCipher,
-             -173 => HttpError::WsUpgrade,
-             -174 => HttpError::ReadIfReadyNotImplemented,
-             -176 => HttpError::NoBufferSpace,
-             -177 => HttpError::SslClientAuthNoCommonAlgorithms,
-             -178 => HttpError::EarlyDataRejected,
-             -179 => HttpError::WrongVersionOnEarlyData,
-             -180 => HttpError::Tls13DowngradeDetected,
-             -181 => HttpError::SslKeyUsageIncompatible,
-             -182 => HttpError::InvalidEchConfigList,
-             -183 => HttpError::EchNotNegotiated,
-             -184 => HttpError::EchFallbackCertificateInvalid,
-             -186 => HttpError::ProxyUnableToConnectToDestination,
-             -187 => HttpError::ProxyDelegateCanceledConnectRequest,
-             -188 => HttpError::ProxyDelegateCanceledConnectResponse,
+        HttpError::from_i32(code)
+    }
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("Invalid net error \"{0}\", expected an ERR_* name or a negative code")]
+pub struct ParseHttpErrorError(String);
+
+impl FromStr for HttpError {
+    type Err = ParseHttpErrorError;
 
-             -300 => HttpError::InvalidUrl,
-             -301 => HttpError::DisallowedUrlScheme,
-             -302 => HttpError::UnknownUrlScheme,
-             -303 => HttpError::InvalidRedirect,
-             -310 => HttpError::TooManyRedirects,
-             -311 => HttpError::UnsafeRedirect,
-             -312 => HttpError::UnsafePort,
-             -320 => HttpError::InvalidResponse,
-             -321 => HttpError::InvalidChunkedEncoding,
-             -322 => HttpError::MethodNotSupported,
-             -323 => HttpError::UnexpectedProxyAuth,
-             -324 => HttpError::EmptyResponse,
-             -325 => HttpError::ResponseHeadersTooBig,
-             -327 => HttpError::PacScriptFailed,
-             -328 => HttpError::RequestRangeNotSatisfiable,
-             -329 => HttpError::MalformedIdentity,
-             -330 => HttpError::ContentDecodingFailed,
-             -331 => HttpError::NetworkIoSuspended,
-             -336 => HttpError::NoSupportedProxies,
-             -337 => HttpError::Http2ProtocolError,
-             -338 => HttpError::InvalidAuthCredentials,
-             -339 => HttpError::UnsupportedAuthScheme,
-             -340 => HttpError::EncodingDetectionFailed,
-             -341 => HttpError::MissingAuthCredentials,
-             -342 => HttpError::UnexpectedSecurityLibraryStatus,
-             -343 => HttpError::MisconfiguredAuthEnvironment,
-             -344 => HttpError::UndocumentedSecurityLibraryStatus,
-             -345 => HttpError::ResponseBodyTooBigToDrain,
-             -346 => HttpError::ResponseHeadersMultipleContentLength,
-             -347 => HttpError::IncompleteHttp2Headers,
-             -348 => HttpError::PacNotInDhcp,
-             -349 => HttpError::ResponseHeadersMultipleContentDisposition,
-             -350 => HttpError::ResponseHeadersMultipleLocation,
-             -351 => HttpError::Http2ServerRefusedStream,
-             -352 => HttpError::Http2PingFailed,
-             -354 => HttpError::ContentLengthMismatch,
-             -355 => HttpError::IncompleteChunkedEncoding,
-             -356 => HttpError::QuicProtocolError,
-             -357 => HttpError::ResponseHeadersTruncated,
-             -358 => HttpError::QuicHandshakeFailed,
-             -360 => HttpError::Http2InadequateTransportSecurity,
-             -361 => HttpError::Http2FlowControlError,
-             -362 => HttpError::Http2FrameSizeError,
-             -363 => HttpError::Http2CompressionError,
-             -364 => HttpError::ProxyAuthRequestedWithNoConnection,
-             -365 => HttpError::Http11Required,
-             -366 => HttpError::ProxyHttp11Required,
-             -367 => HttpError::PacScriptTerminated,
-             -368 => HttpError::ProxyRequired,
-             -370 => HttpError::InvalidHttpResponse,
-             -371 => HttpError::ContentDecodingInitFailed,
-             -372 => HttpError::Http2RstStreamNoErrorReceived,
-             -373 => HttpError::Http2PushedStreamNotAvailable,
-             -374 => HttpError::Http2ClaimedPushedStreamResetByServer,
-             -375 => HttpError::TooManyRetries,
-             -376 => HttpError::Http2StreamClosed,
-             -377 => HttpError::Http2ClientRefusedStream,
-             -378 => HttpError::Http2PushedResponseDoesNotMatch,
-             _ => HttpError::Unknown(code),
+    /// Accepts a symbolic name (`ERR_CONNECTION_RESET`, also with the
+    /// `net::` prefix Chromium's `ErrorToString` adds) or a negative code
+    /// (`-101`). Unlisted negative codes parse to `Unknown`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let err = || ParseHttpErrorError(s.to_string());
+        if let Ok(code) = s.parse::<i32>() {
+            return if code < 0 { Ok(HttpError::from_i32(code)) } else { Err(err()) };
         }
+        let name = s.strip_prefix("net::").unwrap_or(s);
+        HttpError::ALL.iter().copied().find(|e| e.name() == Some(name)).ok_or_else(err)
     }
 }
+
+// Compile-time round trip over every code: each variant maps to a negative
+// code that maps back to the same variant.
+const _: () = {
+    let mut i = 0;
+    while i < HttpError::ALL.len() {
+        let code = HttpError::ALL[i].as_i32();
+        assert!(code < 0, "net error codes are negative");
+        assert!(
+            matches!(HttpError::from_i32(code).index(), Some(j) if j == i),
+            "code does not round-trip"
+        );
+        i += 1;
+    }
+};
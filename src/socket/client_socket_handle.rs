@@ -0,0 +1,333 @@
+use std::fmt;
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::error_map::map_system_error;
+use crate::rust_errors::HttpError;
+use crate::socket::group_id::GroupId;
+use crate::socket::load_timing::ConnectTiming;
+use crate::socket::pool::{ClientSocketPool, ConnectJobFactory, PooledSocket};
+use crate::socket::proxy_chain::ProxyChain;
+
+// `ClientSocketHandle` (net/socket/client_socket_handle.h): the lease on a
+// pooled socket. Dropping the handle is `ReleaseSocket`: the socket goes back
+// to the pool if the last response left it clean, and is closed otherwise.
+//
+// The HTTP layer tells the handle what it saw: `Connection: close`
+// (`set_keep_alive(false)`) and how much of the response body is still on the
+// wire (`set_body_remaining`). Until it does, the socket is dirty: a request
+// cancelled or timed out after it was written may still have its response in
+// flight, which the next request on the socket would read. A body that was not
+// read to the end can be drained to keep the socket, as
+// `HttpNetworkTransaction` does before an auth restart, but only up to a
+// limit; past it the socket is not worth the bytes.
+
+/// Default limit for `ClientSocketHandle::drain_body`. A local choice, not a
+/// Chromium constant: sixteen drain reads, past which a new connection is
+/// cheaper than reading the rest of a body nobody wants.
+pub const MAX_DRAIN_BODY_BYTES: u64 = 16 * 1024;
+// `HttpNetworkTransaction::kDrainBodyBufferSize`.
+const DRAIN_BODY_BUFFER_SIZE: usize = 1024;
+
+/// `StreamSocketHandle::SocketReuseType`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SocketReuseType {
+    /// A new connection made for this request.
+    Unused,
+    /// A preconnected socket that had not carried a request yet.
+    UnusedIdle,
+    /// A socket that carried an earlier request.
+    ReusedIdle,
+}
+
+impl SocketReuseType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SocketReuseType::Unused => "unused",
+            SocketReuseType::UnusedIdle => "unused_idle",
+            SocketReuseType::ReusedIdle => "reused_idle",
+        }
+    }
+}
+
+pub struct ClientSocketHandle<F: ConnectJobFactory> {
+    pool: ClientSocketPool<F>,
+    // Only taken when the handle is dropped.
+    socket: Option<PooledSocket<F::Socket>>,
+    reuse_type: SocketReuseType,
+    idle_time: Duration,
+    connect_timing: ConnectTiming,
+    keep_alive: bool,
+    // `None` until the HTTP layer reports the response, and for a body that
+    // ends when the server closes the connection.
+    body_remaining: Option<u64>,
+    disconnected: bool,
+}
+
+impl<F: ConnectJobFactory> ClientSocketHandle<F> {
+    pub(crate) fn new(
+        pool: ClientSocketPool<F>,
+        socket: PooledSocket<F::Socket>,
+        reuse_type: SocketReuseType,
+        idle_time: Duration,
+        connect_timing: ConnectTiming,
+    ) -> Self {
+        Self {
+            pool,
+            socket: Some(socket),
+            reuse_type,
+            idle_time,
+            connect_timing,
+            keep_alive: true,
+            body_remaining: None,
+            disconnected: false,
+        }
+    }
+
+    pub fn socket(&self) -> &F::Socket {
+        self.pooled().socket()
+    }
+
+    pub fn socket_mut(&mut self) -> &mut F::Socket {
+        self.socket.as_mut().expect("socket is held until drop").socket_mut()
+    }
+
+    pub fn group_id(&self) -> &GroupId {
+        self.pooled().group_id()
+    }
+
+    pub fn proxy_chain(&self) -> &ProxyChain {
+        self.pooled().proxy_chain()
+    }
+
+    pub fn reuse_type(&self) -> SocketReuseType {
+        self.reuse_type
+    }
+
+    /// The socket carried an earlier request. A failure on it before any
+    /// response byte is worth retrying on a new connection.
+    pub fn is_reused(&self) -> bool {
+        self.reuse_type == SocketReuseType::ReusedIdle
+    }
+
+    /// How long the socket sat idle in the pool; zero for a new connection.
+    pub fn idle_time(&self) -> Duration {
+        self.idle_time
+    }
+
+    /// Empty unless the socket was connected for this request.
+    pub fn connect_timing(&self) -> &ConnectTiming {
+        &self.connect_timing
+    }
+
+    /// `false` once the response carried `Connection: close`, or was
+    /// HTTP/1.0 without keep-alive.
+    pub fn set_keep_alive(&mut self, keep_alive: bool) {
+        self.keep_alive = keep_alive;
+    }
+
+    /// Response body bytes still unread on the socket: `Some(0)` once the
+    /// body is complete, `None` if it is delimited by the connection closing.
+    /// A new handle starts at `None`.
+    pub fn set_body_remaining(&mut self, remaining: Option<u64>) {
+        self.body_remaining = remaining;
+    }
+
+    pub fn body_remaining(&self) -> Option<u64> {
+        self.body_remaining
+    }
+
+    /// `Disconnect`: close the socket instead of returning it, for example
+    /// after an error in the middle of a response.
+    pub fn disconnect(&mut self) {
+        self.disconnected = true;
+    }
+
+    /// Whether dropping the handle now returns the socket to the pool: only
+    /// once the HTTP layer has reported the exchange complete. The pool still
+    /// probes it and closes it if the server hung up.
+    pub fn is_reusable(&self) -> bool {
+        self.keep_alive && !self.disconnected && self.body_remaining == Some(0)
+    }
+
+    fn pooled(&self) -> &PooledSocket<F::Socket> {
+        self.socket.as_ref().expect("socket is held until drop")
+    }
+}
+
+impl<F: ConnectJobFactory> ClientSocketHandle<F>
+where
+    F::Socket: AsyncRead + Unpin,
+{
+    /// Reads and discards the rest of a known-length response body so the
+    /// socket can be reused. More than `max_bytes` left, or a body that runs
+    /// to the connection's close or was never reported, is
+    /// `ResponseBodyTooBigToDrain` and the
+    /// socket will be closed. Chunked bodies are drained by the parser, which
+    /// then reports `Some(0)`.
+    pub async fn drain_body(&mut self, max_bytes: u64) -> Result<(), HttpError> {
+        match self.body_remaining {
+            Some(remaining) if remaining <= max_bytes => {}
+            _ => return Err(HttpError::ResponseBodyTooBigToDrain),
+        }
+        let mut buf = [0u8; DRAIN_BODY_BUFFER_SIZE];
+        while let Some(remaining) = self.body_remaining.filter(|&n| n > 0) {
+            let len = buf.len().min(usize::try_from(remaining).unwrap_or(usize::MAX));
+            match self.socket_mut().read(&mut buf[..len]).await {
+                Ok(0) => {
+                    self.disconnect();
+                    return Err(HttpError::ConnectionClosed);
+                }
+                Ok(n) => self.body_remaining = Some(remaining - n as u64),
+                Err(error) => {
+                    self.disconnect();
+                    return Err(map_system_error(&error));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<F: ConnectJobFactory> fmt::Debug for ClientSocketHandle<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClientSocketHandle")
+            .field("group_id", self.group_id())
+            .field("proxy_chain", self.proxy_chain())
+            .field("reuse_type", &self.reuse_type)
+            .field("idle_time", &self.idle_time)
+            .field("keep_alive", &self.keep_alive)
+            .field("body_remaining", &self.body_remaining)
+            .field("disconnected", &self.disconnected)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<F: ConnectJobFactory> Drop for ClientSocketHandle<F> {
+    fn drop(&mut self) {
+        if let Some(socket) = self.socket.take() {
+            let reusable = self.is_reusable();
+            self.pool.release_socket(socket, reusable);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    use tokio::io::{AsyncWriteExt, DuplexStream, ReadBuf};
+    use tokio::sync::mpsc;
+
+    use super::*;
+    use crate::net_error::NetError;
+    use crate::socket::pool::{PoolType, RequestPriority};
+    use crate::socket::stream_socket::StreamSocket;
+
+    // The client end of an in-memory connection.
+    struct Pipe(DuplexStream);
+
+    impl StreamSocket for Pipe {
+        fn is_connected(&self) -> bool {
+            true
+        }
+
+        fn is_connected_and_idle(&self) -> bool {
+            true
+        }
+    }
+
+    impl AsyncRead for Pipe {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<std::io::Result<()>> {
+            Pin::new(&mut self.0).poll_read(cx, buf)
+        }
+    }
+
+    // Hands the server end of each new connection to the test.
+    struct PipeFactory(mpsc::UnboundedSender<DuplexStream>);
+
+    impl ConnectJobFactory for PipeFactory {
+        type Socket = Pipe;
+
+        async fn connect(
+            &self,
+            _group_id: &GroupId,
+            _proxy_chain: &ProxyChain,
+        ) -> Result<(Pipe, ConnectTiming), NetError> {
+            let (client, server) = tokio::io::duplex(64 * 1024);
+            self.0.send(server).expect("test holds the receiver");
+            Ok((Pipe(client), ConnectTiming::default()))
+        }
+    }
+
+    fn pool() -> (ClientSocketPool<PipeFactory>, mpsc::UnboundedReceiver<DuplexStream>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        (ClientSocketPool::new(PoolType::Normal, PipeFactory(tx)), rx)
+    }
+
+    async fn request(pool: &ClientSocketPool<PipeFactory>) -> ClientSocketHandle<PipeFactory> {
+        let group_id = GroupId::new("http", "example.com", 80);
+        pool.request_socket(group_id, ProxyChain::direct(), RequestPriority::default())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn drained_body_returns_socket_to_pool() {
+        let (pool, mut servers) = pool();
+        let mut handle = request(&pool).await;
+        let mut server = servers.recv().await.unwrap();
+        server.write_all(&[b'x'; MAX_DRAIN_BODY_BYTES as usize]).await.unwrap();
+
+        handle.set_body_remaining(Some(MAX_DRAIN_BODY_BYTES));
+        assert!(!handle.is_reusable());
+        handle.drain_body(MAX_DRAIN_BODY_BYTES).await.unwrap();
+        assert_eq!(handle.body_remaining(), Some(0));
+        assert!(handle.is_reusable());
+        drop(handle);
+        assert_eq!(pool.idle_socket_count(), 1);
+
+        let handle = request(&pool).await;
+        assert_eq!(handle.reuse_type(), SocketReuseType::ReusedIdle);
+    }
+
+    #[tokio::test]
+    async fn body_over_the_limit_closes_the_socket() {
+        let (pool, mut servers) = pool();
+        for remaining in [Some(MAX_DRAIN_BODY_BYTES + 1), None] {
+            let mut handle = request(&pool).await;
+            let _server = servers.recv().await.unwrap();
+            handle.set_body_remaining(remaining);
+            let result = handle.drain_body(MAX_DRAIN_BODY_BYTES).await;
+            assert_eq!(result, Err(HttpError::ResponseBodyTooBigToDrain), "{remaining:?}");
+            assert!(!handle.is_reusable());
+            drop(handle);
+            assert_eq!(pool.idle_socket_count(), 0);
+        }
+
+        let handle = request(&pool).await;
+        assert_eq!(handle.reuse_type(), SocketReuseType::Unused);
+    }
+
+    #[tokio::test]
+    async fn server_closing_mid_body_fails_the_drain() {
+        let (pool, mut servers) = pool();
+        let mut handle = request(&pool).await;
+        let mut server = servers.recv().await.unwrap();
+        server.write_all(b"short").await.unwrap();
+        drop(server);
+
+        handle.set_body_remaining(Some(10));
+        assert_eq!(handle.drain_body(MAX_DRAIN_BODY_BYTES).await, Err(HttpError::ConnectionClosed));
+        assert_eq!(handle.body_remaining(), Some(5));
+        assert!(!handle.is_reusable());
+        drop(handle);
+        assert_eq!(pool.idle_socket_count(), 0);
+    }
+}
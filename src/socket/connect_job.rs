@@ -0,0 +1,623 @@
+use std::future::Future;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use url::Url;
+
+use crate::error_map::map_connect_error;
+use crate::error_stats::ErrorStats;
+use crate::fault_injection::{ConnectionFaults, FaultInjector};
+use crate::net_error::{NetError, Phase};
+use crate::rust_errors::HttpError;
+use crate::socket::group_id::{GroupId, SslSessionKey};
+use crate::socket::load_timing::ConnectTiming;
+use crate::socket::pool::ConnectJobFactory;
+use crate::socket::proxy_chain::{ProxyChain, ProxyScheme, ProxyServer};
+use crate::socket::stream_socket::BoxedStream;
+
+// Connect jobs (net/socket/connect_job.h and its subclasses) as one layered
+// pipeline, each layer running over the stream of the one below:
+//
+//   transport    DNS, then TCP to the first hop (`TransportConnectJob`)
+//   proxies      per proxy in the chain: TLS to an HTTPS proxy, then a SOCKS
+//                handshake (`SOCKSConnectJob`) or a CONNECT tunnel
+//                (`HttpProxyConnectJob`) to the next hop
+//   ssl          TLS to the destination for https and wss (`SSLConnectJob`)
+//
+// Plain http through an HTTP proxy is not tunnelled; requests are sent to the
+// proxy in absolute form, as in Chromium.
+//
+// Every phase has its own timeout and fails with a `NetError` naming the
+// phase: `Dns` (NameNotResolved), `Connect` (the mapped socket error, or
+// ConnectionTimedOut), `ProxyTls` and `Tls` (the connector's mapped error,
+// usually SslProtocolError, for an HTTPS proxy and the destination), `Socks`
+// (SocksConnectionFailed) and `Tunnel` (TunnelConnectionFailed). A timeout in
+// any phase after DNS is ConnectionTimedOut.
+
+/// `TransportConnectJob`'s connection timeout.
+pub const TRANSPORT_CONNECT_TIMEOUT: Duration = Duration::from_secs(240);
+/// `kSOCKSConnectJobTimeoutInSeconds`.
+pub const SOCKS_CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+/// `HttpProxyConnectJob`'s tunnel timeout.
+pub const TUNNEL_CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+/// `kSSLHandshakeTimeoutInSeconds`.
+pub const SSL_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(30);
+/// A local choice with no Chromium counterpart: there, host resolution counts
+/// against `TRANSPORT_CONNECT_TIMEOUT`. Separate here so a stuck resolver
+/// fails as `Dns` well before the connect timeout.
+pub const DNS_RESOLVE_TIMEOUT: Duration = Duration::from_secs(30);
+/// `HttpStreamParser::kMaxHeaderBufSize`, for the proxy's CONNECT response.
+const MAX_TUNNEL_RESPONSE_HEADERS: usize = 256 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectTimeouts {
+    pub dns: Duration,
+    pub transport: Duration,
+    pub socks: Duration,
+    pub tunnel: Duration,
+    pub ssl: Duration,
+}
+
+impl Default for ConnectTimeouts {
+    fn default() -> Self {
+        Self {
+            dns: DNS_RESOLVE_TIMEOUT,
+            transport: TRANSPORT_CONNECT_TIMEOUT,
+            socks: SOCKS_CONNECT_TIMEOUT,
+            tunnel: TUNNEL_CONNECT_TIMEOUT,
+            ssl: SSL_HANDSHAKE_TIMEOUT,
+        }
+    }
+}
+
+/// The TLS layer: configures the handshake (chromium_tls_research/) and
+/// resumes sessions only under `session_key`. Handshake failures map through
+/// `map_openssl_error`; the pipeline adds the phase.
+pub trait TlsConnector: Send + Sync + 'static {
+    fn connect(
+        &self,
+        server_name: &str,
+        session_key: &SslSessionKey,
+        stream: BoxedStream,
+    ) -> impl Future<Output = Result<BoxedStream, NetError>> + Send;
+}
+
+/// The pool's `ConnectJobFactory`: runs the layers a group and proxy chain
+/// need.
+pub struct LayeredConnectJobFactory<T> {
+    tls: T,
+    timeouts: ConnectTimeouts,
+    fault_injector: Option<Arc<FaultInjector>>,
+}
+
+impl<T: TlsConnector> LayeredConnectJobFactory<T> {
+    pub fn new(tls: T) -> Self {
+        Self { tls, timeouts: ConnectTimeouts::default(), fault_injector: None }
+    }
+
+    pub fn with_timeouts(mut self, timeouts: ConnectTimeouts) -> Self {
+        self.timeouts = timeouts;
+        self
+    }
+
+    /// Checks each phase of every connection against `injector`, and wraps
+    /// the connected stream for its send and read faults.
+    pub fn with_fault_injector(mut self, injector: Arc<FaultInjector>) -> Self {
+        self.fault_injector = Some(injector);
+        self
+    }
+
+    pub fn timeouts(&self) -> ConnectTimeouts {
+        self.timeouts
+    }
+}
+
+impl<T: TlsConnector> ConnectJobFactory for LayeredConnectJobFactory<T> {
+    type Socket = BoxedStream;
+
+    async fn connect(
+        &self,
+        group_id: &GroupId,
+        proxy_chain: &ProxyChain,
+    ) -> Result<(BoxedStream, ConnectTiming), NetError> {
+        let faults = match &self.fault_injector {
+            Some(injector) => injector.connection(&origin(group_id)),
+            None => ConnectionFaults::none(),
+        };
+        let mut job = ConnectJob {
+            factory: self,
+            group_id,
+            proxy_chain,
+            faults,
+            timing: ConnectTiming::default(),
+        };
+        let stream = job.run().await?;
+        Ok((stream, job.timing))
+    }
+}
+
+// One connection attempt.
+struct ConnectJob<'a, T> {
+    factory: &'a LayeredConnectJobFactory<T>,
+    group_id: &'a GroupId,
+    proxy_chain: &'a ProxyChain,
+    faults: ConnectionFaults,
+    timing: ConnectTiming,
+}
+
+impl<T: TlsConnector> ConnectJob<'_, T> {
+    async fn run(&mut self) -> Result<BoxedStream, NetError> {
+        let timeouts = self.factory.timeouts;
+        let (host, port) = match self.proxy_chain.servers().first() {
+            Some(proxy) => (proxy.host.as_str(), proxy.port),
+            None => (self.group_id.host(), self.group_id.port()),
+        };
+
+        self.timing.domain_lookup_start = Some(Instant::now());
+        let addrs = self
+            .phase(Phase::Dns, timeouts.dns, HttpError::NameNotResolved, resolve(host, port))
+            .await?;
+        self.timing.domain_lookup_end = Some(Instant::now());
+
+        self.timing.connect_start = Some(Instant::now());
+        let tcp = self
+            .phase(Phase::Connect, timeouts.transport, HttpError::ConnectionTimedOut, async {
+                connect_tcp(&addrs).await
+            })
+            .await?;
+        let mut stream: BoxedStream = Box::new(tcp);
+
+        let servers = self.proxy_chain.servers();
+        for (i, proxy) in servers.iter().enumerate() {
+            if proxy.scheme == ProxyScheme::Https {
+                let proxy_id = GroupId::new("https", &proxy.host, proxy.port)
+                    .with_privacy_mode(self.group_id.privacy_mode())
+                    .with_network_anonymization_key(
+                        self.group_id.network_anonymization_key().clone(),
+                    );
+                let session_key = proxy_id.ssl_session_key();
+                stream = self.ssl(Phase::ProxyTls, &proxy.host, &session_key, stream).await?;
+            }
+            let (next_host, next_port) = match servers.get(i + 1) {
+                Some(next) => (next.host.as_str(), next.port),
+                None => (self.group_id.host(), self.group_id.port()),
+            };
+            let is_last = i + 1 == servers.len();
+            stream = self.through_proxy(proxy, is_last, next_host, next_port, stream).await?;
+        }
+
+        if is_secure(self.group_id.scheme()) {
+            self.timing.ssl_start = Some(Instant::now());
+            let session_key = self.group_id.ssl_session_key();
+            stream = self.ssl(Phase::Tls, self.group_id.host(), &session_key, stream).await?;
+            self.timing.ssl_end = Some(Instant::now());
+        }
+        self.timing.connect_end = Some(Instant::now());
+
+        match self.factory.fault_injector {
+            Some(_) => Ok(Box::new(self.faults.wrap(stream))),
+            None => Ok(stream),
+        }
+    }
+
+    // The SOCKS handshake or CONNECT tunnel through `proxy` to the next hop,
+    // which is the destination when `is_last`.
+    async fn through_proxy(
+        &self,
+        proxy: &ProxyServer,
+        is_last: bool,
+        host: &str,
+        port: u16,
+        mut stream: BoxedStream,
+    ) -> Result<BoxedStream, NetError> {
+        let timeouts = self.factory.timeouts;
+        let timed_out = HttpError::ConnectionTimedOut;
+        match proxy.scheme {
+            ProxyScheme::Socks4 => {
+                // SOCKS4 carries an IPv4 address, so the client resolves.
+                let addrs = self
+                    .phase(
+                        Phase::Dns,
+                        timeouts.dns,
+                        HttpError::NameNotResolved,
+                        resolve(host, port),
+                    )
+                    .await?;
+                let Some(addr) = addrs.iter().find(|a| a.is_ipv4()).copied() else {
+                    return Err(NetError::new(HttpError::NameNotResolved).with_phase(Phase::Dns));
+                };
+                self.phase(Phase::Socks, timeouts.socks, timed_out, socks4(&mut stream, addr))
+                    .await?;
+            }
+            ProxyScheme::Socks5 => {
+                self.phase(
+                    Phase::Socks,
+                    timeouts.socks,
+                    timed_out,
+                    socks5(&mut stream, host, port),
+                )
+                .await?;
+            }
+            ProxyScheme::Http | ProxyScheme::Https => {
+                if !is_last || is_secure(self.group_id.scheme()) {
+                    let tunnel = http_connect(&mut stream, host, port);
+                    self.phase(Phase::Tunnel, timeouts.tunnel, timed_out, tunnel).await?;
+                }
+            }
+        }
+        Ok(stream)
+    }
+
+    // TLS to an HTTPS proxy (`ProxyTls`) or to the destination (`Tls`).
+    async fn ssl(
+        &self,
+        phase: Phase,
+        server_name: &str,
+        session_key: &SslSessionKey,
+        stream: BoxedStream,
+    ) -> Result<BoxedStream, NetError> {
+        let handshake = self.factory.tls.connect(unbracket(server_name), session_key, stream);
+        let timeouts = self.factory.timeouts;
+        self.phase(phase, timeouts.ssl, HttpError::ConnectionTimedOut, handshake).await
+    }
+
+    // Runs one phase under its timeout, after any fault scripted for it, and
+    // tags the failure with the phase. A scripted timeout hangs the phase
+    // until its timeout fires.
+    async fn phase<O>(
+        &self,
+        phase: Phase,
+        timeout: Duration,
+        timed_out: HttpError,
+        work: impl Future<Output = Result<O, NetError>>,
+    ) -> Result<O, NetError> {
+        self.faults.check(phase).map_err(|code| NetError::new(code).with_phase(phase))?;
+        if self.faults.stalls(phase) {
+            tokio::time::sleep(timeout).await;
+            return Err(NetError::new(timed_out).with_phase(phase));
+        }
+        match tokio::time::timeout(timeout, work).await {
+            Ok(result) => result.map_err(|error| error.with_phase(phase)),
+            Err(_) => Err(NetError::new(timed_out).with_phase(phase)),
+        }
+    }
+}
+
+async fn resolve(host: &str, port: u16) -> Result<Vec<SocketAddr>, NetError> {
+    let not_resolved = || NetError::new(HttpError::NameNotResolved);
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((unbracket(host), port))
+        .await
+        .map_err(|error| not_resolved().with_source(error))?
+        .collect();
+    if addrs.is_empty() {
+        return Err(not_resolved());
+    }
+    Ok(addrs)
+}
+
+// Tries the addresses in order; the last failure is reported.
+async fn connect_tcp(addrs: &[SocketAddr]) -> Result<TcpStream, NetError> {
+    let mut last_error = NetError::new(HttpError::ConnectionFailed);
+    for &addr in addrs {
+        match TcpStream::connect(addr).await {
+            Ok(stream) => {
+                // Requests are written whole; Nagle would only delay them.
+                let _ = stream.set_nodelay(true);
+                return Ok(stream);
+            }
+            Err(error) => {
+                last_error =
+                    NetError::new(map_connect_error(&error)).with_endpoint(addr).with_source(error);
+            }
+        }
+    }
+    Err(last_error)
+}
+
+// An I/O error inside a proxy handshake. A proxy that hangs up mid-handshake
+// failed the handshake; other errors keep their socket code.
+fn handshake_error(failed: HttpError) -> impl Fn(io::Error) -> NetError {
+    move |error| match error.kind() {
+        io::ErrorKind::UnexpectedEof => NetError::new(failed).with_source(error),
+        _ => NetError::from(error),
+    }
+}
+
+// SOCKS4 (socks_client_socket.cc), with an empty user id.
+async fn socks4(stream: &mut BoxedStream, addr: SocketAddr) -> Result<(), NetError> {
+    const REQUEST_GRANTED: u8 = 0x5a;
+    const HOST_UNREACHABLE: u8 = 0x5c;
+    let io_error = handshake_error(HttpError::SocksConnectionFailed);
+    let IpAddr::V4(ip) = addr.ip() else {
+        return Err(NetError::new(HttpError::SocksConnectionFailed));
+    };
+    let mut request = vec![0x04, 0x01];
+    request.extend_from_slice(&addr.port().to_be_bytes());
+    request.extend_from_slice(&ip.octets());
+    request.push(0);
+    stream.write_all(&request).await.map_err(&io_error)?;
+    let mut reply = [0u8; 8];
+    stream.read_exact(&mut reply).await.map_err(&io_error)?;
+    match reply {
+        [0x00, REQUEST_GRANTED, ..] => Ok(()),
+        [0x00, HOST_UNREACHABLE, ..] => {
+            Err(NetError::new(HttpError::SocksConnectionHostUnreachable))
+        }
+        // REQUEST_REJECTED, or not a SOCKS4 reply at all.
+        _ => Err(NetError::new(HttpError::SocksConnectionFailed)),
+    }
+}
+
+// SOCKS5 (socks5_client_socket.cc): no authentication, and the host is sent
+// as a domain name so the proxy resolves it.
+async fn socks5(stream: &mut BoxedStream, host: &str, port: u16) -> Result<(), NetError> {
+    let failed = || NetError::new(HttpError::SocksConnectionFailed);
+    let io_error = handshake_error(HttpError::SocksConnectionFailed);
+    let host = unbracket(host);
+    let Ok(host_len) = u8::try_from(host.len()) else { return Err(failed()) };
+
+    stream.write_all(&[0x05, 0x01, 0x00]).await.map_err(&io_error)?;
+    let mut greeting = [0u8; 2];
+    stream.read_exact(&mut greeting).await.map_err(&io_error)?;
+    if greeting != [0x05, 0x00] {
+        return Err(failed());
+    }
+
+    let mut request = vec![0x05, 0x01, 0x00, 0x03, host_len];
+    request.extend_from_slice(host.as_bytes());
+    request.extend_from_slice(&port.to_be_bytes());
+    stream.write_all(&request).await.map_err(&io_error)?;
+    let mut reply = [0u8; 4];
+    stream.read_exact(&mut reply).await.map_err(&io_error)?;
+    if reply[0] != 0x05 || reply[1] != 0x00 {
+        return Err(failed());
+    }
+    // The bound address, which is of no use here, then the port.
+    let address_len = match reply[3] {
+        0x01 => 4,
+        0x04 => 16,
+        0x03 => usize::from(stream.read_u8().await.map_err(&io_error)?),
+        _ => return Err(failed()),
+    };
+    let mut bound = vec![0u8; address_len + 2];
+    stream.read_exact(&mut bound).await.map_err(&io_error)?;
+    Ok(())
+}
+
+// `HttpProxyClientSocket`: CONNECT, then anything but 200 fails the tunnel.
+async fn http_connect(stream: &mut BoxedStream, host: &str, port: u16) -> Result<(), NetError> {
+    let failed = || NetError::new(HttpError::TunnelConnectionFailed);
+    let io_error = handshake_error(HttpError::TunnelConnectionFailed);
+    let authority = if host.contains(':') && !host.starts_with('[') {
+        format!("[{host}]:{port}")
+    } else {
+        format!("{host}:{port}")
+    };
+    let request = format!(
+        "CONNECT {authority} HTTP/1.1\r\nHost: {authority}\r\nProxy-Connection: keep-alive\r\n\r\n"
+    );
+    stream.write_all(request.as_bytes()).await.map_err(&io_error)?;
+
+    // Byte by byte, so nothing after the headers is consumed.
+    let mut head = Vec::new();
+    while !head.ends_with(b"\r\n\r\n") {
+        if head.len() >= MAX_TUNNEL_RESPONSE_HEADERS {
+            return Err(failed());
+        }
+        head.push(stream.read_u8().await.map_err(&io_error)?);
+    }
+    let status_line = head.split(|&b| b == b'\n').next().unwrap_or_default();
+    let status = std::str::from_utf8(status_line)
+        .ok()
+        .filter(|line| line.starts_with("HTTP/1."))
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|code| code.parse::<u16>().ok());
+    match status {
+        Some(200) => Ok(()),
+        // No credentials to answer with; the caller may restart with some.
+        Some(407) => Err(NetError::new(HttpError::ProxyAuthRequested)),
+        _ => Err(failed()),
+    }
+}
+
+// The origin `FaultInjector` rules are written against.
+fn origin(group_id: &GroupId) -> String {
+    let url = format!("{}://{}:{}/", group_id.scheme(), group_id.host(), group_id.port());
+    Url::parse(&url).map_or(url, |url| ErrorStats::origin_of(&url))
+}
+
+fn is_secure(scheme: &str) -> bool {
+    matches!(scheme, "https" | "wss")
+}
+
+fn unbracket(host: &str) -> &str {
+    host.strip_prefix('[').and_then(|h| h.strip_suffix(']')).unwrap_or(host)
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::TcpListener;
+    use tokio::sync::oneshot;
+
+    use super::*;
+    use crate::fault_injection::FaultRule;
+
+    // Completes the handshake, or fails it with the given code.
+    struct FakeTls(Option<HttpError>);
+
+    impl TlsConnector for FakeTls {
+        async fn connect(
+            &self,
+            _server_name: &str,
+            _session_key: &SslSessionKey,
+            stream: BoxedStream,
+        ) -> Result<BoxedStream, NetError> {
+            match self.0 {
+                Some(code) => Err(NetError::new(code)),
+                None => Ok(stream),
+            }
+        }
+    }
+
+    // A one-connection proxy on localhost that reads a request head, answers
+    // it with `reply` (nothing if empty) and reports the head it read.
+    async fn http_proxy(reply: &'static str) -> (u16, oneshot::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (tx, rx) = oneshot::channel();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut head = Vec::new();
+            while !head.ends_with(b"\r\n\r\n") {
+                let Ok(byte) = stream.read_u8().await else { return };
+                head.push(byte);
+            }
+            stream.write_all(reply.as_bytes()).await.unwrap();
+            let _ = tx.send(String::from_utf8(head).unwrap());
+            let _ = stream.read_u8().await;
+        });
+        (port, rx)
+    }
+
+    fn proxy(scheme: ProxyScheme, port: u16) -> ProxyServer {
+        ProxyServer::new(scheme, "127.0.0.1", port)
+    }
+
+    async fn connect(
+        factory: &LayeredConnectJobFactory<FakeTls>,
+        destination: &str,
+        chain: &ProxyChain,
+    ) -> Result<ConnectTiming, NetError> {
+        let url = Url::parse(destination).unwrap();
+        let group_id = GroupId::from_url(&url).unwrap();
+        factory.connect(&group_id, chain).await.map(|(_, timing)| timing)
+    }
+
+    #[tokio::test]
+    async fn tunnels_to_the_destination_for_https() {
+        let (port, head) = http_proxy("HTTP/1.1 200 Connection established\r\n\r\n").await;
+        let factory = LayeredConnectJobFactory::new(FakeTls(None));
+        let chain = ProxyChain::from(proxy(ProxyScheme::Http, port));
+        let timing = connect(&factory, "https://example.com/", &chain).await.unwrap();
+        assert!(head.await.unwrap().starts_with("CONNECT example.com:443 HTTP/1.1\r\n"));
+        assert!(timing.dns_time().is_some());
+        assert!(timing.connect_time().is_some());
+        assert!(timing.ssl_time().is_some());
+    }
+
+    #[tokio::test]
+    async fn repeated_proxy_still_tunnels_to_the_next_hop() {
+        let (port, head) = http_proxy("HTTP/1.1 200 Connection established\r\n\r\n").await;
+        let factory = LayeredConnectJobFactory::new(FakeTls(None));
+        let chain =
+            ProxyChain::new(vec![proxy(ProxyScheme::Http, port), proxy(ProxyScheme::Http, port)]);
+        connect(&factory, "http://example.com/", &chain).await.unwrap();
+        let head = tokio::time::timeout(Duration::from_secs(5), head).await.unwrap().unwrap();
+        assert!(head.starts_with(&format!("CONNECT 127.0.0.1:{port} HTTP/1.1\r\n")), "{head}");
+    }
+
+    #[tokio::test]
+    async fn tunnel_failures_are_tagged_tunnel() {
+        let factory = LayeredConnectJobFactory::new(FakeTls(None)).with_timeouts(ConnectTimeouts {
+            tunnel: Duration::from_millis(50),
+            ..Default::default()
+        });
+        for (reply, expected) in [
+            ("HTTP/1.1 502 Bad Gateway\r\n\r\n", HttpError::TunnelConnectionFailed),
+            ("HTTP/1.1 407 Proxy Authentication Required\r\n\r\n", HttpError::ProxyAuthRequested),
+            ("", HttpError::ConnectionTimedOut),
+        ] {
+            let (port, _head) = http_proxy(reply).await;
+            let chain = ProxyChain::from(proxy(ProxyScheme::Http, port));
+            let error = connect(&factory, "https://example.com/", &chain).await.unwrap_err();
+            assert_eq!(error, expected);
+            assert_eq!(error.phase(), Some(Phase::Tunnel));
+        }
+    }
+
+    #[tokio::test]
+    async fn proxy_tls_and_destination_tls_are_told_apart() {
+        let (port, _head) = http_proxy("").await;
+        let factory = LayeredConnectJobFactory::new(FakeTls(Some(HttpError::SslProtocolError)));
+        let chain = ProxyChain::from(proxy(ProxyScheme::Https, port));
+        let error = connect(&factory, "https://example.com/", &chain).await.unwrap_err();
+        assert_eq!(error, HttpError::SslProtocolError);
+        assert_eq!(error.phase(), Some(Phase::ProxyTls));
+
+        let (port, _head) = http_proxy("").await;
+        let error = connect(&factory, &format!("https://127.0.0.1:{port}/"), &ProxyChain::direct())
+            .await
+            .unwrap_err();
+        assert_eq!(error.phase(), Some(Phase::Tls));
+    }
+
+    #[tokio::test]
+    async fn socks5_rejection_is_tagged_socks() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut greeting = [0u8; 3];
+            stream.read_exact(&mut greeting).await.unwrap();
+            stream.write_all(&[0x05, 0x00]).await.unwrap();
+            let mut request = [0u8; 5];
+            stream.read_exact(&mut request).await.unwrap();
+            let mut rest = vec![0u8; usize::from(request[4]) + 2];
+            stream.read_exact(&mut rest).await.unwrap();
+            // General SOCKS server failure.
+            stream.write_all(&[0x05, 0x01, 0x00, 0x01, 0, 0, 0, 0, 0, 0]).await.unwrap();
+        });
+        let factory = LayeredConnectJobFactory::new(FakeTls(None));
+        let chain = ProxyChain::from(proxy(ProxyScheme::Socks5, port));
+        let error = connect(&factory, "https://example.com/", &chain).await.unwrap_err();
+        assert_eq!(error, HttpError::SocksConnectionFailed);
+        assert_eq!(error.phase(), Some(Phase::Socks));
+    }
+
+    #[tokio::test]
+    async fn refused_connect_is_tagged_connect_with_endpoint() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+        let factory = LayeredConnectJobFactory::new(FakeTls(None));
+        let url = format!("http://127.0.0.1:{port}/");
+        let error = connect(&factory, &url, &ProxyChain::direct()).await.unwrap_err();
+        assert_eq!(error, HttpError::ConnectionRefused);
+        assert_eq!(error.phase(), Some(Phase::Connect));
+        assert_eq!(error.endpoint().map(|e| e.port()), Some(port));
+    }
+
+    #[tokio::test]
+    async fn injected_faults_are_tagged_with_their_phase() {
+        let (port, _head) = http_proxy("").await;
+        let rule = FaultRule::new(Phase::Tls, HttpError::SslProtocolError);
+        let injector = Arc::new(FaultInjector::new(1).with_rule(rule));
+        let factory = LayeredConnectJobFactory::new(FakeTls(None)).with_fault_injector(injector);
+        let url = format!("https://127.0.0.1:{port}/");
+        let error = connect(&factory, &url, &ProxyChain::direct()).await.unwrap_err();
+        assert_eq!(error, HttpError::SslProtocolError);
+        assert_eq!(error.phase(), Some(Phase::Tls));
+    }
+
+    #[tokio::test]
+    async fn injected_timeouts_wait_for_the_phase_timeout() {
+        let rule = FaultRule::new(Phase::Connect, HttpError::ConnectionTimedOut);
+        let injector = Arc::new(FaultInjector::new(1).with_rule(rule));
+        let factory = LayeredConnectJobFactory::new(FakeTls(None))
+            .with_timeouts(ConnectTimeouts {
+                transport: Duration::from_millis(50),
+                ..Default::default()
+            })
+            .with_fault_injector(injector);
+        let started = Instant::now();
+        let error =
+            connect(&factory, "http://127.0.0.1:1/", &ProxyChain::direct()).await.unwrap_err();
+        assert!(started.elapsed() >= Duration::from_millis(50));
+        assert_eq!(error, HttpError::ConnectionTimedOut);
+        assert_eq!(error.phase(), Some(Phase::Connect));
+    }
+}
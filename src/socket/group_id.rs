@@ -0,0 +1,334 @@
+use std::fmt;
+
+use url::Url;
+
+use crate::socket::proxy_chain::ProxyChain;
+
+// `ClientSocketPool::GroupId` and the partitioning keys it carries
+// (net/base/privacy_mode.h, network_anonymization_key.h).
+//
+// The pool only reuses a socket within the group that opened it, and the group
+// carries the privacy mode and network anonymization key, so separate
+// partitions never share a pooled socket. `SslSessionKey` and `SpdySessionKey`
+// carry both as well; `SslClientSessionCache` and `SpdySessionPool` store and
+// find sessions by nothing else, so TLS and HTTP/2 sessions are partitioned
+// the same way.
+
+/// `PrivacyMode`: whether credentials and other stored state may be sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub enum PrivacyMode {
+    #[default]
+    Disabled,
+    Enabled,
+    EnabledWithoutClientCerts,
+    EnabledPartitionedStateAllowed,
+}
+
+impl PrivacyMode {
+    pub const ALL: [PrivacyMode; 4] = [
+        PrivacyMode::Disabled,
+        PrivacyMode::Enabled,
+        PrivacyMode::EnabledWithoutClientCerts,
+        PrivacyMode::EnabledPartitionedStateAllowed,
+    ];
+
+    /// `PrivacyModeToDebugString`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PrivacyMode::Disabled => "disabled",
+            PrivacyMode::Enabled => "enabled",
+            PrivacyMode::EnabledWithoutClientCerts => "enabled without client certs",
+            PrivacyMode::EnabledPartitionedStateAllowed => "enabled partitioned state allowed",
+        }
+    }
+
+    pub fn is_enabled(self) -> bool {
+        self != PrivacyMode::Disabled
+    }
+}
+
+/// `NetworkAnonymizationKey`: the top-frame site a request was made for,
+/// whether the frame was cross-site to it, and an optional nonce.
+///
+/// Chromium's site is scheme plus registrable domain; without a public
+/// suffix list the site here is scheme plus host, which partitions more
+/// finely but never less.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct NetworkAnonymizationKey {
+    top_frame_site: Option<String>,
+    is_cross_site: bool,
+    nonce: Option<u64>,
+}
+
+impl NetworkAnonymizationKey {
+    /// The empty key: the request is not partitioned.
+    pub fn empty() -> Self {
+        Self::default()
+    }
+
+    /// `CreateSameSite` / `CreateCrossSite`. `None` for URLs without a host.
+    pub fn new(top_frame_url: &Url, is_cross_site: bool) -> Option<Self> {
+        let site = format!("{}://{}", top_frame_url.scheme(), top_frame_url.host_str()?);
+        Some(Self { top_frame_site: Some(site.to_ascii_lowercase()), is_cross_site, nonce: None })
+    }
+
+    /// Sets the nonce, which makes the key transient. Chromium uses a random
+    /// token; a caller partitioning by tenant can use the tenant's id.
+    pub fn with_nonce(mut self, nonce: u64) -> Self {
+        self.nonce = Some(nonce);
+        self
+    }
+
+    pub fn top_frame_site(&self) -> Option<&str> {
+        self.top_frame_site.as_deref()
+    }
+
+    pub fn is_cross_site(&self) -> bool {
+        self.is_cross_site
+    }
+
+    pub fn nonce(&self) -> Option<u64> {
+        self.nonce
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.top_frame_site.is_none() && self.nonce.is_none()
+    }
+
+    pub fn is_transient(&self) -> bool {
+        self.nonce.is_some()
+    }
+}
+
+// `ToDebugString`: "https://a.com same_site (with nonce 7)", or "null".
+impl fmt::Display for NetworkAnonymizationKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.top_frame_site.as_deref().unwrap_or("null"))?;
+        if self.is_empty() {
+            return Ok(());
+        }
+        f.write_str(if self.is_cross_site { " cross_site" } else { " same_site" })?;
+        if let Some(nonce) = self.nonce {
+            write!(f, " (with nonce {nonce})")?;
+        }
+        Ok(())
+    }
+}
+
+/// `ClientSocketPool::GroupId`: requests with the same id may share sockets.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GroupId {
+    scheme: String,
+    host: String,
+    port: u16,
+    privacy_mode: PrivacyMode,
+    network_anonymization_key: NetworkAnonymizationKey,
+}
+
+impl GroupId {
+    /// The final destination, not the proxy. Scheme and host are lowercased.
+    pub fn new(scheme: &str, host: &str, port: u16) -> Self {
+        Self {
+            scheme: scheme.to_ascii_lowercase(),
+            host: host.to_ascii_lowercase(),
+            port,
+            privacy_mode: PrivacyMode::Disabled,
+            network_anonymization_key: NetworkAnonymizationKey::empty(),
+        }
+    }
+
+    /// `None` for URLs without a host or a known default port.
+    pub fn from_url(url: &Url) -> Option<Self> {
+        Some(Self::new(url.scheme(), url.host_str()?, url.port_or_known_default()?))
+    }
+
+    pub fn with_privacy_mode(mut self, privacy_mode: PrivacyMode) -> Self {
+        self.privacy_mode = privacy_mode;
+        self
+    }
+
+    pub fn with_network_anonymization_key(mut self, key: NetworkAnonymizationKey) -> Self {
+        self.network_anonymization_key = key;
+        self
+    }
+
+    pub fn scheme(&self) -> &str {
+        &self.scheme
+    }
+
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    pub fn privacy_mode(&self) -> PrivacyMode {
+        self.privacy_mode
+    }
+
+    pub fn network_anonymization_key(&self) -> &NetworkAnonymizationKey {
+        &self.network_anonymization_key
+    }
+
+    /// Same privacy mode and network anonymization key: connection state may
+    /// move between the two ids.
+    pub fn same_partition(&self, other: &GroupId) -> bool {
+        self.privacy_mode == other.privacy_mode
+            && self.network_anonymization_key == other.network_anonymization_key
+    }
+
+    /// The key for resuming TLS sessions to this destination.
+    pub fn ssl_session_key(&self) -> SslSessionKey {
+        SslSessionKey {
+            host: self.host.clone(),
+            port: self.port,
+            privacy_mode: self.privacy_mode,
+            network_anonymization_key: self.network_anonymization_key.clone(),
+        }
+    }
+
+    /// The key for sharing an HTTP/2 session to this destination through
+    /// `proxy_chain`.
+    pub fn spdy_session_key(&self, proxy_chain: &ProxyChain) -> SpdySessionKey {
+        SpdySessionKey {
+            host: self.host.clone(),
+            port: self.port,
+            proxy_chain: proxy_chain.clone(),
+            privacy_mode: self.privacy_mode,
+            network_anonymization_key: self.network_anonymization_key.clone(),
+        }
+    }
+}
+
+// `ToString`: "pm/https://example.com <https://a.com same_site>". The port is
+// left out when it is the scheme's default, as `SchemeHostPort::Serialize`
+// does.
+impl fmt::Display for GroupId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self.privacy_mode {
+            PrivacyMode::Disabled => "",
+            PrivacyMode::Enabled => "pm/",
+            PrivacyMode::EnabledWithoutClientCerts => "pmwocc/",
+            PrivacyMode::EnabledPartitionedStateAllowed => "pmpsa/",
+        })?;
+        write!(f, "{}://{}", self.scheme, self.host)?;
+        if Some(self.port) != default_port(&self.scheme) {
+            write!(f, ":{}", self.port)?;
+        }
+        if !self.network_anonymization_key.is_empty() {
+            write!(f, " <{}>", self.network_anonymization_key)?;
+        }
+        Ok(())
+    }
+}
+
+// `url::DefaultPortForScheme`, for the schemes a group can have.
+fn default_port(scheme: &str) -> Option<u16> {
+    match scheme {
+        "http" | "ws" => Some(80),
+        "https" | "wss" => Some(443),
+        _ => None,
+    }
+}
+
+/// `SSLClientSessionCache::Key`: a TLS session is only offered for resumption
+/// within the partition it was established in.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SslSessionKey {
+    host: String,
+    port: u16,
+    privacy_mode: PrivacyMode,
+    network_anonymization_key: NetworkAnonymizationKey,
+}
+
+impl SslSessionKey {
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+}
+
+/// `SpdySessionKey`: streams only share an HTTP/2 session within its
+/// partition. Aliasing a session to another host by IP must compare the whole
+/// key except host and port, not just the address.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SpdySessionKey {
+    host: String,
+    port: u16,
+    proxy_chain: ProxyChain,
+    privacy_mode: PrivacyMode,
+    network_anonymization_key: NetworkAnonymizationKey,
+}
+
+impl SpdySessionKey {
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    pub fn proxy_chain(&self) -> &ProxyChain {
+        &self.proxy_chain
+    }
+
+    /// A session opened for `self` may carry streams for `other`'s host once
+    /// the certificate covers it.
+    pub fn can_alias(&self, other: &SpdySessionKey) -> bool {
+        self.proxy_chain == other.proxy_chain
+            && self.privacy_mode == other.privacy_mode
+            && self.network_anonymization_key == other.network_anonymization_key
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_prefixes_each_privacy_mode() {
+        let group_id = GroupId::new("https", "example.com", 443);
+        let shown: Vec<String> = PrivacyMode::ALL
+            .into_iter()
+            .map(|mode| group_id.clone().with_privacy_mode(mode).to_string())
+            .collect();
+        assert_eq!(
+            shown,
+            [
+                "https://example.com",
+                "pm/https://example.com",
+                "pmwocc/https://example.com",
+                "pmpsa/https://example.com",
+            ]
+        );
+    }
+
+    #[test]
+    fn display_appends_network_anonymization_key() {
+        let top_frame = Url::parse("https://a.com/page").unwrap();
+        let key = NetworkAnonymizationKey::new(&top_frame, false).unwrap().with_nonce(7);
+        let group_id = GroupId::new("https", "example.com", 443)
+            .with_privacy_mode(PrivacyMode::Enabled)
+            .with_network_anonymization_key(key);
+        assert_eq!(
+            group_id.to_string(),
+            "pm/https://example.com <https://a.com same_site (with nonce 7)>"
+        );
+    }
+
+    #[test]
+    fn display_shows_only_non_default_ports() {
+        let shown = |scheme, port| GroupId::new(scheme, "example.com", port).to_string();
+        assert_eq!(shown("http", 80), "http://example.com");
+        assert_eq!(shown("ws", 80), "ws://example.com");
+        assert_eq!(shown("wss", 443), "wss://example.com");
+        assert_eq!(shown("https", 8443), "https://example.com:8443");
+        assert_eq!(shown("http", 443), "http://example.com:443");
+    }
+}
@@ -0,0 +1,35 @@
+use std::time::{Duration, Instant};
+
+/// `LoadTimingInfo::ConnectTiming` (net/base/load_timing_info.h): when each
+/// step of establishing a connection started and ended. Steps that did not
+/// happen, and every step of a reused socket, are `None`.
+///
+/// As in Chromium, `connect_start..connect_end` spans the whole connection
+/// after DNS, proxy tunnels and the TLS handshake included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ConnectTiming {
+    pub domain_lookup_start: Option<Instant>,
+    pub domain_lookup_end: Option<Instant>,
+    pub connect_start: Option<Instant>,
+    pub connect_end: Option<Instant>,
+    pub ssl_start: Option<Instant>,
+    pub ssl_end: Option<Instant>,
+}
+
+impl ConnectTiming {
+    pub fn dns_time(&self) -> Option<Duration> {
+        span(self.domain_lookup_start, self.domain_lookup_end)
+    }
+
+    pub fn connect_time(&self) -> Option<Duration> {
+        span(self.connect_start, self.connect_end)
+    }
+
+    pub fn ssl_time(&self) -> Option<Duration> {
+        span(self.ssl_start, self.ssl_end)
+    }
+}
+
+fn span(start: Option<Instant>, end: Option<Instant>) -> Option<Duration> {
+    Some(end?.saturating_duration_since(start?))
+}
@@ -0,0 +1,12 @@
+// Socket layer (net/socket/): connection pooling and connect jobs, laid out
+// as in chromium_rust_mapping.md.
+
+pub mod client_socket_handle;
+pub mod connect_job;
+pub mod group_id;
+pub mod load_timing;
+pub mod pool;
+pub mod proxy_chain;
+pub mod spdy_session_pool;
+pub mod ssl_client_session_cache;
+pub mod stream_socket;
@@ -0,0 +1,1280 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::future::Future;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+use tokio::sync::{oneshot, Notify};
+use tokio::task::JoinSet;
+use tokio::time::Instant;
+
+use crate::net_error::NetError;
+use crate::rust_errors::HttpError;
+use crate::socket::client_socket_handle::{ClientSocketHandle, SocketReuseType};
+use crate::socket::group_id::GroupId;
+use crate::socket::load_timing::ConnectTiming;
+use crate::socket::proxy_chain::ProxyChain;
+use crate::socket::stream_socket::StreamSocket;
+
+// `ClientSocketPool` (net/socket/transport_client_socket_pool.cc): hands out
+// connected sockets per group, reusing idle ones, under three limits: per
+// group, per proxy chain and for the whole pool. The group id carries the
+// privacy mode and network anonymization key, so an idle socket is only ever
+// reused within the partition that opened it.
+//
+// Chromium runs the pool on one thread. Here the bookkeeping sits behind one
+// mutex that is never held across an await, since every limit decision looks
+// at more than one group; the per-group idle lists are part of that state.
+//
+// A request that cannot be served waits in its group's queue, highest
+// priority first and FIFO within a priority. A freed slot goes back to its
+// own group first (`OnAvailableSocketSlot`). Slots freed while the pool or a
+// proxy chain is at its cap go to the stalled group with the top request
+// (`CheckForStalledSocketGroups`), closing idle sockets of other groups to
+// make room. A request that is granted a slot runs its own connect job.
+//
+// Idle sockets expire after `UNUSED_IDLE_SOCKET_TIMEOUT` if they never
+// carried a request and after `USED_IDLE_SOCKET_TIMEOUT` if they did, checked
+// at the start of each request as Chromium does, on tokio's clock so tests
+// can pause it. An idle socket is also probed
+// before it is handed out or put back, so one the server already closed is
+// dropped here instead of failing the first write with ERR_CONNECTION_RESET.
+//
+// `flush_with_error` starts a new generation: queued requests and running
+// connects fail, and sockets handed out before it are closed on release.
+
+/// `g_socket_soft_cap_per_pool`, for both pool types.
+pub const MAX_SOCKETS_PER_POOL: usize = 256;
+/// `g_max_sockets_per_group` for `NORMAL_SOCKET_POOL`.
+pub const MAX_SOCKETS_PER_GROUP: usize = 6;
+/// `g_max_sockets_per_group` for `WEBSOCKET_SOCKET_POOL`.
+pub const MAX_WEBSOCKET_SOCKETS_PER_GROUP: usize = 255;
+/// `kDefaultMaxSocketsPerProxyChain`. Direct connections are only bound by
+/// `MAX_SOCKETS_PER_POOL`.
+pub const MAX_SOCKETS_PER_PROXY_CHAIN: usize = 32;
+/// How long a preconnected socket that never carried a request stays idle.
+pub const UNUSED_IDLE_SOCKET_TIMEOUT: Duration = Duration::from_secs(60);
+/// `g_used_idle_socket_timeout_s`.
+pub const USED_IDLE_SOCKET_TIMEOUT: Duration = Duration::from_secs(300);
+
+// Why a socket was closed, as logged with `SOCKET_POOL_CLOSING_SOCKET`.
+pub const CLOSED_CONNECTION_RETURNED_TO_POOL: &str =
+    "Connection was closed when it was returned to the pool";
+pub const DATA_RECEIVED_UNEXPECTEDLY: &str = "Data received unexpectedly";
+pub const IDLE_TIME_LIMIT_EXPIRED: &str = "Idle time limit expired";
+pub const NETWORK_CHANGED: &str = "Network changed";
+pub const REMOTE_SIDE_CLOSED_CONNECTION: &str = "Remote side closed connection";
+pub const SOCKET_GENERATION_OUT_OF_DATE: &str = "Socket generation out of date";
+
+/// `HttpNetworkSession::SocketPoolType`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PoolType {
+    Normal,
+    WebSocket,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolLimits {
+    pub max_sockets: usize,
+    pub max_sockets_per_group: usize,
+    pub max_sockets_per_proxy_chain: usize,
+    pub unused_idle_socket_timeout: Duration,
+    pub used_idle_socket_timeout: Duration,
+}
+
+impl PoolLimits {
+    pub const fn for_pool_type(pool_type: PoolType) -> Self {
+        let max_sockets_per_group = match pool_type {
+            PoolType::Normal => MAX_SOCKETS_PER_GROUP,
+            PoolType::WebSocket => MAX_WEBSOCKET_SOCKETS_PER_GROUP,
+        };
+        Self {
+            max_sockets: MAX_SOCKETS_PER_POOL,
+            max_sockets_per_group,
+            max_sockets_per_proxy_chain: MAX_SOCKETS_PER_PROXY_CHAIN,
+            unused_idle_socket_timeout: UNUSED_IDLE_SOCKET_TIMEOUT,
+            used_idle_socket_timeout: USED_IDLE_SOCKET_TIMEOUT,
+        }
+    }
+}
+
+/// net/base/request_priority.h, lowest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub enum RequestPriority {
+    Throttled,
+    /// `DEFAULT_PRIORITY`.
+    #[default]
+    Idle,
+    Lowest,
+    Low,
+    Medium,
+    Highest,
+}
+
+impl RequestPriority {
+    pub const ALL: [RequestPriority; 6] = [
+        RequestPriority::Throttled,
+        RequestPriority::Idle,
+        RequestPriority::Lowest,
+        RequestPriority::Low,
+        RequestPriority::Medium,
+        RequestPriority::Highest,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RequestPriority::Throttled => "throttled",
+            RequestPriority::Idle => "idle",
+            RequestPriority::Lowest => "lowest",
+            RequestPriority::Low => "low",
+            RequestPriority::Medium => "medium",
+            RequestPriority::Highest => "highest",
+        }
+    }
+}
+
+/// Opens new connections for the pool, reporting when each step ran. TLS
+/// sessions are looked up by `GroupId::ssl_session_key` in an
+/// `SslClientSessionCache`, never by host alone.
+pub trait ConnectJobFactory: Send + Sync + 'static {
+    type Socket: StreamSocket + Send + 'static;
+
+    fn connect(
+        &self,
+        group_id: &GroupId,
+        proxy_chain: &ProxyChain,
+    ) -> impl Future<Output = Result<(Self::Socket, ConnectTiming), NetError>> + Send;
+}
+
+// A socket handed out by the pool, held by its `ClientSocketHandle`.
+#[derive(Debug)]
+pub(crate) struct PooledSocket<S> {
+    socket: S,
+    key: GroupKey,
+    generation: u64,
+}
+
+impl<S> PooledSocket<S> {
+    pub(crate) fn socket(&self) -> &S {
+        &self.socket
+    }
+
+    pub(crate) fn socket_mut(&mut self) -> &mut S {
+        &mut self.socket
+    }
+
+    pub(crate) fn group_id(&self) -> &GroupId {
+        &self.key.group_id
+    }
+
+    pub(crate) fn proxy_chain(&self) -> &ProxyChain {
+        &self.key.proxy_chain
+    }
+}
+
+// Chromium has one pool per proxy chain; here the chain is part of the key.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct GroupKey {
+    group_id: GroupId,
+    proxy_chain: ProxyChain,
+}
+
+struct IdleSocket<S> {
+    socket: S,
+    start_time: Instant,
+    used: bool,
+}
+
+impl<S: StreamSocket> IdleSocket<S> {
+    // `IdleSocket::IsUsable`. A preconnected socket may already hold unread
+    // bytes, such as TLS session tickets; one that carried a request may not.
+    fn unusable_reason(&self) -> Option<&'static str> {
+        if self.used {
+            not_idle_reason(&self.socket, REMOTE_SIDE_CLOSED_CONNECTION)
+        } else if !self.socket.is_connected() {
+            Some(REMOTE_SIDE_CLOSED_CONNECTION)
+        } else {
+            None
+        }
+    }
+}
+
+fn not_idle_reason<S: StreamSocket>(socket: &S, closed: &'static str) -> Option<&'static str> {
+    if socket.is_connected_and_idle() {
+        None
+    } else if socket.is_connected() {
+        Some(DATA_RECEIVED_UNEXPECTEDLY)
+    } else {
+        Some(closed)
+    }
+}
+
+// What a queued request is woken with, with the generation it belongs to.
+enum Grant<S> {
+    Idle(IdleSocket<S>, u64),
+    // A connecting slot has been reserved for the request.
+    Connect(u64),
+    // The pool was flushed.
+    Failed(HttpError),
+}
+
+type RequestKey = (Reverse<RequestPriority>, u64);
+
+struct Group<S> {
+    idle: VecDeque<IdleSocket<S>>,
+    active: usize,
+    connecting: usize,
+    pending: BTreeMap<RequestKey, oneshot::Sender<Grant<S>>>,
+}
+
+impl<S> Default for Group<S> {
+    fn default() -> Self {
+        Self { idle: VecDeque::new(), active: 0, connecting: 0, pending: BTreeMap::new() }
+    }
+}
+
+impl<S> Group<S> {
+    fn socket_count(&self) -> usize {
+        self.active + self.connecting + self.idle.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.socket_count() == 0 && self.pending.is_empty()
+    }
+
+    // `AssignIdleSocketToRequest`: the newest used socket, else the oldest
+    // unused one.
+    fn take_idle(&mut self) -> Option<IdleSocket<S>> {
+        let index = self.idle.iter().rposition(|s| s.used).unwrap_or(0);
+        self.idle.remove(index)
+    }
+}
+
+enum Slot {
+    Available,
+    GroupFull,
+    // At the pool or proxy chain cap.
+    Capped,
+}
+
+struct PoolState<S> {
+    groups: HashMap<GroupKey, Group<S>>,
+    // Sockets in use (connecting, handed out or idle) per proxy chain.
+    chain_sockets: HashMap<ProxyChain, usize>,
+    handed_out: usize,
+    connecting: usize,
+    idle: usize,
+    next_request: u64,
+    generation: u64,
+    // What connects of older generations fail with.
+    flush_error: HttpError,
+}
+
+impl<S: StreamSocket> PoolState<S> {
+    fn sockets_in_use(&self) -> usize {
+        self.handed_out + self.connecting + self.idle
+    }
+
+    fn chain_capped(&self, limits: &PoolLimits, chain: &ProxyChain) -> bool {
+        !chain.is_direct()
+            && self.chain_sockets.get(chain).copied().unwrap_or_default()
+                >= limits.max_sockets_per_proxy_chain
+    }
+
+    fn slot(&self, limits: &PoolLimits, key: &GroupKey) -> Slot {
+        let in_group = self.groups.get(key).map_or(0, Group::socket_count);
+        if in_group >= limits.max_sockets_per_group {
+            Slot::GroupFull
+        } else if self.sockets_in_use() >= limits.max_sockets
+            || self.chain_capped(limits, &key.proxy_chain)
+        {
+            Slot::Capped
+        } else {
+            Slot::Available
+        }
+    }
+
+    // Room for a new socket in `key`, closing an idle socket of another group
+    // if that is what it takes (`CloseOneIdleSocketExceptInGroup`).
+    fn make_room(&mut self, limits: &PoolLimits, key: &GroupKey) -> bool {
+        match self.slot(limits, key) {
+            Slot::Available => true,
+            Slot::GroupFull => false,
+            Slot::Capped => {
+                let chain_capped = self.chain_capped(limits, &key.proxy_chain);
+                let victim = self
+                    .groups
+                    .iter()
+                    .find(|(k, g)| {
+                        *k != key
+                            && !g.idle.is_empty()
+                            && (!chain_capped || k.proxy_chain == key.proxy_chain)
+                    })
+                    .map(|(k, _)| k.clone());
+                match victim {
+                    Some(victim) => {
+                        self.close_oldest_idle(&victim);
+                        matches!(self.slot(limits, key), Slot::Available)
+                    }
+                    None => false,
+                }
+            }
+        }
+    }
+
+    fn close_oldest_idle(&mut self, key: &GroupKey) {
+        let Some(group) = self.groups.get_mut(key) else { return };
+        if group.idle.pop_front().is_some() {
+            self.idle -= 1;
+            self.release_chain_slot(&key.proxy_chain);
+        }
+        self.remove_if_empty(key);
+    }
+
+    // Returns the generation the slot belongs to.
+    fn reserve(&mut self, key: &GroupKey) -> u64 {
+        self.groups.entry(key.clone()).or_default().connecting += 1;
+        self.connecting += 1;
+        if !key.proxy_chain.is_direct() {
+            *self.chain_sockets.entry(key.proxy_chain.clone()).or_default() += 1;
+        }
+        self.generation
+    }
+
+    fn unreserve(&mut self, key: &GroupKey) {
+        if let Some(group) = self.groups.get_mut(key) {
+            group.connecting -= 1;
+        }
+        self.connecting -= 1;
+        self.release_chain_slot(&key.proxy_chain);
+    }
+
+    fn release_chain_slot(&mut self, chain: &ProxyChain) {
+        if let Some(count) = self.chain_sockets.get_mut(chain) {
+            *count -= 1;
+            if *count == 0 {
+                self.chain_sockets.remove(chain);
+            }
+        }
+    }
+
+    fn check_generation(&self, generation: u64) -> Result<(), HttpError> {
+        if generation == self.generation {
+            Ok(())
+        } else {
+            Err(self.flush_error)
+        }
+    }
+
+    fn take_idle(&mut self, limits: &PoolLimits, key: &GroupKey) -> Option<IdleSocket<S>> {
+        self.cleanup_idle_sockets_in_group(limits, key, None);
+        let group = self.groups.get_mut(key)?;
+        let idle = group.take_idle()?;
+        group.active += 1;
+        self.idle -= 1;
+        self.handed_out += 1;
+        Some(idle)
+    }
+
+    // `CleanupIdleSocketsInGroup`: closes the idle sockets that expired or
+    // are no longer usable, or all of them when `force` gives a reason.
+    fn cleanup_idle_sockets_in_group(
+        &mut self,
+        limits: &PoolLimits,
+        key: &GroupKey,
+        force: Option<&str>,
+    ) {
+        let Some(group) = self.groups.get_mut(key) else { return };
+        let now = Instant::now();
+        let before = group.idle.len();
+        group.idle.retain(|idle| {
+            let timeout = if idle.used {
+                limits.used_idle_socket_timeout
+            } else {
+                limits.unused_idle_socket_timeout
+            };
+            let expired = now.duration_since(idle.start_time) >= timeout;
+            let reason =
+                idle.unusable_reason().or(expired.then_some(IDLE_TIME_LIMIT_EXPIRED)).or(force);
+            if let Some(reason) = reason {
+                tracing::debug!(group = %key.group_id, reason, "closing idle socket");
+            }
+            reason.is_none()
+        });
+        let closed = before - group.idle.len();
+        self.idle -= closed;
+        for _ in 0..closed {
+            self.release_chain_slot(&key.proxy_chain);
+        }
+    }
+
+    // `CleanupIdleSockets`.
+    fn cleanup_idle_sockets(&mut self, limits: &PoolLimits, force: Option<&str>) {
+        if self.idle == 0 {
+            return;
+        }
+        let keys: Vec<GroupKey> = self.groups.keys().cloned().collect();
+        for key in keys {
+            self.cleanup_idle_sockets_in_group(limits, &key, force);
+            self.remove_if_empty(&key);
+        }
+    }
+
+    // `FlushWithError`.
+    fn flush_with_error(&mut self, limits: &PoolLimits, error: HttpError) {
+        self.cleanup_idle_sockets(limits, Some(&error.to_string()));
+        for group in self.groups.values_mut() {
+            while let Some((_, waiter)) = group.pending.pop_first() {
+                let _ = waiter.send(Grant::Failed(error));
+            }
+        }
+        self.groups.retain(|_, group| !group.is_empty());
+        self.generation += 1;
+        self.flush_error = error;
+    }
+
+    // Undoes a grant whose request went away before taking it.
+    fn return_grant(&mut self, key: &GroupKey, grant: Grant<S>) {
+        match grant {
+            Grant::Idle(idle, _) => {
+                let group = self.groups.entry(key.clone()).or_default();
+                group.active -= 1;
+                if idle.used {
+                    group.idle.push_back(idle);
+                } else {
+                    group.idle.push_front(idle);
+                }
+                self.handed_out -= 1;
+                self.idle += 1;
+            }
+            Grant::Connect(_) => self.unreserve(key),
+            Grant::Failed(_) => {}
+        }
+    }
+
+    // `ProcessPendingRequest`, repeated while the group can serve its queue.
+    fn process_pending(&mut self, limits: &PoolLimits, key: &GroupKey) {
+        loop {
+            let Some(group) = self.groups.get(key) else { return };
+            if group.pending.is_empty() {
+                break;
+            }
+            let grant = match self.take_idle(limits, key) {
+                Some(idle) => Grant::Idle(idle, self.generation),
+                None if self.make_room(limits, key) => Grant::Connect(self.reserve(key)),
+                None => break,
+            };
+            let group = self.groups.get_mut(key).expect("group with pending requests");
+            let (_, waiter) = group.pending.pop_first().expect("pending request");
+            if let Err(grant) = waiter.send(grant) {
+                self.return_grant(key, grant);
+            }
+        }
+        self.remove_if_empty(key);
+    }
+
+    // `CheckForStalledSocketGroups`: hands freed capacity to the groups with
+    // the highest-priority requests that are not at their own group limit.
+    fn check_for_stalled_groups(&mut self, limits: &PoolLimits) {
+        loop {
+            let mut stalled: Vec<(RequestKey, GroupKey)> = self
+                .groups
+                .iter()
+                .filter(|(_, g)| g.socket_count() < limits.max_sockets_per_group)
+                .filter_map(|(k, g)| g.pending.keys().next().map(|r| (*r, k.clone())))
+                .collect();
+            stalled.sort_by_key(|(request, _)| *request);
+            let Some((_, key)) = stalled.into_iter().find(|(_, k)| self.make_room(limits, k))
+            else {
+                return;
+            };
+            self.process_pending(limits, &key);
+        }
+    }
+
+    fn on_slot_freed(&mut self, limits: &PoolLimits, key: &GroupKey) {
+        self.process_pending(limits, key);
+        self.check_for_stalled_groups(limits);
+    }
+
+    fn is_stalled(&self, limits: &PoolLimits) -> bool {
+        self.groups.iter().any(|(k, g)| {
+            !g.pending.is_empty()
+                && g.socket_count() < limits.max_sockets_per_group
+                && matches!(self.slot(limits, k), Slot::Capped)
+        })
+    }
+
+    fn remove_if_empty(&mut self, key: &GroupKey) {
+        if self.groups.get(key).is_some_and(Group::is_empty) {
+            self.groups.remove(key);
+        }
+    }
+}
+
+struct Shared<F: ConnectJobFactory> {
+    limits: PoolLimits,
+    factory: F,
+    state: Mutex<PoolState<F::Socket>>,
+    // Wakes running connects when the pool is flushed.
+    flushed: Notify,
+}
+
+impl<F: ConnectJobFactory> Shared<F> {
+    // Bookkeeping stays consistent across a panic elsewhere, so a poisoned
+    // lock is still usable.
+    fn state(&self) -> MutexGuard<'_, PoolState<F::Socket>> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+pub struct ClientSocketPool<F: ConnectJobFactory> {
+    shared: Arc<Shared<F>>,
+}
+
+impl<F: ConnectJobFactory> Clone for ClientSocketPool<F> {
+    fn clone(&self) -> Self {
+        Self { shared: self.shared.clone() }
+    }
+}
+
+impl<F: ConnectJobFactory> ClientSocketPool<F> {
+    pub fn new(pool_type: PoolType, factory: F) -> Self {
+        Self::with_limits(PoolLimits::for_pool_type(pool_type), factory)
+    }
+
+    pub fn with_limits(limits: PoolLimits, factory: F) -> Self {
+        let state = PoolState {
+            groups: HashMap::new(),
+            chain_sockets: HashMap::new(),
+            handed_out: 0,
+            connecting: 0,
+            idle: 0,
+            next_request: 0,
+            generation: 0,
+            flush_error: HttpError::Aborted,
+        };
+        let shared = Shared { limits, factory, state: Mutex::new(state), flushed: Notify::new() };
+        Self { shared: Arc::new(shared) }
+    }
+
+    pub fn limits(&self) -> PoolLimits {
+        self.shared.limits
+    }
+
+    /// `RequestSocket`: an idle socket of the group if there is one, else a
+    /// new connection once the limits allow it. Dropping the future cancels
+    /// the request; dropping the handle releases the socket.
+    pub async fn request_socket(
+        &self,
+        group_id: GroupId,
+        proxy_chain: ProxyChain,
+        priority: RequestPriority,
+    ) -> Result<ClientSocketHandle<F>, NetError> {
+        let key = GroupKey { group_id, proxy_chain };
+        let limits = &self.shared.limits;
+        let grant = {
+            let mut state = self.shared.state();
+            state.cleanup_idle_sockets(limits, None);
+            state.groups.entry(key.clone()).or_default();
+            match state.take_idle(limits, &key) {
+                Some(idle) => Ok(Grant::Idle(idle, state.generation)),
+                None if state.make_room(limits, &key) => Ok(Grant::Connect(state.reserve(&key))),
+                None => {
+                    let request = (Reverse(priority), state.next_request);
+                    state.next_request += 1;
+                    let (tx, rx) = oneshot::channel();
+                    let group = state.groups.get_mut(&key).expect("group was just created");
+                    group.pending.insert(request, tx);
+                    Err(Waiter { shared: &self.shared, key: key.clone(), request, rx: Some(rx) })
+                }
+            }
+        };
+        let grant = match grant {
+            Ok(grant) => grant,
+            Err(waiter) => waiter.wait().await,
+        };
+        match grant {
+            Grant::Idle(idle, generation) => {
+                let reuse_type = if idle.used {
+                    SocketReuseType::ReusedIdle
+                } else {
+                    SocketReuseType::UnusedIdle
+                };
+                let socket = PooledSocket { socket: idle.socket, key, generation };
+                let idle_time = idle.start_time.elapsed();
+                let timing = ConnectTiming::default();
+                Ok(ClientSocketHandle::new(self.clone(), socket, reuse_type, idle_time, timing))
+            }
+            Grant::Connect(generation) => {
+                let (socket, timing) = self.connect(key, generation).await?;
+                let reuse_type = SocketReuseType::Unused;
+                Ok(ClientSocketHandle::new(
+                    self.clone(),
+                    socket,
+                    reuse_type,
+                    Duration::ZERO,
+                    timing,
+                ))
+            }
+            Grant::Failed(error) => Err(error.into()),
+        }
+    }
+
+    /// `RequestSockets`: preconnects until the group holds `num_sockets`
+    /// sockets (at most the group limit), leaving them idle. Connect failures
+    /// are not reported; hitting a limit first is
+    /// `ERR_PRECONNECT_MAX_SOCKET_LIMIT`, after the connects that did fit.
+    pub async fn request_sockets(
+        &self,
+        group_id: GroupId,
+        proxy_chain: ProxyChain,
+        num_sockets: usize,
+    ) -> Result<(), HttpError> {
+        let key = GroupKey { group_id, proxy_chain };
+        let limits = &self.shared.limits;
+        let num_sockets = num_sockets.min(limits.max_sockets_per_group);
+        let mut result = Ok(());
+        let mut jobs = JoinSet::new();
+        {
+            let mut state = self.shared.state();
+            state.cleanup_idle_sockets(limits, None);
+            while state.groups.get(&key).map_or(0, Group::socket_count) < num_sockets {
+                if !state.make_room(limits, &key) {
+                    result = Err(HttpError::PreconnectMaxSocketLimit);
+                    break;
+                }
+                let generation = state.reserve(&key);
+                let pool = self.clone();
+                let key = key.clone();
+                jobs.spawn(async move { pool.preconnect(key, generation).await });
+            }
+        }
+        while jobs.join_next().await.is_some() {}
+        result
+    }
+
+    /// `ReleaseSocket`. A `reusable` socket that is still connected with
+    /// nothing unread goes to the group's idle list (or straight to a queued
+    /// request); any other, or one handed out before a flush, is closed.
+    pub(crate) fn release_socket(&self, socket: PooledSocket<F::Socket>, reusable: bool) {
+        let PooledSocket { socket, key, generation, .. } = socket;
+        let limits = &self.shared.limits;
+        let mut state = self.shared.state();
+        let reason = if reusable {
+            not_idle_reason(&socket, CLOSED_CONNECTION_RETURNED_TO_POOL)
+        } else {
+            Some(CLOSED_CONNECTION_RETURNED_TO_POOL)
+        }
+        .or((generation != state.generation).then_some(SOCKET_GENERATION_OUT_OF_DATE));
+        let group = state.groups.get_mut(&key).expect("released socket's group exists");
+        group.active -= 1;
+        match reason {
+            None => {
+                group.idle.push_back(IdleSocket { socket, start_time: Instant::now(), used: true });
+                state.idle += 1;
+            }
+            Some(reason) => {
+                tracing::debug!(group = %key.group_id, reason, "closing released socket");
+                drop(socket);
+                state.release_chain_slot(&key.proxy_chain);
+            }
+        }
+        state.handed_out -= 1;
+        state.on_slot_freed(limits, &key);
+    }
+
+    /// `CloseIdleSockets`: closes every idle socket, logging `reason`.
+    pub fn close_idle_sockets(&self, reason: &str) {
+        self.shared.state().cleanup_idle_sockets(&self.shared.limits, Some(reason));
+    }
+
+    /// `FlushWithError`, for network changes and the like: closes the idle
+    /// sockets, fails queued requests and running connects with `error`, and
+    /// closes sockets handed out before the flush when they are released.
+    /// The error's description is the closing reason; for `NetworkChanged`
+    /// that is Chromium's own.
+    pub fn flush_with_error(&self, error: HttpError) {
+        self.shared.state().flush_with_error(&self.shared.limits, error);
+        self.shared.flushed.notify_waiters();
+    }
+
+    /// A request is waiting on the pool or proxy chain cap rather than on its
+    /// own group's limit.
+    pub fn is_stalled(&self) -> bool {
+        self.shared.state().is_stalled(&self.shared.limits)
+    }
+
+    pub fn idle_socket_count(&self) -> usize {
+        self.shared.state().idle
+    }
+
+    pub fn idle_socket_count_in_group(
+        &self,
+        group_id: &GroupId,
+        proxy_chain: &ProxyChain,
+    ) -> usize {
+        let key = GroupKey { group_id: group_id.clone(), proxy_chain: proxy_chain.clone() };
+        self.shared.state().groups.get(&key).map_or(0, |g| g.idle.len())
+    }
+
+    pub fn handed_out_socket_count(&self) -> usize {
+        self.shared.state().handed_out
+    }
+
+    pub fn connecting_socket_count(&self) -> usize {
+        self.shared.state().connecting
+    }
+
+    async fn connect(
+        &self,
+        key: GroupKey,
+        generation: u64,
+    ) -> Result<(PooledSocket<F::Socket>, ConnectTiming), NetError> {
+        let slot = ConnectSlot { shared: &self.shared, key: Some(key.clone()), generation };
+        let (socket, timing) = self.run_connect_job(&key, generation).await?;
+        slot.complete(|state, group| {
+            group.active += 1;
+            state.handed_out += 1;
+            None
+        })?;
+        Ok((PooledSocket { socket, key, generation }, timing))
+    }
+
+    async fn preconnect(&self, key: GroupKey, generation: u64) {
+        let slot = ConnectSlot { shared: &self.shared, key: Some(key.clone()), generation };
+        if let Ok((socket, _)) = self.run_connect_job(&key, generation).await {
+            let idle = IdleSocket { socket, start_time: Instant::now(), used: false };
+            let _ = slot.complete(|state, group| {
+                group.idle.push_back(idle);
+                state.idle += 1;
+                Some(key)
+            });
+        }
+    }
+
+    // Fails with the flush error once the pool is flushed, whether before the
+    // job starts or while it runs.
+    async fn run_connect_job(
+        &self,
+        key: &GroupKey,
+        generation: u64,
+    ) -> Result<(F::Socket, ConnectTiming), NetError> {
+        let flushed = self.shared.flushed.notified();
+        let current = self.shared.state().check_generation(generation);
+        current?;
+        tokio::select! {
+            result = self.shared.factory.connect(&key.group_id, &key.proxy_chain) => result,
+            () = flushed => Err(self.shared.state().flush_error.into()),
+        }
+    }
+}
+
+// A queued request. Dropping it (the caller gave up) removes it from the
+// queue, and hands back anything it was granted in the meantime.
+struct Waiter<'a, F: ConnectJobFactory> {
+    shared: &'a Shared<F>,
+    key: GroupKey,
+    request: RequestKey,
+    rx: Option<oneshot::Receiver<Grant<F::Socket>>>,
+}
+
+impl<F: ConnectJobFactory> Waiter<'_, F> {
+    async fn wait(mut self) -> Grant<F::Socket> {
+        let rx = self.rx.as_mut().expect("waiter polled once");
+        let grant = rx.await.expect("queued requests are granted before their sender drops");
+        self.rx = None;
+        grant
+    }
+}
+
+impl<F: ConnectJobFactory> Drop for Waiter<'_, F> {
+    fn drop(&mut self) {
+        let Some(mut rx) = self.rx.take() else { return };
+        let limits = &self.shared.limits;
+        let mut state = self.shared.state();
+        if let Some(group) = state.groups.get_mut(&self.key) {
+            group.pending.remove(&self.request);
+        }
+        if let Ok(grant) = rx.try_recv() {
+            state.return_grant(&self.key, grant);
+        }
+        state.on_slot_freed(limits, &self.key);
+    }
+}
+
+// A reserved connecting slot. Released if the connect fails or is cancelled.
+struct ConnectSlot<'a, F: ConnectJobFactory> {
+    shared: &'a Shared<F>,
+    key: Option<GroupKey>,
+    generation: u64,
+}
+
+impl<F: ConnectJobFactory> ConnectSlot<'_, F> {
+    // Turns the slot into a connected socket; `place` books it as handed out
+    // or idle and returns the group to run the queue for, if any. A socket
+    // from before a flush is closed instead.
+    fn complete(
+        mut self,
+        place: impl FnOnce(&mut PoolState<F::Socket>, &mut Group<F::Socket>) -> Option<GroupKey>,
+    ) -> Result<(), HttpError> {
+        let key = self.key.take().expect("slot completed once");
+        let limits = &self.shared.limits;
+        let mut state = self.shared.state();
+        let mut group = state.groups.remove(&key).unwrap_or_default();
+        group.connecting -= 1;
+        state.connecting -= 1;
+        let result = state.check_generation(self.generation);
+        let freed = match result {
+            Ok(()) => place(&mut state, &mut group),
+            Err(_) => {
+                state.release_chain_slot(&key.proxy_chain);
+                Some(key.clone())
+            }
+        };
+        state.groups.insert(key, group);
+        if let Some(key) = freed {
+            state.on_slot_freed(limits, &key);
+        }
+        result
+    }
+}
+
+impl<F: ConnectJobFactory> Drop for ConnectSlot<'_, F> {
+    fn drop(&mut self) {
+        let Some(key) = self.key.take() else { return };
+        let limits = &self.shared.limits;
+        let mut state = self.shared.state();
+        state.unreserve(&key);
+        state.on_slot_freed(limits, &key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::socket::group_id::{NetworkAnonymizationKey, PrivacyMode};
+
+    struct FakeSocket;
+
+    impl StreamSocket for FakeSocket {
+        fn is_connected(&self) -> bool {
+            true
+        }
+
+        fn is_connected_and_idle(&self) -> bool {
+            true
+        }
+    }
+
+    // Connects at once, or never when `hang` is set.
+    #[derive(Clone, Default)]
+    struct FakeFactory {
+        hang: bool,
+        connects: Arc<AtomicUsize>,
+    }
+
+    impl ConnectJobFactory for FakeFactory {
+        type Socket = FakeSocket;
+
+        async fn connect(
+            &self,
+            _group_id: &GroupId,
+            _proxy_chain: &ProxyChain,
+        ) -> Result<(FakeSocket, ConnectTiming), NetError> {
+            self.connects.fetch_add(1, Ordering::SeqCst);
+            if self.hang {
+                std::future::pending::<()>().await;
+            }
+            Ok((FakeSocket, ConnectTiming::default()))
+        }
+    }
+
+    fn pool(max_sockets: usize, max_sockets_per_group: usize) -> ClientSocketPool<FakeFactory> {
+        pool_with(max_sockets, max_sockets_per_group, FakeFactory::default())
+    }
+
+    fn pool_with(
+        max_sockets: usize,
+        max_sockets_per_group: usize,
+        factory: FakeFactory,
+    ) -> ClientSocketPool<FakeFactory> {
+        let limits = PoolLimits {
+            max_sockets,
+            max_sockets_per_group,
+            ..PoolLimits::for_pool_type(PoolType::Normal)
+        };
+        ClientSocketPool::with_limits(limits, factory)
+    }
+
+    fn group(host: &str) -> GroupId {
+        GroupId::new("https", host, 443)
+    }
+
+    type Request = tokio::task::JoinHandle<Result<ClientSocketHandle<FakeFactory>, NetError>>;
+
+    fn spawn_request(
+        pool: &ClientSocketPool<FakeFactory>,
+        host: &str,
+        priority: RequestPriority,
+    ) -> Request {
+        let pool = pool.clone();
+        let group_id = group(host);
+        tokio::spawn(
+            async move { pool.request_socket(group_id, ProxyChain::direct(), priority).await },
+        )
+    }
+
+    // Lets spawned requests run until they complete or queue.
+    async fn run_until_idle() {
+        for _ in 0..16 {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    async fn request(
+        pool: &ClientSocketPool<FakeFactory>,
+        host: &str,
+    ) -> ClientSocketHandle<FakeFactory> {
+        pool.request_socket(group(host), ProxyChain::direct(), RequestPriority::default())
+            .await
+            .unwrap()
+    }
+
+    fn assert_counts(
+        pool: &ClientSocketPool<FakeFactory>,
+        handed_out: usize,
+        connecting: usize,
+        idle: usize,
+    ) {
+        assert_eq!(
+            (
+                pool.handed_out_socket_count(),
+                pool.connecting_socket_count(),
+                pool.idle_socket_count()
+            ),
+            (handed_out, connecting, idle),
+            "(handed out, connecting, idle)"
+        );
+    }
+
+    #[tokio::test]
+    async fn group_limit_queues_requests() {
+        let pool = pool(10, 2);
+        let mut first = request(&pool, "a.test").await;
+        let _second = request(&pool, "a.test").await;
+        let queued = spawn_request(&pool, "a.test", RequestPriority::default());
+        let mut other_group = request(&pool, "b.test").await;
+        run_until_idle().await;
+        assert!(!queued.is_finished());
+        assert!(!pool.is_stalled());
+        assert_counts(&pool, 3, 0, 0);
+
+        first.disconnect();
+        drop(first);
+        run_until_idle().await;
+        let _third = queued.await.unwrap().unwrap();
+        other_group.disconnect();
+        drop(other_group);
+        assert_counts(&pool, 2, 0, 0);
+    }
+
+    #[tokio::test]
+    async fn pool_cap_goes_to_highest_priority_stalled_group() {
+        let pool = pool(2, 6);
+        let first = request(&pool, "a.test").await;
+        let second = request(&pool, "a.test").await;
+        let low = spawn_request(&pool, "b.test", RequestPriority::Low);
+        run_until_idle().await;
+        let highest = spawn_request(&pool, "c.test", RequestPriority::Highest);
+        run_until_idle().await;
+        assert!(pool.is_stalled());
+
+        drop(first);
+        run_until_idle().await;
+        let highest = highest.await.unwrap().unwrap();
+        assert_eq!(highest.group_id(), &group("c.test"));
+        assert!(!low.is_finished());
+
+        drop(second);
+        run_until_idle().await;
+        let low = low.await.unwrap().unwrap();
+        assert_eq!(low.group_id(), &group("b.test"));
+        assert!(!pool.is_stalled());
+        assert_counts(&pool, 2, 0, 0);
+    }
+
+    #[tokio::test]
+    async fn cancelled_queued_request_is_not_granted() {
+        let factory = FakeFactory::default();
+        let pool = pool_with(10, 1, factory.clone());
+        let mut held = request(&pool, "a.test").await;
+        let queued = spawn_request(&pool, "a.test", RequestPriority::default());
+        run_until_idle().await;
+        queued.abort();
+        assert!(queued.await.unwrap_err().is_cancelled());
+
+        held.set_body_remaining(Some(0));
+        drop(held);
+        run_until_idle().await;
+        assert_counts(&pool, 0, 0, 1);
+        let next = request(&pool, "a.test").await;
+        assert_eq!(next.reuse_type(), SocketReuseType::ReusedIdle);
+        assert_eq!(factory.connects.load(Ordering::SeqCst), 1);
+        assert_counts(&pool, 1, 0, 0);
+    }
+
+    #[tokio::test]
+    async fn cancelled_connect_frees_its_slot() {
+        let hanging = pool_with(1, 1, FakeFactory { hang: true, ..Default::default() });
+        let connecting = spawn_request(&hanging, "a.test", RequestPriority::default());
+        run_until_idle().await;
+        assert_counts(&hanging, 0, 1, 0);
+        connecting.abort();
+        let _ = connecting.await;
+        assert_counts(&hanging, 0, 0, 0);
+    }
+
+    #[tokio::test]
+    async fn handle_dropped_before_response_is_not_reused() {
+        let factory = FakeFactory::default();
+        let pool = pool_with(10, 1, factory.clone());
+        let cancelled = request(&pool, "a.test").await;
+        let queued = spawn_request(&pool, "a.test", RequestPriority::default());
+        run_until_idle().await;
+        drop(cancelled);
+        run_until_idle().await;
+        let mut next = queued.await.unwrap().unwrap();
+        assert_eq!(next.reuse_type(), SocketReuseType::Unused);
+        assert_eq!(factory.connects.load(Ordering::SeqCst), 2);
+
+        next.set_body_remaining(Some(0));
+        assert!(next.is_reusable());
+        drop(next);
+        assert_counts(&pool, 0, 0, 1);
+    }
+
+    #[tokio::test]
+    async fn preconnect_stops_at_limits() {
+        let pool = pool(10, 2);
+        pool.request_sockets(group("a.test"), ProxyChain::direct(), 5).await.unwrap();
+        assert_counts(&pool, 0, 0, 2);
+
+        let pool = pool_with(2, 6, FakeFactory::default());
+        let result = pool.request_sockets(group("a.test"), ProxyChain::direct(), 3).await;
+        assert_eq!(result, Err(HttpError::PreconnectMaxSocketLimit));
+        assert_counts(&pool, 0, 0, 2);
+
+        let handle = request(&pool, "a.test").await;
+        assert_eq!(handle.reuse_type(), SocketReuseType::UnusedIdle);
+        assert_counts(&pool, 1, 0, 1);
+    }
+
+    #[tokio::test]
+    async fn flush_fails_queued_requests_and_running_connects() {
+        let pool = pool_with(10, 1, FakeFactory { hang: true, ..Default::default() });
+        let connecting = spawn_request(&pool, "a.test", RequestPriority::default());
+        run_until_idle().await;
+        let queued = spawn_request(&pool, "a.test", RequestPriority::default());
+        run_until_idle().await;
+        assert_counts(&pool, 0, 1, 0);
+
+        pool.flush_with_error(HttpError::NetworkChanged);
+        assert_eq!(connecting.await.unwrap().unwrap_err(), HttpError::NetworkChanged);
+        assert_eq!(queued.await.unwrap().unwrap_err(), HttpError::NetworkChanged);
+        assert_counts(&pool, 0, 0, 0);
+    }
+
+    #[tokio::test]
+    async fn partitions_do_not_share_idle_sockets() {
+        let factory = FakeFactory::default();
+        let pool = pool_with(10, 6, factory.clone());
+        let top_frame = url::Url::parse("https://tenant-a.test/").unwrap();
+        let tenant_a = NetworkAnonymizationKey::new(&top_frame, false).unwrap();
+        let partitions = [
+            group("a.test"),
+            group("a.test").with_privacy_mode(PrivacyMode::Enabled),
+            group("a.test").with_network_anonymization_key(tenant_a.clone()),
+            group("a.test").with_network_anonymization_key(tenant_a.with_nonce(2)),
+        ];
+        for group_id in &partitions {
+            let mut handle = pool
+                .request_socket(group_id.clone(), ProxyChain::direct(), RequestPriority::default())
+                .await
+                .unwrap();
+            assert_eq!(handle.reuse_type(), SocketReuseType::Unused, "{group_id}");
+            handle.set_body_remaining(Some(0));
+        }
+        assert_eq!(factory.connects.load(Ordering::SeqCst), partitions.len());
+        assert_counts(&pool, 0, 0, partitions.len());
+        for group_id in &partitions {
+            assert_eq!(pool.idle_socket_count_in_group(group_id, &ProxyChain::direct()), 1);
+            let handle = pool
+                .request_socket(group_id.clone(), ProxyChain::direct(), RequestPriority::default())
+                .await
+                .unwrap();
+            assert_eq!(handle.reuse_type(), SocketReuseType::ReusedIdle, "{group_id}");
+            assert_eq!(handle.group_id(), group_id);
+        }
+        assert_eq!(factory.connects.load(Ordering::SeqCst), partitions.len());
+    }
+
+    // A request that leaves its socket clean for reuse.
+    async fn request_and_release(pool: &ClientSocketPool<FakeFactory>, host: &str) {
+        let mut handle = request(pool, host).await;
+        handle.set_body_remaining(Some(0));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn idle_sockets_expire_by_whether_they_were_used() {
+        let pool = ClientSocketPool::new(PoolType::Normal, FakeFactory::default());
+        pool.request_sockets(group("unused.test"), ProxyChain::direct(), 1).await.unwrap();
+        request_and_release(&pool, "used.test").await;
+        assert_counts(&pool, 0, 0, 2);
+
+        // Expiry is checked when a request starts.
+        tokio::time::advance(UNUSED_IDLE_SOCKET_TIMEOUT - Duration::from_secs(1)).await;
+        request_and_release(&pool, "other.test").await;
+        assert_counts(&pool, 0, 0, 3);
+
+        tokio::time::advance(Duration::from_secs(1)).await;
+        request_and_release(&pool, "other.test").await;
+        assert_eq!(
+            pool.idle_socket_count_in_group(&group("unused.test"), &ProxyChain::direct()),
+            0
+        );
+        assert_eq!(pool.idle_socket_count_in_group(&group("used.test"), &ProxyChain::direct()), 1);
+
+        tokio::time::advance(USED_IDLE_SOCKET_TIMEOUT - UNUSED_IDLE_SOCKET_TIMEOUT).await;
+        let handle = request(&pool, "used.test").await;
+        assert_eq!(handle.reuse_type(), SocketReuseType::Unused);
+        // other.test's socket was released a minute later and is still idle.
+        assert_counts(&pool, 1, 0, 1);
+    }
+
+    #[tokio::test]
+    async fn close_idle_sockets_closes_every_group() {
+        let factory = FakeFactory::default();
+        let pool = pool_with(10, 6, factory.clone());
+        pool.request_sockets(group("a.test"), ProxyChain::direct(), 2).await.unwrap();
+        request_and_release(&pool, "b.test").await;
+        let held = request(&pool, "c.test").await;
+        assert_counts(&pool, 1, 0, 3);
+
+        pool.close_idle_sockets(NETWORK_CHANGED);
+        assert_counts(&pool, 1, 0, 0);
+        let handle = request(&pool, "b.test").await;
+        assert_eq!(handle.reuse_type(), SocketReuseType::Unused);
+        assert_eq!(factory.connects.load(Ordering::SeqCst), 5);
+        drop(held);
+    }
+
+    // Real TCP sockets, for the peek-based probe.
+    #[cfg(unix)]
+    mod probe {
+        use std::net::SocketAddr;
+
+        use tokio::io::AsyncWriteExt;
+        use tokio::net::{TcpListener, TcpStream};
+
+        use super::*;
+
+        // Connects every group to one local listener.
+        struct TcpFactory(SocketAddr);
+
+        impl ConnectJobFactory for TcpFactory {
+            type Socket = TcpStream;
+
+            async fn connect(
+                &self,
+                _group_id: &GroupId,
+                _proxy_chain: &ProxyChain,
+            ) -> Result<(TcpStream, ConnectTiming), NetError> {
+                let stream = TcpStream::connect(self.0)
+                    .await
+                    .map_err(|e| NetError::new(crate::error_map::map_system_error(&e)))?;
+                Ok((stream, ConnectTiming::default()))
+            }
+        }
+
+        async fn tcp_pool() -> (ClientSocketPool<TcpFactory>, TcpListener) {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let factory = TcpFactory(listener.local_addr().unwrap());
+            (ClientSocketPool::new(PoolType::Normal, factory), listener)
+        }
+
+        async fn tcp_request(
+            pool: &ClientSocketPool<TcpFactory>,
+        ) -> ClientSocketHandle<TcpFactory> {
+            pool.request_socket(group("a.test"), ProxyChain::direct(), RequestPriority::default())
+                .await
+                .unwrap()
+        }
+
+        // Gives the loopback peer's bytes or FIN time to arrive.
+        async fn settle() {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        #[tokio::test]
+        async fn probe_drops_idle_socket_with_unread_data() {
+            let (pool, listener) = tcp_pool().await;
+            let mut handle = tcp_request(&pool).await;
+            let (mut server, _) = listener.accept().await.unwrap();
+            handle.set_body_remaining(Some(0));
+            drop(handle);
+            let mut handle = tcp_request(&pool).await;
+            assert_eq!(handle.reuse_type(), SocketReuseType::ReusedIdle);
+            handle.set_body_remaining(Some(0));
+            drop(handle);
+
+            server.write_all(b"HTTP/1.1 408 Request Timeout\r\n\r\n").await.unwrap();
+            settle().await;
+            let handle = tcp_request(&pool).await;
+            assert_eq!(handle.reuse_type(), SocketReuseType::Unused);
+            assert_eq!(pool.idle_socket_count(), 0);
+
+            // A preconnected socket may hold bytes such as session tickets.
+            drop(handle);
+            pool.request_sockets(group("a.test"), ProxyChain::direct(), 1).await.unwrap();
+            let (mut server, _) = listener.accept().await.unwrap();
+            server.write_all(b"ticket").await.unwrap();
+            settle().await;
+            let handle = tcp_request(&pool).await;
+            assert_eq!(handle.reuse_type(), SocketReuseType::UnusedIdle);
+        }
+
+        #[tokio::test]
+        async fn server_closed_socket_is_not_reused() {
+            let (pool, listener) = tcp_pool().await;
+            let mut handle = tcp_request(&pool).await;
+            let (server, _) = listener.accept().await.unwrap();
+            handle.set_body_remaining(Some(0));
+            drop(handle);
+            assert_eq!(pool.idle_socket_count(), 1);
+
+            drop(server);
+            settle().await;
+            let mut handle = tcp_request(&pool).await;
+            assert_eq!(handle.reuse_type(), SocketReuseType::Unused);
+            assert_eq!(pool.idle_socket_count(), 0);
+
+            // Closed while handed out: not put back on release either.
+            let (server, _) = listener.accept().await.unwrap();
+            drop(server);
+            settle().await;
+            handle.set_body_remaining(Some(0));
+            drop(handle);
+            assert_counts_of(&pool, 0, 0);
+        }
+
+        fn assert_counts_of(pool: &ClientSocketPool<TcpFactory>, handed_out: usize, idle: usize) {
+            assert_eq!(
+                (pool.handed_out_socket_count(), pool.idle_socket_count()),
+                (handed_out, idle)
+            );
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use std::fmt;
+use std::str::FromStr;
+
+use thiserror::Error;
+
+// `ProxyServer` and `ProxyChain` (net/base/proxy_server.h, proxy_chain.h).
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ProxyScheme {
+    Http,
+    Https,
+    Socks4,
+    Socks5,
+}
+
+impl ProxyScheme {
+    pub const ALL: [ProxyScheme; 4] =
+        [ProxyScheme::Http, ProxyScheme::Https, ProxyScheme::Socks4, ProxyScheme::Socks5];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ProxyScheme::Http => "http",
+            ProxyScheme::Https => "https",
+            ProxyScheme::Socks4 => "socks4",
+            ProxyScheme::Socks5 => "socks5",
+        }
+    }
+
+    pub fn default_port(self) -> u16 {
+        match self {
+            ProxyScheme::Http => 80,
+            ProxyScheme::Https => 443,
+            ProxyScheme::Socks4 | ProxyScheme::Socks5 => 1080,
+        }
+    }
+
+    pub fn is_socks(self) -> bool {
+        matches!(self, ProxyScheme::Socks4 | ProxyScheme::Socks5)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ProxyServer {
+    pub scheme: ProxyScheme,
+    pub host: String,
+    pub port: u16,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("Invalid proxy server \"{0}\", expected [SCHEME://]HOST[:PORT]")]
+pub struct ParseProxyServerError(String);
+
+impl ProxyServer {
+    pub fn new(scheme: ProxyScheme, host: &str, port: u16) -> Self {
+        Self { scheme, host: host.to_ascii_lowercase(), port }
+    }
+}
+
+// socks5://127.0.0.1:1080
+impl fmt::Display for ProxyServer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}://{}:{}", self.scheme.as_str(), self.host, self.port)
+    }
+}
+
+impl FromStr for ProxyServer {
+    type Err = ParseProxyServerError;
+
+    /// The URI form of `ProxyUriToProxyServer`: `socks5://host:1080`,
+    /// `[::1]:8080`. Without a scheme the proxy is HTTP; without a port it is
+    /// the scheme's default.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let err = || ParseProxyServerError(s.to_string());
+        let (scheme, rest) = match s.split_once("://") {
+            Some((scheme, rest)) => (
+                ProxyScheme::ALL
+                    .into_iter()
+                    .find(|p| p.as_str().eq_ignore_ascii_case(scheme))
+                    .ok_or_else(err)?,
+                rest,
+            ),
+            None => (ProxyScheme::Http, s),
+        };
+        // A bracketed IPv6 literal keeps its colons.
+        let port_separator = match rest.rfind(']') {
+            Some(end) => rest[end..].find(':').map(|i| end + i),
+            None => rest.rfind(':'),
+        };
+        let (host, port) = match port_separator {
+            Some(i) => (&rest[..i], rest[i + 1..].parse().map_err(|_| err())?),
+            None => (rest, scheme.default_port()),
+        };
+        if host.is_empty() || host.contains('/') {
+            return Err(err());
+        }
+        Ok(Self::new(scheme, host, port))
+    }
+}
+
+/// The proxies a connection is tunnelled through, outermost first. The empty
+/// chain is a direct connection.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ProxyChain {
+    servers: Vec<ProxyServer>,
+}
+
+impl ProxyChain {
+    pub fn direct() -> Self {
+        Self::default()
+    }
+
+    pub fn new(servers: Vec<ProxyServer>) -> Self {
+        Self { servers }
+    }
+
+    pub fn is_direct(&self) -> bool {
+        self.servers.is_empty()
+    }
+
+    pub fn servers(&self) -> &[ProxyServer] {
+        &self.servers
+    }
+}
+
+impl From<ProxyServer> for ProxyChain {
+    fn from(server: ProxyServer) -> Self {
+        Self::new(vec![server])
+    }
+}
+
+// `direct://`, or the servers joined as in `ProxyChain::ToDebugString`.
+impl fmt::Display for ProxyChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_direct() {
+            return f.write_str("direct://");
+        }
+        f.write_str("[")?;
+        for (i, server) in self.servers.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{server}")?;
+        }
+        f.write_str("]")
+    }
+}
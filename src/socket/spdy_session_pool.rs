@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use crate::socket::group_id::SpdySessionKey;
+
+// `SpdySessionPool` (net/spdy/spdy_session_pool.cc): the HTTP/2 sessions
+// streams may be multiplexed onto, found by the whole `SpdySessionKey`. Two
+// requests that differ only in privacy mode or network anonymization key get
+// different keys, so they never share a session.
+//
+// A session may also carry streams for another host that resolves to the
+// address it is connected to (IP pooling), but only if the two keys agree on
+// everything except host and port (`SpdySessionKey::can_alias`) and the
+// session's certificate covers the other host. The match is then remembered
+// under the other key, and goes away with the session.
+
+struct Available<S> {
+    session: S,
+    address: Option<IpAddr>,
+    // The key the session was opened for, when this entry is an alias.
+    alias_of: Option<SpdySessionKey>,
+}
+
+pub struct SpdySessionPool<S> {
+    sessions: Mutex<HashMap<SpdySessionKey, Available<S>>>,
+}
+
+impl<S: Clone> Default for SpdySessionPool<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: Clone> SpdySessionPool<S> {
+    pub fn new() -> Self {
+        Self { sessions: Mutex::new(HashMap::new()) }
+    }
+
+    // Only whole operations run under the lock, so a poisoned lock is still
+    // consistent.
+    fn sessions(&self) -> MutexGuard<'_, HashMap<SpdySessionKey, Available<S>>> {
+        self.sessions.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// `CreateAvailableSessionFromSocket`: makes `session`, connected to
+    /// `address`, available under `key`, replacing any session there.
+    pub fn insert(&self, key: SpdySessionKey, address: Option<IpAddr>, session: S) {
+        let mut sessions = self.sessions();
+        remove_with_aliases(&mut sessions, &key);
+        sessions.insert(key, Available { session, address, alias_of: None });
+    }
+
+    /// `FindAvailableSession` without IP pooling: a session opened for `key`,
+    /// or one earlier aliased to it.
+    pub fn find_available_session(&self, key: &SpdySessionKey) -> Option<S> {
+        self.sessions().get(key).map(|available| available.session.clone())
+    }
+
+    /// `FindMatchingIpSessionForServiceEndpoint`: a session connected to one
+    /// of `addresses` whose key may alias `key` and for which
+    /// `covers(session, host)` says the certificate is valid for `key`'s host.
+    /// A match is also made available under `key`.
+    pub fn find_matching_ip_session(
+        &self,
+        key: &SpdySessionKey,
+        addresses: &[IpAddr],
+        covers: impl Fn(&S, &str) -> bool,
+    ) -> Option<S> {
+        let mut sessions = self.sessions();
+        if let Some(available) = sessions.get(key) {
+            return Some(available.session.clone());
+        }
+        let (original, session) = sessions.iter().find_map(|(other, available)| {
+            let matches = available.alias_of.is_none()
+                && available.address.is_some_and(|address| addresses.contains(&address))
+                && other.can_alias(key)
+                && covers(&available.session, key.host());
+            matches.then(|| (other.clone(), available.session.clone()))
+        })?;
+        let alias = Available { session: session.clone(), address: None, alias_of: Some(original) };
+        sessions.insert(key.clone(), alias);
+        Some(session)
+    }
+
+    /// `MakeSessionUnavailable`: drops the session under `key`, with every
+    /// alias to it if it was opened for `key`.
+    pub fn remove(&self, key: &SpdySessionKey) {
+        remove_with_aliases(&mut self.sessions(), key);
+    }
+
+    /// `CloseAllSessions`.
+    pub fn close_all_sessions(&self) {
+        self.sessions().clear();
+    }
+
+    /// The number of keys a session is available under, aliases included.
+    pub fn len(&self) -> usize {
+        self.sessions().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+fn remove_with_aliases<S>(
+    sessions: &mut HashMap<SpdySessionKey, Available<S>>,
+    key: &SpdySessionKey,
+) {
+    if let Some(removed) = sessions.remove(key) {
+        if removed.alias_of.is_none() {
+            sessions.retain(|_, available| available.alias_of.as_ref() != Some(key));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    use super::*;
+    use crate::socket::group_id::{GroupId, NetworkAnonymizationKey, PrivacyMode};
+    use crate::socket::proxy_chain::ProxyChain;
+
+    const ADDRESS: IpAddr = IpAddr::V4(std::net::Ipv4Addr::new(192, 0, 2, 1));
+
+    fn group(host: &str) -> GroupId {
+        GroupId::new("https", host, 443)
+    }
+
+    fn key(group_id: GroupId) -> SpdySessionKey {
+        group_id.spdy_session_key(&ProxyChain::direct())
+    }
+
+    fn nak(top_frame: &str) -> NetworkAnonymizationKey {
+        NetworkAnonymizationKey::new(&Url::parse(top_frame).unwrap(), false).unwrap()
+    }
+
+    fn covers_all(_: &&str, _: &str) -> bool {
+        true
+    }
+
+    #[test]
+    fn sessions_are_found_only_in_their_partition() {
+        let pool = SpdySessionPool::new();
+        let tenant_a = group("example.com").with_network_anonymization_key(nak("https://a.com/"));
+        pool.insert(key(tenant_a.clone()), Some(ADDRESS), "a");
+        assert_eq!(pool.find_available_session(&key(tenant_a)), Some("a"));
+
+        let tenant_b = group("example.com").with_network_anonymization_key(nak("https://b.com/"));
+        let private = group("example.com").with_privacy_mode(PrivacyMode::Enabled);
+        for other in [tenant_b, private, group("example.com")] {
+            let other = key(other);
+            assert_eq!(pool.find_available_session(&other), None);
+            assert_eq!(pool.find_matching_ip_session(&other, &[ADDRESS], covers_all), None);
+        }
+    }
+
+    #[test]
+    fn ip_pooling_aliases_within_a_partition() {
+        let pool = SpdySessionPool::new();
+        pool.insert(key(group("www.example.com")), Some(ADDRESS), "www");
+        let cdn = key(group("cdn.example.com"));
+        assert_eq!(pool.find_matching_ip_session(&cdn, &[ADDRESS], |_, host| host == "x"), None);
+        let other_address = IpAddr::V4(std::net::Ipv4Addr::new(192, 0, 2, 2));
+        assert_eq!(pool.find_matching_ip_session(&cdn, &[other_address], covers_all), None);
+
+        assert_eq!(pool.find_matching_ip_session(&cdn, &[ADDRESS], covers_all), Some("www"));
+        assert_eq!(pool.find_available_session(&cdn), Some("www"));
+        assert_eq!(pool.len(), 2);
+
+        pool.remove(&key(group("www.example.com")));
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn aliases_do_not_cross_proxy_chains() {
+        let pool = SpdySessionPool::new();
+        let proxy = "https://proxy.test:443".parse().unwrap();
+        let proxied = group("cdn.example.com").spdy_session_key(&ProxyChain::new(vec![proxy]));
+        pool.insert(key(group("www.example.com")), Some(ADDRESS), "www");
+        assert_eq!(pool.find_matching_ip_session(&proxied, &[ADDRESS], covers_all), None);
+    }
+}
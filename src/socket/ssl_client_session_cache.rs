@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use crate::socket::group_id::SslSessionKey;
+
+// `SSLClientSessionCache` (net/ssl/ssl_client_session_cache.cc): the TLS
+// sessions a `TlsConnector` may offer for resumption. Sessions are stored and
+// looked up by the whole `SslSessionKey`, privacy mode and network
+// anonymization key included, so a session established in one partition is
+// never offered in another, even to the same host and port.
+//
+// As in Chromium each key holds its two newest sessions, a single-use session
+// (a TLS 1.3 ticket) is removed when it is handed out, and the least recently
+// used key is evicted once the cache is full. Expiry is left to the TLS
+// library, which refuses to resume a stale session.
+
+/// `SSLClientSessionCache::Config::max_entries`.
+pub const MAX_SSL_SESSION_CACHE_ENTRIES: usize = 1024;
+// `Entry::sessions`.
+const SESSIONS_PER_KEY: usize = 2;
+
+struct CachedSession<S> {
+    session: S,
+    single_use: bool,
+}
+
+struct Entry<S> {
+    // Oldest first.
+    sessions: Vec<CachedSession<S>>,
+    last_used: u64,
+}
+
+struct Cache<S> {
+    entries: HashMap<SslSessionKey, Entry<S>>,
+    clock: u64,
+}
+
+pub struct SslClientSessionCache<S> {
+    max_entries: usize,
+    cache: Mutex<Cache<S>>,
+}
+
+impl<S: Clone> Default for SslClientSessionCache<S> {
+    fn default() -> Self {
+        Self::new(MAX_SSL_SESSION_CACHE_ENTRIES)
+    }
+}
+
+impl<S: Clone> SslClientSessionCache<S> {
+    pub fn new(max_entries: usize) -> Self {
+        let cache = Cache { entries: HashMap::new(), clock: 0 };
+        Self { max_entries, cache: Mutex::new(cache) }
+    }
+
+    // Only whole operations run under the lock, so a poisoned lock is still
+    // consistent.
+    fn cache(&self) -> MutexGuard<'_, Cache<S>> {
+        self.cache.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// `Lookup`: the newest session stored under `key`. A single-use session
+    /// is removed, so it is only ever offered once.
+    pub fn lookup(&self, key: &SslSessionKey) -> Option<S> {
+        let mut cache = self.cache();
+        cache.clock += 1;
+        let clock = cache.clock;
+        let entry = cache.entries.get_mut(key)?;
+        entry.last_used = clock;
+        let newest = entry.sessions.last()?;
+        if !newest.single_use {
+            return Some(newest.session.clone());
+        }
+        let session = entry.sessions.pop().map(|cached| cached.session);
+        if entry.sessions.is_empty() {
+            cache.entries.remove(key);
+        }
+        session
+    }
+
+    /// `Insert`: stores a session the server issued under `key`, dropping the
+    /// oldest of the key's sessions, or the least recently used key, to make
+    /// room.
+    pub fn insert(&self, key: SslSessionKey, session: S, single_use: bool) {
+        if self.max_entries == 0 {
+            return;
+        }
+        let mut cache = self.cache();
+        cache.clock += 1;
+        let clock = cache.clock;
+        if !cache.entries.contains_key(&key) && cache.entries.len() >= self.max_entries {
+            let oldest = cache.entries.iter().min_by_key(|(_, e)| e.last_used).map(|(k, _)| k);
+            if let Some(oldest) = oldest.cloned() {
+                cache.entries.remove(&oldest);
+            }
+        }
+        let entry =
+            cache.entries.entry(key).or_insert(Entry { sessions: Vec::new(), last_used: 0 });
+        if entry.sessions.len() == SESSIONS_PER_KEY {
+            entry.sessions.remove(0);
+        }
+        entry.sessions.push(CachedSession { session, single_use });
+        entry.last_used = clock;
+    }
+
+    /// Drops every session under `key`, for a server that refused to resume
+    /// one.
+    pub fn remove(&self, key: &SslSessionKey) {
+        self.cache().entries.remove(key);
+    }
+
+    /// `Flush`: drops every session, for a network change or cleared state.
+    pub fn flush(&self) {
+        self.cache().entries.clear();
+    }
+
+    /// The number of keys with a stored session.
+    pub fn len(&self) -> usize {
+        self.cache().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    use super::*;
+    use crate::socket::group_id::{GroupId, NetworkAnonymizationKey, PrivacyMode};
+
+    fn key(host: &str) -> SslSessionKey {
+        GroupId::new("https", host, 443).ssl_session_key()
+    }
+
+    fn partitioned(top_frame: &str) -> SslSessionKey {
+        let top_frame = Url::parse(top_frame).unwrap();
+        let nak = NetworkAnonymizationKey::new(&top_frame, false).unwrap();
+        GroupId::new("https", "example.com", 443)
+            .with_network_anonymization_key(nak)
+            .ssl_session_key()
+    }
+
+    #[test]
+    fn sessions_stay_in_their_partition() {
+        let cache = SslClientSessionCache::default();
+        cache.insert(partitioned("https://a.com/"), "a", false);
+        assert_eq!(cache.lookup(&partitioned("https://a.com/")), Some("a"));
+        assert_eq!(cache.lookup(&partitioned("https://b.com/")), None);
+        assert_eq!(cache.lookup(&key("example.com")), None);
+
+        let private = GroupId::new("https", "example.com", 443)
+            .with_privacy_mode(PrivacyMode::Enabled)
+            .ssl_session_key();
+        cache.insert(key("example.com"), "shared", false);
+        assert_eq!(cache.lookup(&private), None);
+        assert_eq!(cache.lookup(&key("example.com")), Some("shared"));
+    }
+
+    #[test]
+    fn single_use_sessions_are_handed_out_once() {
+        let cache = SslClientSessionCache::default();
+        cache.insert(key("example.com"), 1, true);
+        cache.insert(key("example.com"), 2, true);
+        cache.insert(key("example.com"), 3, true);
+        assert_eq!(cache.lookup(&key("example.com")), Some(3));
+        assert_eq!(cache.lookup(&key("example.com")), Some(2));
+        assert_eq!(cache.lookup(&key("example.com")), None);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn least_recently_used_key_is_evicted() {
+        let cache = SslClientSessionCache::new(2);
+        cache.insert(key("a.test"), "a", false);
+        cache.insert(key("b.test"), "b", false);
+        assert_eq!(cache.lookup(&key("a.test")), Some("a"));
+        cache.insert(key("c.test"), "c", false);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.lookup(&key("b.test")), None);
+        assert_eq!(cache.lookup(&key("a.test")), Some("a"));
+
+        cache.flush();
+        assert!(cache.is_empty());
+    }
+}
@@ -0,0 +1,92 @@
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use crate::fault_injection::FaultyStream;
+
+// `StreamSocket::IsConnected` and `IsConnectedAndIdle`
+// (net/socket/stream_socket.h): what the pool asks a socket before keeping it
+// idle or handing it out again.
+//
+// A TCP socket answers by peeking one byte without blocking, as
+// `TCPSocketPosix` does: EOF means the peer closed, an error such as ECONNRESET
+// means the connection is gone, would-block means connected with nothing
+// unread. Unread bytes on an idle HTTP/1.1 socket mean the server sent
+// something nobody asked for, so the socket is not reused.
+
+pub trait StreamSocket {
+    /// The peer has not closed the connection. Unread data is allowed.
+    fn is_connected(&self) -> bool;
+
+    /// Connected, with nothing waiting to be read.
+    fn is_connected_and_idle(&self) -> bool;
+}
+
+/// A connected stream of any layering: TCP, a tunnel through proxies, TLS.
+pub trait AsyncStream: AsyncRead + AsyncWrite + StreamSocket + Send + Unpin {}
+
+impl<T: AsyncRead + AsyncWrite + StreamSocket + Send + Unpin> AsyncStream for T {}
+
+pub type BoxedStream = Box<dyn AsyncStream>;
+
+impl<S: StreamSocket + ?Sized> StreamSocket for Box<S> {
+    fn is_connected(&self) -> bool {
+        (**self).is_connected()
+    }
+
+    fn is_connected_and_idle(&self) -> bool {
+        (**self).is_connected_and_idle()
+    }
+}
+
+impl<S: StreamSocket> StreamSocket for FaultyStream<S> {
+    fn is_connected(&self) -> bool {
+        self.get_ref().is_connected()
+    }
+
+    fn is_connected_and_idle(&self) -> bool {
+        self.get_ref().is_connected_and_idle()
+    }
+}
+
+#[cfg(unix)]
+impl StreamSocket for tokio::net::TcpStream {
+    fn is_connected(&self) -> bool {
+        !matches!(peek(self), Peek::Closed)
+    }
+
+    fn is_connected_and_idle(&self) -> bool {
+        matches!(peek(self), Peek::Idle)
+    }
+}
+
+#[cfg(unix)]
+enum Peek {
+    Idle,
+    Data,
+    Closed,
+}
+
+#[cfg(unix)]
+fn peek(socket: &impl std::os::fd::AsRawFd) -> Peek {
+    let mut byte = 0u8;
+    loop {
+        // SAFETY: one byte into a live local, on a descriptor the caller
+        // borrows for the duration of the call.
+        let rv = unsafe {
+            libc::recv(
+                socket.as_raw_fd(),
+                (&mut byte as *mut u8).cast(),
+                1,
+                libc::MSG_PEEK | libc::MSG_DONTWAIT,
+            )
+        };
+        return match rv {
+            0 => Peek::Closed,
+            1.. => Peek::Data,
+            _ => match std::io::Error::last_os_error().kind() {
+                std::io::ErrorKind::Interrupted => continue,
+                std::io::ErrorKind::WouldBlock => Peek::Idle,
+                _ => Peek::Closed,
+            },
+        };
+    }
+}